@@ -0,0 +1,302 @@
+//! Collects the report from /proc and other system sources. Only Linux is supported.
+
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::net::Ipv4Addr;
+
+use crate::*;
+
+const OS_RELEASE_PATHS: [&str; 2] = ["/etc/os-release", "/usr/lib/os-release"];
+const FSTAB_PATH: &str = "/etc/fstab";
+const UTMP_PATH: &str = "/var/run/utmp";
+/// Size of `struct utmp` on Linux with glibc and musl.
+const UTMP_RECORD_SIZE: usize = 384;
+const UTMP_USER_PROCESS: i16 = 7;
+/// Pseudo filesystems that `df` also leaves out.
+const IGNORED_FS_TYPES: [&str; 20] = [
+    "autofs", "binfmt_misc", "bpf", "cgroup", "cgroup2", "configfs", "debugfs", "devpts", "fusectl", "hugetlbfs",
+    "mqueue", "nsfs", "proc", "pstore", "rpc_pipefs", "securityfs", "selinuxfs", "sysfs", "tracefs", "fuse.gvfsd-fuse",
+];
+
+
+pub fn collect_report() -> Report {
+    let mut report = Report {
+        report_version: REPORT_VERSION,
+        agent_version: env!("CARGO_PKG_VERSION").to_string(),
+        facts: collect_facts(),
+        ..Default::default()
+    };
+
+    match read_uptime() {
+        Ok(uptime) => report.uptime_seconds = Some(uptime),
+        Err(error) => report.errors.push(format!("uptime: {}", error)),
+    }
+
+    match read_load_average() {
+        Ok(load_average) => report.load_average = Some(load_average),
+        Err(error) => report.errors.push(format!("load average: {}", error)),
+    }
+
+    match read_memory() {
+        Ok(memory) => report.memory = Some(memory),
+        Err(error) => report.errors.push(format!("memory: {}", error)),
+    }
+
+    match read_filesystems() {
+        Ok(filesystems) => report.filesystems = filesystems,
+        Err(error) => report.errors.push(format!("filesystems: {}", error)),
+    }
+
+    match read_users() {
+        Ok(users) => report.users = users,
+        Err(error) => report.errors.push(format!("users: {}", error)),
+    }
+
+    match read_routes() {
+        Ok(routes) => report.routes = routes,
+        Err(error) => report.errors.push(format!("routes: {}", error)),
+    }
+
+    report
+}
+
+fn collect_facts() -> Facts {
+    let mut facts = Facts::default();
+
+    if let Some(os_release) = OS_RELEASE_PATHS.iter().find_map(|path| fs::read_to_string(path).ok()) {
+        for line in os_release.lines() {
+            if let Some((key, value)) = line.split_once('=') {
+                let value = value.trim_matches('"').trim_matches('\'').to_string();
+                match key {
+                    "ID" => facts.os_id = value,
+                    "VERSION_ID" => facts.os_version = value,
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    if let Some((hostname, kernel, architecture)) = uname() {
+        facts.hostname = hostname;
+        facts.kernel = kernel;
+        facts.architecture = architecture;
+    }
+
+    facts
+}
+
+fn uname() -> Option<(String, String, String)> {
+    let mut uts_name: libc::utsname = unsafe { std::mem::zeroed() };
+    if unsafe { libc::uname(&mut uts_name) } != 0 {
+        return None;
+    }
+
+    let to_string = |field: &[libc::c_char]| unsafe { CStr::from_ptr(field.as_ptr()) }.to_string_lossy().to_string();
+    Some((to_string(&uts_name.nodename), to_string(&uts_name.release), to_string(&uts_name.machine)))
+}
+
+fn read_uptime() -> Result<u64, String> {
+    let contents = fs::read_to_string("/proc/uptime").map_err(|error| error.to_string())?;
+    let seconds = contents.split_whitespace().next().unwrap_or_default();
+    seconds.parse::<f64>().map(|seconds| seconds as u64).map_err(|error| error.to_string())
+}
+
+fn read_load_average() -> Result<LoadAverage, String> {
+    let contents = fs::read_to_string("/proc/loadavg").map_err(|error| error.to_string())?;
+    let values = contents.split_whitespace()
+                         .take(3)
+                         .map(|value| value.parse::<f64>().map_err(|error| error.to_string()))
+                         .collect::<Result<Vec<_>, _>>()?;
+
+    if values.len() != 3 {
+        return Err(String::from("Invalid /proc/loadavg"));
+    }
+
+    Ok(LoadAverage { one: values[0], five: values[1], fifteen: values[2] })
+}
+
+fn read_memory() -> Result<Memory, String> {
+    let contents = fs::read_to_string("/proc/meminfo").map_err(|error| error.to_string())?;
+    let mut memory = Memory::default();
+
+    for line in contents.lines() {
+        let mut parts = line.split_whitespace();
+        let key = parts.next().unwrap_or_default();
+        let value = parts.next().unwrap_or_default().parse::<u64>().unwrap_or_default();
+        match key {
+            "MemTotal:" => memory.total_kb = value,
+            "MemAvailable:" => memory.available_kb = value,
+            _ => (),
+        }
+    }
+
+    if memory.total_kb == 0 {
+        return Err(String::from("MemTotal missing from /proc/meminfo"));
+    }
+    Ok(memory)
+}
+
+// Field types of statvfs differ between architectures.
+#[allow(clippy::unnecessary_cast)]
+fn read_filesystems() -> Result<Vec<Filesystem>, String> {
+    let contents = fs::read_to_string("/proc/self/mounts").map_err(|error| error.to_string())?;
+    let read_only_in_fstab = read_fstab_read_only_mountpoints();
+    let mut filesystems: Vec<Filesystem> = Vec::new();
+
+    for line in contents.lines() {
+        let parts = line.split_whitespace().collect::<Vec<_>>();
+        if parts.len() < 3 {
+            continue;
+        }
+
+        let source = unescape_mount_field(parts[0]);
+        let mountpoint = unescape_mount_field(parts[1]);
+        let fs_type = parts[2].to_string();
+        let read_only = parts.get(3).map(|options| options.split(',').any(|option| option == "ro")).unwrap_or(false);
+
+        if IGNORED_FS_TYPES.contains(&fs_type.as_str()) {
+            continue;
+        }
+
+        // Same mountpoint can appear multiple times if something was mounted on top. Only the last one is visible.
+        filesystems.retain(|filesystem| filesystem.mountpoint != mountpoint);
+
+        let stats = match statvfs(&mountpoint) {
+            Some(stats) => stats,
+            // Usually caused by missing permissions. `df` skips these too.
+            None => continue,
+        };
+
+        let block_size = stats.f_frsize as u64;
+        let size_bytes = stats.f_blocks as u64 * block_size;
+        if size_bytes == 0 {
+            continue;
+        }
+
+        filesystems.push(Filesystem {
+            read_only_in_fstab: read_only_in_fstab.contains(&mountpoint),
+            source,
+            fs_type,
+            mountpoint,
+            size_bytes,
+            used_bytes: (stats.f_blocks as u64 - stats.f_bfree as u64) * block_size,
+            available_bytes: stats.f_bavail as u64 * block_size,
+            inodes_total: stats.f_files as u64,
+            inodes_free: stats.f_ffree as u64,
+            read_only,
+        });
+    }
+
+    Ok(filesystems)
+}
+
+/// Returns mountpoints that have the "ro" option in /etc/fstab. Missing fstab is not an error.
+fn read_fstab_read_only_mountpoints() -> Vec<String> {
+    let contents = fs::read_to_string(FSTAB_PATH).unwrap_or_default();
+    contents.lines()
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .filter_map(|line| {
+                let parts = line.split_whitespace().collect::<Vec<_>>();
+                match parts.as_slice() {
+                    [_, mountpoint, _, options, ..] if options.split(',').any(|option| option == "ro") => Some(unescape_mount_field(mountpoint)),
+                    _ => None,
+                }
+            })
+            .collect()
+}
+
+fn statvfs(path: &str) -> Option<libc::statvfs> {
+    let path = CString::new(path).ok()?;
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stats) } == 0 {
+        Some(stats)
+    }
+    else {
+        None
+    }
+}
+
+/// Spaces and some other characters are octal-escaped in /proc/self/mounts, e.g. "\040".
+pub fn unescape_mount_field(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        if bytes[index] == b'\\' && index + 3 < bytes.len() && bytes[index + 1..index + 4].iter().all(|byte| (b'0'..=b'7').contains(byte)) {
+            let octal = std::str::from_utf8(&bytes[index + 1..index + 4]).unwrap_or_default();
+            if let Ok(value) = u8::from_str_radix(octal, 8) {
+                result.push(value);
+                index += 4;
+                continue;
+            }
+        }
+        result.push(bytes[index]);
+        index += 1;
+    }
+
+    String::from_utf8_lossy(&result).to_string()
+}
+
+fn read_users() -> Result<Vec<UserSession>, String> {
+    let contents = match fs::read(UTMP_PATH) {
+        Ok(contents) => contents,
+        // File is missing on some minimal systems when nobody has logged in.
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error.to_string()),
+    };
+    let mut users = Vec::new();
+
+    for record in contents.chunks_exact(UTMP_RECORD_SIZE) {
+        let record_type = i16::from_ne_bytes([record[0], record[1]]);
+        if record_type != UTMP_USER_PROCESS {
+            continue;
+        }
+
+        users.push(UserSession {
+            user: read_c_string(&record[44..76]),
+            terminal: read_c_string(&record[8..40]),
+            host: read_c_string(&record[76..332]),
+            login_time: i32::from_ne_bytes([record[340], record[341], record[342], record[343]]) as i64,
+        });
+    }
+
+    Ok(users)
+}
+
+fn read_c_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|byte| *byte == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).to_string()
+}
+
+fn read_routes() -> Result<Vec<Route>, String> {
+    let contents = fs::read_to_string("/proc/net/route").map_err(|error| error.to_string())?;
+    let mut routes = Vec::new();
+
+    // First line contains headers.
+    for line in contents.lines().skip(1) {
+        let parts = line.split_whitespace().collect::<Vec<_>>();
+        if parts.len() < 8 {
+            continue;
+        }
+
+        let destination = parse_route_address(parts[1])?;
+        let gateway = parse_route_address(parts[2])?;
+        let mask = parse_route_address(parts[7])?;
+        let prefix_length = u32::from(mask).count_ones();
+
+        routes.push(Route {
+            destination: if prefix_length == 0 { String::from("default") } else { format!("{}/{}", destination, prefix_length) },
+            gateway: if gateway.is_unspecified() { None } else { Some(gateway.to_string()) },
+            interface: parts[0].to_string(),
+        });
+    }
+
+    Ok(routes)
+}
+
+/// Addresses in /proc/net/route are hexadecimal in host byte order.
+fn parse_route_address(hex: &str) -> Result<Ipv4Addr, String> {
+    let value = u32::from_str_radix(hex, 16).map_err(|error| error.to_string())?;
+    Ok(Ipv4Addr::from(value.to_ne_bytes()))
+}
@@ -0,0 +1,131 @@
+//! Report format of `lightkeeper-agent`, a small binary that can be deployed on monitored hosts
+//! to collect host facts and metrics in a single execution instead of running multiple shell commands.
+//!
+//! The agent prints a [`Report`] as JSON. Lightkeeper uses the same types to parse it, so the format
+//! is versioned with [`REPORT_VERSION`] and Lightkeeper ignores reports with a version it doesn't know.
+//! Adding new optional fields doesn't require a version bump, removing or changing existing ones does.
+
+pub mod collect;
+
+use serde_derive::{Deserialize, Serialize};
+
+/// Increased on every incompatible change to the report format.
+pub const REPORT_VERSION: u32 = 1;
+
+/// Command line flag that prints only the report version. Used to detect the agent on a host.
+pub const REPORT_VERSION_FLAG: &str = "--report-version";
+
+pub const AGENT_BINARY_NAME: &str = "lightkeeper-agent";
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Report {
+    pub report_version: u32,
+    pub agent_version: String,
+    pub facts: Facts,
+    /// Metrics that couldn't be collected are left empty and the reason is added to `errors`.
+    #[serde(default)]
+    pub uptime_seconds: Option<u64>,
+    #[serde(default)]
+    pub load_average: Option<LoadAverage>,
+    #[serde(default)]
+    pub memory: Option<Memory>,
+    #[serde(default)]
+    pub filesystems: Vec<Filesystem>,
+    #[serde(default)]
+    pub users: Vec<UserSession>,
+    #[serde(default)]
+    pub routes: Vec<Route>,
+    #[serde(default)]
+    pub errors: Vec<String>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Facts {
+    pub hostname: String,
+    /// `ID` from os-release, e.g. "debian".
+    pub os_id: String,
+    /// `VERSION_ID` from os-release, e.g. "12".
+    pub os_version: String,
+    /// Same as `uname -m`.
+    pub architecture: String,
+    /// Same as `uname -r`.
+    pub kernel: String,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct LoadAverage {
+    pub one: f64,
+    pub five: f64,
+    pub fifteen: f64,
+}
+
+/// Values are in kibibytes, same as in /proc/meminfo.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Memory {
+    pub total_kb: u64,
+    pub available_kb: u64,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Filesystem {
+    pub source: String,
+    pub fs_type: String,
+    pub mountpoint: String,
+    pub size_bytes: u64,
+    pub used_bytes: u64,
+    pub available_bytes: u64,
+    /// Zero if the filesystem has no fixed inode count, e.g. btrfs.
+    #[serde(default)]
+    pub inodes_total: u64,
+    #[serde(default)]
+    pub inodes_free: u64,
+    #[serde(default)]
+    pub read_only: bool,
+    /// Mounted read-only on purpose according to /etc/fstab.
+    #[serde(default)]
+    pub read_only_in_fstab: bool,
+}
+
+impl Filesystem {
+    /// Calculated the same way as in `df`: relative to the space available to non-root users.
+    pub fn used_percent(&self) -> f64 {
+        let usable = self.used_bytes + self.available_bytes;
+        if usable == 0 {
+            0.0
+        }
+        else {
+            (self.used_bytes as f64 * 100.0 / usable as f64).ceil()
+        }
+    }
+
+    /// Same as IUse% in `df -i`. None if the filesystem has no fixed inode count.
+    pub fn inodes_used_percent(&self) -> Option<f64> {
+        if self.inodes_total == 0 {
+            None
+        }
+        else {
+            let inodes_used = self.inodes_total.saturating_sub(self.inodes_free);
+            Some((inodes_used as f64 * 100.0 / self.inodes_total as f64).ceil())
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct UserSession {
+    pub user: String,
+    pub terminal: String,
+    /// Remote host or empty for local sessions.
+    pub host: String,
+    /// Unix timestamp.
+    pub login_time: i64,
+}
+
+/// IPv4 route, corresponds to a line in `ip route ls`.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Route {
+    /// "default" or subnet in CIDR notation.
+    pub destination: String,
+    #[serde(default)]
+    pub gateway: Option<String>,
+    pub interface: String,
+}
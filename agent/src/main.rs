@@ -0,0 +1,37 @@
+use std::process::ExitCode;
+
+use lightkeeper_agent::{collect, REPORT_VERSION, REPORT_VERSION_FLAG};
+
+
+fn main() -> ExitCode {
+    let arguments = std::env::args().skip(1).collect::<Vec<_>>();
+
+    match arguments.first().map(String::as_str) {
+        None => {
+            let report = collect::collect_report();
+            match serde_json::to_string(&report) {
+                Ok(json) => {
+                    println!("{}", json);
+                    ExitCode::SUCCESS
+                },
+                Err(error) => {
+                    eprintln!("Failed to serialize report: {}", error);
+                    ExitCode::FAILURE
+                }
+            }
+        },
+        Some(REPORT_VERSION_FLAG) => {
+            println!("{}", REPORT_VERSION);
+            ExitCode::SUCCESS
+        },
+        Some("--version") => {
+            println!("{}", env!("CARGO_PKG_VERSION"));
+            ExitCode::SUCCESS
+        },
+        Some(argument) => {
+            eprintln!("Unknown argument: {}", argument);
+            eprintln!("Usage: lightkeeper-agent [{} | --version]", REPORT_VERSION_FLAG);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -9,6 +9,7 @@ use syn;
 //     name="name",
 //     version="1.0",
 //     description="description",
+//     cache_sensitivity="Sensitive",
 //     settings={
 //         parameter1_key => "parameter1_description",
 //         parameter2_key => "parameter2_description"
@@ -21,6 +22,7 @@ struct ModuleArgs {
     parent_module_name: Option<String>,
     parent_module_version: Option<String>,
     cache_scope: String,
+    cache_sensitivity: String,
     settings: HashMap<String, String>,
 }
 
@@ -32,6 +34,7 @@ impl syn::parse::Parse for ModuleArgs {
         let mut parent_module_name = None;
         let mut parent_module_version = None;
         let mut cache_scope = String::from("Host");
+        let mut cache_sensitivity = String::from("Normal");
         let mut settings = HashMap::new();
 
         while !input.is_empty() {
@@ -56,6 +59,9 @@ impl syn::parse::Parse for ModuleArgs {
                 "cache_scope" => {
                     cache_scope = input.parse::<syn::LitStr>()?.value();
                 }
+                "cache_sensitivity" => {
+                    cache_sensitivity = input.parse::<syn::LitStr>()?.value();
+                }
                 "settings" => {
                     let content;
                     syn::braced!(content in input);
@@ -84,6 +90,7 @@ impl syn::parse::Parse for ModuleArgs {
             parent_module_name: parent_module_name,
             parent_module_version: parent_module_version,
             cache_scope: cache_scope,
+            cache_sensitivity: cache_sensitivity,
             settings: settings,
         })
     }
@@ -96,6 +103,7 @@ pub fn monitoring_module(args: TokenStream, input: TokenStream) -> TokenStream {
     let module_name = args_parsed.name;
     let module_version = args_parsed.version;
     let module_description = args_parsed.description;
+    let cache_sensitivity = args_parsed.cache_sensitivity;
     let settings = args_parsed.settings.iter().map(|(key, value)| {
         quote! {
             (#key.to_string(), #value.to_string())
@@ -123,6 +131,7 @@ pub fn monitoring_module(args: TokenStream, input: TokenStream) -> TokenStream {
                         parent_module: None,
                         is_stateless: true,
                         cache_scope: crate::cache::CacheScope::Host,
+                        cache_sensitivity: #cache_sensitivity.parse::<crate::cache::CacheSensitivity>().unwrap(),
                     }
                 }
 
@@ -154,6 +163,7 @@ pub fn monitoring_extension_module(args: TokenStream, input: TokenStream) -> Tok
     let module_name = args_parsed.name;
     let module_version = args_parsed.version;
     let module_description = args_parsed.description;
+    let cache_sensitivity = args_parsed.cache_sensitivity;
     let parent_module_name = args_parsed.parent_module_name;
     let parent_module_version = args_parsed.parent_module_version;
     let settings = args_parsed.settings.iter().map(|(key, value)| {
@@ -183,6 +193,7 @@ pub fn monitoring_extension_module(args: TokenStream, input: TokenStream) -> Tok
                         parent_module: Some(ModuleSpecification::new_with_type(#parent_module_name, #parent_module_version, "monitor")),
                         is_stateless: true,
                         cache_scope: crate::cache::CacheScope::Host,
+                        cache_sensitivity: #cache_sensitivity.parse::<crate::cache::CacheSensitivity>().unwrap(),
                     }
                 }
 
@@ -213,6 +224,7 @@ pub fn command_module(args: TokenStream, input: TokenStream) -> TokenStream {
     let module_name = args_parsed.name;
     let module_version = args_parsed.version;
     let module_description = args_parsed.description;
+    let cache_sensitivity = args_parsed.cache_sensitivity;
     let settings = args_parsed.settings.iter().map(|(key, value)| {
         quote! {
             (#key.to_string(), #value.to_string())
@@ -238,6 +250,7 @@ pub fn command_module(args: TokenStream, input: TokenStream) -> TokenStream {
                     parent_module: None,
                     is_stateless: true,
                     cache_scope: crate::cache::CacheScope::Host,
+                    cache_sensitivity: #cache_sensitivity.parse::<crate::cache::CacheSensitivity>().unwrap(),
                 }
             }
 
@@ -265,6 +278,7 @@ pub fn connection_module(args: TokenStream, input: TokenStream) -> TokenStream {
     let module_name = args_parsed.name;
     let module_version = args_parsed.version;
     let module_description = args_parsed.description;
+    let cache_sensitivity = args_parsed.cache_sensitivity;
     let settings = args_parsed.settings.iter().map(|(key, value)| {
         quote! {
             (#key.to_string(), #value.to_string())
@@ -290,6 +304,7 @@ pub fn connection_module(args: TokenStream, input: TokenStream) -> TokenStream {
                     parent_module: None,
                     is_stateless: false,
                     cache_scope: crate::cache::CacheScope::Host,
+                    cache_sensitivity: #cache_sensitivity.parse::<crate::cache::CacheSensitivity>().unwrap(),
                 }
             }
 
@@ -311,6 +326,7 @@ pub fn stateless_connection_module(args: TokenStream, input: TokenStream) -> Tok
     let module_name = args_parsed.name;
     let module_version = args_parsed.version;
     let module_description = args_parsed.description;
+    let cache_sensitivity = args_parsed.cache_sensitivity;
     let cache_scope = args_parsed.cache_scope;
     let settings = args_parsed.settings.iter().map(|(key, value)| {
         quote! {
@@ -338,6 +354,7 @@ pub fn stateless_connection_module(args: TokenStream, input: TokenStream) -> Tok
                         parent_module: None,
                         is_stateless: true,
                         cache_scope: #cache_scope.parse::<crate::cache::CacheScope>().unwrap(),
+                        cache_sensitivity: #cache_sensitivity.parse::<crate::cache::CacheSensitivity>().unwrap(),
                     }
                 }
 
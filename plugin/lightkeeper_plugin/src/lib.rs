@@ -0,0 +1,231 @@
+//! C ABI for native Lightkeeper module plugins and a safe wrapper for loading them.
+//!
+//! A plugin is a `cdylib` that exports `lightkeeper_plugin_register` (see [`RegisterFunction`]).
+//! Complex values (settings, hosts, data points, responses etc.) are passed as JSON strings so that the ABI stays small
+//! and doesn't depend on the memory layout of Rust types, which isn't stable between compiler versions.
+//!
+//! Hooks are called with [`PluginRegistration::call`] and return `{"ok": <value>}` or `{"error": "<message>"}`.
+//! An empty error message means that the hook isn't implemented, same as with compiled modules.
+//!
+//! Module instances can be called from multiple threads at the same time, so plugins have to make them thread-safe.
+
+use std::collections::HashMap;
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Increased on every incompatible change to the ABI or to the JSON messages.
+pub const ABI_VERSION: u32 = 1;
+
+/// Name of the registration function plugins have to export.
+pub const REGISTER_FUNCTION_NAME: &str = "lightkeeper_plugin_register";
+
+/// Receives the host's ABI version. Plugins that don't support it should return null.
+/// Returned pointer has to stay valid until the library is unloaded.
+pub type RegisterFunction = unsafe extern "C" fn(host_abi_version: u32) -> *const PluginRegistration;
+
+#[repr(C)]
+pub struct PluginRegistration {
+    /// ABI version the plugin was built against. Has to equal the host's ABI_VERSION.
+    pub abi_version: u32,
+    /// JSON-serialized [`PluginManifest`]. Owned by the plugin.
+    pub manifest: *const c_char,
+    /// Creates a module instance with JSON-serialized settings. Returns null on error.
+    pub create_module: unsafe extern "C" fn(module_type: *const c_char, module_id: *const c_char, settings: *const c_char) -> *mut c_void,
+    pub destroy_module: unsafe extern "C" fn(instance: *mut c_void),
+    /// Calls a hook of an instance with JSON-serialized arguments. Returned string is freed with `free_string`.
+    pub call: unsafe extern "C" fn(instance: *mut c_void, hook: *const c_char, arguments: *const c_char) -> *mut c_char,
+    pub free_string: unsafe extern "C" fn(string: *mut c_char),
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PluginManifest {
+    pub name: String,
+    pub version: String,
+    pub modules: Vec<ModuleManifest>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ModuleManifest {
+    /// "monitor", "command" or "connector".
+    pub module_type: String,
+    pub id: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    /// Setting names and descriptions.
+    #[serde(default)]
+    pub settings: HashMap<String, String>,
+    /// Connector used by monitors and commands, e.g. "ssh". None if not needed.
+    #[serde(default)]
+    pub connector: Option<String>,
+    /// Connectors only. Stateful connectors keep a persistent connection and handle one request at a time.
+    #[serde(default)]
+    pub stateful: bool,
+}
+
+
+/// Loaded plugin library. Unloaded when the last module instance is dropped.
+pub struct PluginLibrary {
+    pub path: PathBuf,
+    pub manifest: PluginManifest,
+    create_module: unsafe extern "C" fn(*const c_char, *const c_char, *const c_char) -> *mut c_void,
+    destroy_module: unsafe extern "C" fn(*mut c_void),
+    call: unsafe extern "C" fn(*mut c_void, *const c_char, *const c_char) -> *mut c_char,
+    free_string: unsafe extern "C" fn(*mut c_char),
+    // Has to be dropped last, since the function pointers point to the library.
+    _library: libloading::Library,
+}
+
+impl PluginLibrary {
+    /// Loads the library and checks that it's compatible. Errors are meant to be shown to the user as is.
+    pub fn load(path: &Path) -> Result<Arc<Self>, String> {
+        // SAFETY: loading a library runs its initialization routines. Plugins are trusted the same way as the application itself.
+        let library = unsafe { libloading::Library::new(path) }
+            .map_err(|error| format!("Failed to load library: {}", error))?;
+
+        // SAFETY: the symbol's type is defined by this ABI.
+        let register = unsafe { library.get::<RegisterFunction>(REGISTER_FUNCTION_NAME.as_bytes()) }
+            .map(|symbol| *symbol)
+            .map_err(|_| format!("Not a Lightkeeper plugin: function {} not found", REGISTER_FUNCTION_NAME))?;
+
+        // SAFETY: register follows the ABI, so the returned pointer is either null or valid while the library is loaded.
+        let registration = unsafe { register(ABI_VERSION).as_ref() }
+            .ok_or(format!("Plugin refused to register. It doesn't support ABI version {}", ABI_VERSION))?;
+
+        if registration.abi_version != ABI_VERSION {
+            return Err(format!(
+                "Incompatible plugin: built for ABI version {}, but this version of Lightkeeper supports only version {}. Rebuild the plugin against lightkeeper_plugin with ABI version {}",
+                registration.abi_version, ABI_VERSION, ABI_VERSION
+            ));
+        }
+
+        if registration.manifest.is_null() {
+            return Err(String::from("Plugin manifest is missing"));
+        }
+
+        // SAFETY: checked for null above and owned by the plugin.
+        let manifest_json = unsafe { CStr::from_ptr(registration.manifest) }.to_string_lossy().to_string();
+        let manifest = serde_json::from_str::<PluginManifest>(&manifest_json)
+            .map_err(|error| format!("Invalid plugin manifest: {}", error))?;
+
+        Ok(Arc::new(PluginLibrary {
+            path: path.to_path_buf(),
+            manifest,
+            create_module: registration.create_module,
+            destroy_module: registration.destroy_module,
+            call: registration.call,
+            free_string: registration.free_string,
+            _library: library,
+        }))
+    }
+
+    pub fn create_module(self: &Arc<Self>, module_type: &str, module_id: &str, settings: &HashMap<String, String>) -> Result<ModuleInstance, String> {
+        let module_type = to_c_string(module_type)?;
+        let module_id = to_c_string(module_id)?;
+        let settings = to_c_string(&serde_json::to_string(settings).map_err(|error| error.to_string())?)?;
+
+        // SAFETY: arguments are valid null-terminated strings that outlive the call.
+        let handle = unsafe { (self.create_module)(module_type.as_ptr(), module_id.as_ptr(), settings.as_ptr()) };
+        if handle.is_null() {
+            return Err(format!("Plugin failed to create module {}", module_id.to_string_lossy()));
+        }
+
+        Ok(ModuleInstance {
+            library: self.clone(),
+            handle,
+        })
+    }
+}
+
+
+/// Module instance created by a plugin. Destroyed when dropped.
+pub struct ModuleInstance {
+    library: Arc<PluginLibrary>,
+    handle: *mut c_void,
+}
+
+// SAFETY: the ABI requires instances to be thread-safe.
+unsafe impl Send for ModuleInstance {}
+unsafe impl Sync for ModuleInstance {}
+
+impl ModuleInstance {
+    /// Returns the hook's result value. An empty error means that the hook isn't implemented.
+    pub fn call(&self, hook: &str, arguments: &Value) -> Result<Value, String> {
+        let hook_c = to_c_string(hook)?;
+        let arguments = to_c_string(&arguments.to_string())?;
+
+        // SAFETY: handle is valid until drop and arguments are valid null-terminated strings.
+        let result_pointer = unsafe { (self.library.call)(self.handle, hook_c.as_ptr(), arguments.as_ptr()) };
+        if result_pointer.is_null() {
+            return Err(format!("Plugin returned no result for {}", hook));
+        }
+
+        // SAFETY: checked for null above. String is copied before it's freed by the plugin.
+        let result_json = unsafe { CStr::from_ptr(result_pointer) }.to_string_lossy().to_string();
+        unsafe { (self.library.free_string)(result_pointer) };
+
+        let mut result = serde_json::from_str::<Value>(&result_json)
+            .map_err(|error| format!("Invalid result from plugin for {}: {}", hook, error))?;
+
+        if let Some(error) = result.get("error") {
+            return Err(error.as_str().unwrap_or_default().to_string());
+        }
+
+        result.get_mut("ok").map(Value::take).ok_or(format!("Invalid result from plugin for {}: missing \"ok\" or \"error\"", hook))
+    }
+}
+
+impl Drop for ModuleInstance {
+    fn drop(&mut self) {
+        // SAFETY: handle was created by this library and is destroyed only once.
+        unsafe { (self.library.destroy_module)(self.handle) };
+    }
+}
+
+fn to_c_string(value: &str) -> Result<CString, String> {
+    CString::new(value).map_err(|_| format!("String contains a null byte: {}", value))
+}
+
+
+/// Helpers for implementing plugins.
+pub mod guest {
+    use super::*;
+
+    /// Transfers ownership of the string to the caller. Has to be freed with [`free_string`].
+    pub fn into_c_string(value: String) -> *mut c_char {
+        CString::new(value).unwrap_or_default().into_raw()
+    }
+
+    /// Returns an empty string for null pointers.
+    ///
+    /// # Safety
+    /// Pointer has to be null or point to a valid null-terminated string.
+    pub unsafe fn from_c_str(value: *const c_char) -> String {
+        match value.is_null() {
+            true => String::new(),
+            false => CStr::from_ptr(value).to_string_lossy().to_string(),
+        }
+    }
+
+    pub fn ok(value: Value) -> *mut c_char {
+        into_c_string(serde_json::json!({ "ok": value }).to_string())
+    }
+
+    pub fn error(message: &str) -> *mut c_char {
+        into_c_string(serde_json::json!({ "error": message }).to_string())
+    }
+
+    /// Can be used directly as `PluginRegistration::free_string`.
+    ///
+    /// # Safety
+    /// String has to be created with [`into_c_string`].
+    pub unsafe extern "C" fn free_string(value: *mut c_char) {
+        if !value.is_null() {
+            drop(CString::from_raw(value));
+        }
+    }
+}
@@ -1,15 +1,18 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::os::unix::prelude::PermissionsExt;
 use std::path::Path;
 use std::time::{SystemTime, Duration, Instant};
 use std::fs;
 
 use serde_derive::{Serialize, Deserialize};
-use strum_macros::EnumString;
+use strum_macros::{Display, EnumString};
 
 use crate::file_handler;
 
-const CACHE_FILE_NAME: &str = "cache.yml";
+pub mod encryption;
+pub mod keyring;
+
+const CACHE_FILE_BASE_NAME: &str = "cache";
 
 
 // Simple cache implementation.
@@ -18,7 +21,12 @@ const CACHE_FILE_NAME: &str = "cache.yml";
 pub struct Cache<K, V> {
     time_to_live: Duration,
     final_time_to_live: Duration,
+    /// Least recently used entry is evicted when inserting a new one would exceed this. 0 means no limit.
+    max_entries: usize,
+    file_options: CacheFileOptions,
     data: HashMap<K, CacheEntry<V>>,
+    /// Hits and misses by source module. Not persisted.
+    lookups: HashMap<String, LookupStatistics>,
 }
 
 impl <K: Eq + std::hash::Hash + Clone, V: Clone> Cache<K, V> {
@@ -26,12 +34,26 @@ impl <K: Eq + std::hash::Hash + Clone, V: Clone> Cache<K, V> {
         Self {
             time_to_live: Duration::from_secs(time_to_live),
             final_time_to_live: Duration::from_secs(final_time_to_live),
+            max_entries: 0,
+            file_options: CacheFileOptions::default(),
             data: HashMap::new(),
+            lookups: HashMap::new(),
         }
     }
 
-    pub fn get(&mut self, key: &K) -> Option<V> {
-        let entry = self.data.get(key)?.clone();
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    pub fn with_file_options(mut self, file_options: CacheFileOptions) -> Self {
+        self.file_options = file_options;
+        self
+    }
+
+    /// Returns the value and how long ago it was inserted. Expiration is based on the last access.
+    pub fn get_with_age(&mut self, key: &K) -> Option<(V, Duration)> {
+        let entry = self.data.get_mut(key)?;
 
         if entry.last_access.elapsed() > self.final_time_to_live {
             self.data.remove(key);
@@ -42,23 +64,103 @@ impl <K: Eq + std::hash::Hash + Clone, V: Clone> Cache<K, V> {
         }
         else {
             // Update last access time.
-            self.data.insert(key.clone(), CacheEntry {
-                value: entry.value.clone(),
-                last_access: Instant::now(),
-            });
-            Some(entry.value)
+            entry.last_access = Instant::now();
+            Some((entry.value.clone(), entry.created.elapsed()))
+        }
+    }
+
+    /// Same as `get_with_age`, but also counts a hit or miss for the source module.
+    pub fn lookup(&mut self, key: &K, source_id: &str) -> Option<(V, Duration)> {
+        let result = self.get_with_age(key);
+        let lookups = self.lookups.entry(source_id.to_string()).or_default();
+        match result {
+            Some(_) => lookups.hits += 1,
+            None => lookups.misses += 1,
         }
+        result
+    }
+
+    /// `source_id` is the module the value belongs to. Used for statistics and purging.
+    pub fn insert(&mut self, key: K, value: V, source_id: &str) {
+        self.insert_entry(key, value, source_id, false);
+    }
+
+    /// Entry is never written to disk.
+    pub fn insert_memory_only(&mut self, key: K, value: V, source_id: &str) {
+        self.insert_entry(key, value, source_id, true);
     }
 
-    pub fn insert(&mut self, key: K, value: V) {
-        self.data.insert(key.clone(), CacheEntry {
-            value: value.clone(),
+    fn insert_entry(&mut self, key: K, value: V, source_id: &str, memory_only: bool) {
+        if self.max_entries > 0 && !self.data.contains_key(&key) {
+            while self.data.len() >= self.max_entries {
+                self.evict_least_recently_used();
+            }
+        }
+
+        self.data.insert(key, CacheEntry {
+            value: value,
+            source_id: source_id.to_string(),
+            created: Instant::now(),
             last_access: Instant::now(),
+            memory_only: memory_only,
+        });
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        let oldest_key = self.data.iter()
+                                  .min_by_key(|(_, entry)| entry.last_access)
+                                  .map(|(key, _)| key.clone());
+
+        if let Some(oldest_key) = oldest_key {
+            self.data.remove(&oldest_key);
+        }
+    }
+
+    /// Returns true if the key was found.
+    pub fn remove(&mut self, key: &K) -> bool {
+        self.data.remove(key).is_some()
+    }
+
+    /// Removes entries matching the filter. Returns the number of removed entries.
+    pub fn purge_matching(&mut self, filter: &PurgeFilter) -> usize where K: AsRef<str> {
+        let count_before = self.data.len();
+        self.data.retain(|key, entry| {
+            match filter {
+                PurgeFilter::KeyPrefix(prefix) => !key.as_ref().starts_with(prefix.as_str()),
+                PurgeFilter::Source(source_id) => &entry.source_id != source_id,
+            }
         });
+        count_before - self.data.len()
+    }
+
+    pub fn get_statistics(&self) -> CacheStatistics {
+        let mut statistics = CacheStatistics {
+            entry_count: self.data.len(),
+            memory_only_count: self.data.values().filter(|entry| entry.memory_only).count(),
+            oldest_entry_age: self.data.values().map(|entry| entry.created.elapsed().as_secs()).max(),
+            ..Default::default()
+        };
+
+        for entry in self.data.values() {
+            let module = statistics.modules.entry(entry.source_id.clone()).or_default();
+            let age = entry.created.elapsed().as_secs();
+            module.entry_count += 1;
+            module.oldest_entry_age = Some(module.oldest_entry_age.map_or(age, |oldest| oldest.max(age)));
+            module.newest_entry_age = Some(module.newest_entry_age.map_or(age, |newest| newest.min(age)));
+        }
+
+        for (source_id, lookups) in self.lookups.iter() {
+            let module = statistics.modules.entry(source_id.clone()).or_default();
+            module.hits = lookups.hits;
+            module.misses = lookups.misses;
+        }
+
+        statistics
     }
 
     pub fn purge(&mut self) where K: serde::Serialize, V: serde::Serialize {
         self.data.clear();
+        self.lookups.clear();
         self.write_to_disk().unwrap();
 
         // TODO: recovery handler and dialog if cached text editor files are found on start?
@@ -67,9 +169,9 @@ impl <K: Eq + std::hash::Hash + Clone, V: Clone> Cache<K, V> {
         }
     }
 
-    /// Writes cache contents to disk in YAML format so they can be loaded on application start.
+    /// Writes cache contents to disk so they can be loaded on application start.
+    /// Memory-only entries are left out, as are the least recently used entries if the file would exceed the size limit.
     pub fn write_to_disk(&self) -> Result<usize, String> where K: serde::Serialize, V: serde::Serialize {
-        // TODO: don't write sensitive information. Modules should define if they can contain sensitive info.
         let cache_dir = file_handler::get_cache_dir().map_err(|error| error.to_string())?;
 
         if !Path::new(&cache_dir).exists() {
@@ -81,31 +183,152 @@ impl <K: Eq + std::hash::Hash + Clone, V: Clone> Cache<K, V> {
             return Err(format!("Error while setting cache directory permissions: {}", error));
         }
 
-        let file_path = cache_dir.join(CACHE_FILE_NAME);
-        let serialized = serde_yaml::to_string(&self.data).unwrap();
-        fs::write(file_path, serialized).map_err(|error| format!("Error while writing cache to disk: {}", error))?;
-        Ok(self.data.len())
+        // Files in other formats are removed so that e.g. an unencrypted file doesn't stay around after enabling encryption.
+        let file_name = self.file_options.get_file_name();
+        for other_file_name in CacheFileOptions::all_file_names() {
+            let other_file_path = cache_dir.join(&other_file_name);
+            if Some(&other_file_name) != file_name.as_ref() && other_file_path.exists() {
+                fs::remove_file(&other_file_path).map_err(|error| format!("Error while removing old cache file: {}", error))?;
+            }
+        }
+
+        let file_name = match file_name {
+            Some(file_name) => file_name,
+            None => return Ok(0),
+        };
+
+        let mut entries = self.data.iter().filter(|(_, entry)| !entry.memory_only).collect::<Vec<_>>();
+        entries.sort_by_key(|(_, entry)| std::cmp::Reverse(entry.last_access));
+
+        if self.file_options.max_file_size > 0 {
+            let mut total_size = 0;
+            let mut entry_count = 0;
+            for entry in entries.iter() {
+                total_size += self.file_options.format.serialize(&[entry])?.len();
+                if total_size > self.file_options.max_file_size {
+                    break;
+                }
+                entry_count += 1;
+            }
+
+            if entry_count < entries.len() {
+                log::debug!("Cache file size limit reached, leaving out {} entries", entries.len() - entry_count);
+                entries.truncate(entry_count);
+            }
+        }
+
+        let serialized = self.file_options.format.serialize(&entries.iter().cloned().collect::<HashMap<_, _>>())?;
+        let contents = match &self.file_options.encryption_key {
+            Some(encryption_key) => encryption::encrypt(encryption_key, &serialized)?,
+            None => serialized,
+        };
+
+        fs::write(cache_dir.join(file_name), contents).map_err(|error| format!("Error while writing cache to disk: {}", error))?;
+        Ok(entries.len())
     }
 
     /// Reads the cache from disk.
     pub fn read_from_disk(&mut self) -> Result<usize, String> where K: serde::de::DeserializeOwned, V: serde::de::DeserializeOwned {
+        let file_name = match self.file_options.get_file_name() {
+            Some(file_name) => file_name,
+            None => return Ok(0),
+        };
+
         let config_dir = file_handler::get_cache_dir().map_err(|error| error.to_string())?;
-        let file_path = config_dir.join(CACHE_FILE_NAME);
+        let file_path = config_dir.join(file_name);
         log::debug!("Reading cache from file: {}", file_path.display());
 
-        let serialized = fs::read_to_string(file_path).map_err(|error| format!("Error while reading cache from disk: {}", error))?;
-        self.data = serde_yaml::from_str(&serialized).map_err(|error| format!("Cache is in invalid format: {}", error))?;
+        let contents = fs::read(file_path).map_err(|error| format!("Error while reading cache from disk: {}", error))?;
+        let serialized = match &self.file_options.encryption_key {
+            Some(encryption_key) => encryption::decrypt(encryption_key, &contents)?,
+            None => contents,
+        };
+        self.data = self.file_options.format.deserialize(&serialized)?;
 
-        // Go through the cache to clean up any old entries.
-        let keys = self.data.keys().cloned().collect::<Vec<K>>();
-        for key in keys {
-            let _ = self.get(&key);
+        // Clean up any old entries.
+        let final_time_to_live = self.final_time_to_live;
+        self.data.retain(|_, entry| entry.last_access.elapsed() <= final_time_to_live);
+
+        // Older cache files don't have creation times, so last access is the best estimate.
+        for entry in self.data.values_mut() {
+            entry.created = entry.created.min(entry.last_access);
+        }
+
+        while self.max_entries > 0 && self.data.len() > self.max_entries {
+            self.evict_least_recently_used();
         }
 
         Ok(self.data.len())
     }
 }
 
+#[derive(Clone, Default)]
+pub struct CacheFileOptions {
+    pub format: CacheFileFormat,
+    /// 256-bit key. File is encrypted if set.
+    pub encryption_key: Option<Vec<u8>>,
+    /// Maximum size of the file in bytes. 0 means no limit.
+    pub max_file_size: usize,
+    /// Nothing is read from or written to disk. Existing cache files are removed on write.
+    pub memory_only: bool,
+}
+
+impl CacheFileOptions {
+    fn get_file_name(&self) -> Option<String> {
+        if self.memory_only {
+            None
+        }
+        else {
+            Some(Self::file_name(&self.format, self.encryption_key.is_some()))
+        }
+    }
+
+    fn file_name(format: &CacheFileFormat, encrypted: bool) -> String {
+        let extension = match format {
+            CacheFileFormat::Yaml => "yml",
+            CacheFileFormat::Binary => "bin",
+        };
+
+        if encrypted {
+            format!("{}.{}.enc", CACHE_FILE_BASE_NAME, extension)
+        }
+        else {
+            format!("{}.{}", CACHE_FILE_BASE_NAME, extension)
+        }
+    }
+
+    fn all_file_names() -> Vec<String> {
+        [CacheFileFormat::Yaml, CacheFileFormat::Binary].iter()
+            .flat_map(|format| [Self::file_name(format, false), Self::file_name(format, true)])
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum CacheFileFormat {
+    /// Human-readable.
+    #[default]
+    Yaml,
+    /// Smaller and faster to read and write.
+    Binary,
+}
+
+impl CacheFileFormat {
+    fn serialize<T: serde::Serialize>(&self, value: &T) -> Result<Vec<u8>, String> {
+        match self {
+            CacheFileFormat::Yaml => serde_yaml::to_string(value).map(String::into_bytes).map_err(|error| error.to_string()),
+            CacheFileFormat::Binary => bincode::serialize(value).map_err(|error| error.to_string()),
+        }
+    }
+
+    fn deserialize<T: serde::de::DeserializeOwned>(&self, serialized: &[u8]) -> Result<T, String> {
+        match self {
+            CacheFileFormat::Yaml => serde_yaml::from_slice(serialized).map_err(|error| format!("Cache is in invalid format: {}", error)),
+            CacheFileFormat::Binary => bincode::deserialize(serialized).map_err(|error| format!("Cache is in invalid format: {}", error)),
+        }
+    }
+}
+
 /// Describes if cache is global or host-specific.
 #[derive(Clone, Debug, EnumString)]
 pub enum CacheScope {
@@ -113,12 +336,98 @@ pub enum CacheScope {
     Host,
 }
 
+/// Describes if responses can be written to the cache file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, EnumString, Display)]
+pub enum CacheSensitivity {
+    Normal,
+    /// E.g. logs or environment variables that can contain secrets. Kept only in memory.
+    Sensitive,
+}
+
+impl CacheSensitivity {
+    /// Module setting that overrides the module's default sensitivity.
+    pub const SETTING: &'static str = "sensitive";
+    pub const SETTING_DESCRIPTION: &'static str =
+        "Responses are only cached in memory and never written to the cache file, e.g. if the output contains secrets.";
+
+    pub fn from_settings(default: CacheSensitivity, settings: &HashMap<String, String>) -> Self {
+        match settings.get(Self::SETTING).map(|value| value.as_str()) {
+            Some("true") => CacheSensitivity::Sensitive,
+            Some("false") => CacheSensitivity::Normal,
+            _ => default,
+        }
+    }
+}
+
+/// Selects entries for `Cache::purge_matching`.
+#[derive(Clone, Debug)]
+pub enum PurgeFilter {
+    KeyPrefix(String),
+    /// Module that the entry belongs to.
+    Source(String),
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct CacheStatistics {
+    pub entry_count: usize,
+    /// Entries that aren't written to the cache file.
+    pub memory_only_count: usize,
+    /// In seconds.
+    pub oldest_entry_age: Option<u64>,
+    pub modules: BTreeMap<String, ModuleCacheStatistics>,
+}
+
+impl std::fmt::Display for CacheStatistics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Entries: {} ({} only in memory)", self.entry_count, self.memory_only_count)?;
+        if let Some(oldest_entry_age) = self.oldest_entry_age {
+            writeln!(f, "Oldest entry: {} seconds", oldest_entry_age)?;
+        }
+
+        for (source_id, module) in self.modules.iter() {
+            let source_id = if source_id.is_empty() { "(unknown)" } else { source_id.as_str() };
+            write!(f, "  {}: {} entries, {} hits, {} misses", source_id, module.entry_count, module.hits, module.misses)?;
+            if let (Some(newest), Some(oldest)) = (module.newest_entry_age, module.oldest_entry_age) {
+                write!(f, ", age {}-{} seconds", newest, oldest)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ModuleCacheStatistics {
+    pub entry_count: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// In seconds.
+    pub newest_entry_age: Option<u64>,
+    /// In seconds.
+    pub oldest_entry_age: Option<u64>,
+}
+
+#[derive(Clone, Default)]
+struct LookupStatistics {
+    hits: u64,
+    misses: u64,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 struct CacheEntry<V> {
     value: V,
+    /// Module that the value belongs to. Missing from older cache files.
+    #[serde(default)]
+    source_id: String,
+    /// Expiration and evicting the least recently used entries are based on this.
     // Instant is a better choice here than SystemTime.
     #[serde(serialize_with = "serialize_instant", deserialize_with = "deserialize_instant")]
     last_access: Instant,
+    /// Only used for reporting the age of the value. Missing from older cache files.
+    #[serde(default = "Instant::now", serialize_with = "serialize_instant", deserialize_with = "deserialize_instant")]
+    created: Instant,
+    #[serde(skip)]
+    memory_only: bool,
 }
 
 fn serialize_instant<S>(instant: &Instant, serializer: S) -> Result<S::Ok, S::Error> where S: serde::Serializer
@@ -0,0 +1,45 @@
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use aes_gcm::aead::{Aead, KeyInit};
+
+/// Identifies the file format and version so that other files aren't accidentally decrypted.
+const HEADER: &[u8] = b"LKCACHE1";
+const KEY_SIZE: usize = 32;
+const NONCE_SIZE: usize = 12;
+
+
+pub fn generate_key() -> Vec<u8> {
+    (0..KEY_SIZE).map(|_| rand::random::<u8>()).collect()
+}
+
+/// Encrypts with AES-256-GCM. Output contains the header, a random nonce and the ciphertext.
+pub fn encrypt(key: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let cipher = new_cipher(key)?;
+    let nonce_bytes = (0..NONCE_SIZE).map(|_| rand::random::<u8>()).collect::<Vec<_>>();
+    let ciphertext = cipher.encrypt(Nonce::from_slice(&nonce_bytes), plaintext)
+                           .map_err(|_| String::from("Error while encrypting cache"))?;
+
+    let mut result = Vec::with_capacity(HEADER.len() + NONCE_SIZE + ciphertext.len());
+    result.extend_from_slice(HEADER);
+    result.extend_from_slice(&nonce_bytes);
+    result.extend_from_slice(&ciphertext);
+    Ok(result)
+}
+
+pub fn decrypt(key: &[u8], contents: &[u8]) -> Result<Vec<u8>, String> {
+    if contents.len() < HEADER.len() + NONCE_SIZE || !contents.starts_with(HEADER) {
+        return Err(String::from("Cache file is not encrypted or is in unknown format"));
+    }
+
+    let cipher = new_cipher(key)?;
+    let (nonce_bytes, ciphertext) = contents[HEADER.len()..].split_at(NONCE_SIZE);
+    // Also fails if the file was modified, since GCM authenticates the contents.
+    cipher.decrypt(Nonce::from_slice(nonce_bytes), ciphertext)
+          .map_err(|_| String::from("Couldn't decrypt cache file. Encryption key may have changed."))
+}
+
+fn new_cipher(key: &[u8]) -> Result<Aes256Gcm, String> {
+    if key.len() != KEY_SIZE {
+        return Err(format!("Invalid encryption key length: {}", key.len()));
+    }
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)))
+}
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use dbus::arg;
+use dbus::blocking::Connection;
+
+// Freedesktop Secret Service API. Implemented by e.g. GNOME Keyring and KWallet.
+// See: https://specifications.freedesktop.org/secret-service-spec/latest/
+// Called directly with dbus for the same reason as in desktop_portal_model: only a few calls are needed.
+const SECRETS_DESTINATION: &str = "org.freedesktop.secrets";
+const SERVICE_PATH: &str = "/org/freedesktop/secrets";
+const DEFAULT_COLLECTION_PATH: &str = "/org/freedesktop/secrets/aliases/default";
+const SERVICE_INTERFACE: &str = "org.freedesktop.Secret.Service";
+const COLLECTION_INTERFACE: &str = "org.freedesktop.Secret.Collection";
+const ITEM_INTERFACE: &str = "org.freedesktop.Secret.Item";
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+const ITEM_LABEL: &str = "Lightkeeper cache encryption key";
+
+/// Secret Service secret: session, parameters, value and content type.
+type Secret = (dbus::Path<'static>, Vec<u8>, Vec<u8>, String);
+
+
+/// Returns the cache encryption key from the system keyring. A new key is generated and stored if there isn't one yet.
+pub fn get_or_create_key() -> Result<Vec<u8>, String> {
+    let connection = Connection::new_session().map_err(|error| format!("Couldn't connect to session bus: {}", error))?;
+    let service = connection.with_proxy(SECRETS_DESTINATION, SERVICE_PATH, TIMEOUT);
+
+    // Plain algorithm doesn't encrypt the transfer, but the session bus is local to the user anyway.
+    let (_output, session): (arg::Variant<Box<dyn arg::RefArg>>, dbus::Path) = service.method_call(
+        SERVICE_INTERFACE, "OpenSession", ("plain", arg::Variant(String::new()))
+    ).map_err(|error| format!("Couldn't open keyring session: {}", error))?;
+
+    let result = get_key(&connection, &session).and_then(|key| match key {
+        Some(key) => Ok(key),
+        None => create_key(&connection, &session),
+    });
+
+    let session_proxy = connection.with_proxy(SECRETS_DESTINATION, session, TIMEOUT);
+    let _: Result<(), _> = session_proxy.method_call("org.freedesktop.Secret.Session", "Close", ());
+
+    result
+}
+
+fn get_attributes() -> HashMap<&'static str, &'static str> {
+    HashMap::from([
+        ("application", "lightkeeper"),
+        ("purpose", "cache-encryption"),
+    ])
+}
+
+fn get_key(connection: &Connection, session: &dbus::Path<'static>) -> Result<Option<Vec<u8>>, String> {
+    let service = connection.with_proxy(SECRETS_DESTINATION, SERVICE_PATH, TIMEOUT);
+    let (unlocked, locked): (Vec<dbus::Path>, Vec<dbus::Path>) = service.method_call(SERVICE_INTERFACE, "SearchItems", (get_attributes(),))
+                                                                        .map_err(|error| format!("Couldn't search keyring: {}", error))?;
+
+    let item_path = match unlocked.into_iter().next() {
+        Some(item_path) => item_path,
+        // Unlocking would require showing a prompt, which isn't supported.
+        None if !locked.is_empty() => return Err(String::from("Keyring is locked")),
+        None => return Ok(None),
+    };
+
+    let item = connection.with_proxy(SECRETS_DESTINATION, item_path, TIMEOUT);
+    let (secret,): (Secret,) = item.method_call(ITEM_INTERFACE, "GetSecret", (session.clone(),))
+                                   .map_err(|error| format!("Couldn't read key from keyring: {}", error))?;
+
+    Ok(Some(secret.2))
+}
+
+fn create_key(connection: &Connection, session: &dbus::Path<'static>) -> Result<Vec<u8>, String> {
+    log::info!("Creating a new cache encryption key in keyring");
+    let key = super::encryption::generate_key();
+
+    let attributes = get_attributes().into_iter().map(|(name, value)| (name.to_string(), value.to_string())).collect::<HashMap<_, _>>();
+    let mut properties = arg::PropMap::new();
+    properties.insert(String::from("org.freedesktop.Secret.Item.Label"), arg::Variant(Box::new(ITEM_LABEL.to_string())));
+    properties.insert(String::from("org.freedesktop.Secret.Item.Attributes"), arg::Variant(Box::new(attributes)));
+
+    let secret: Secret = (session.clone(), Vec::new(), key.clone(), String::from("application/octet-stream"));
+    let collection = connection.with_proxy(SECRETS_DESTINATION, DEFAULT_COLLECTION_PATH, TIMEOUT);
+    let (_item, prompt): (dbus::Path, dbus::Path) = collection.method_call(COLLECTION_INTERFACE, "CreateItem", (properties, secret, true))
+                                                              .map_err(|error| format!("Couldn't store key in keyring: {}", error))?;
+
+    // Prompt is needed if the collection is locked.
+    if &*prompt != "/" {
+        return Err(String::from("Keyring is locked"));
+    }
+
+    Ok(key)
+}
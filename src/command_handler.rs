@@ -24,6 +24,7 @@ use crate::{
 use crate::module::{
     command::Command,
     command::CommandResult,
+    command::FileUpload,
 };
 
 // Default needs to be implemented because of Qt QObject requirements.
@@ -78,9 +79,31 @@ impl CommandHandler {
 
         for (host_id, host_config) in hosts_config.hosts.iter() {
             for (command_id, command_config) in host_config.commands.iter() {
-                let command_spec = crate::module::ModuleSpecification::new(command_id, &command_config.version);
-                let command = self.module_factory.new_command(&command_spec, &command_config.settings);
-                self.add_command(host_id, command);
+                let command_spec = command_config.get_module_spec(command_id);
+                match self.module_factory.new_command(&command_spec, &command_config.get_module_settings(command_id)) {
+                    Ok(command) => self.add_command(host_id, command),
+                    Err(error) => log::error!("[{}] Command {} is disabled: {}", host_id, command_id, error),
+                }
+            }
+        }
+    }
+
+    /// Recreates commands of the given hosts. Hosts missing from `hosts_config` are removed.
+    pub fn reconfigure_hosts(&mut self, hosts_config: &Hosts, host_ids: &[String]) {
+        for host_id in host_ids.iter() {
+            self.commands.remove(host_id);
+            self.hosts_config.hosts.remove(host_id);
+
+            if let Some(host_config) = hosts_config.hosts.get(host_id) {
+                self.hosts_config.hosts.insert(host_id.clone(), host_config.clone());
+
+                for (command_id, command_config) in host_config.commands.iter() {
+                    let command_spec = command_config.get_module_spec(command_id);
+                    match self.module_factory.new_command(&command_spec, &command_config.get_module_settings(command_id)) {
+                        Ok(command) => self.add_command(host_id, command),
+                        Err(error) => log::error!("[{}] Command {} is disabled: {}", host_id, command_id, error),
+                    }
+                }
             }
         }
     }
@@ -108,8 +131,12 @@ impl CommandHandler {
                                    .get(command_id).unwrap();
         let state_update_sender = self.state_update_sender.as_ref().unwrap().clone();
 
-        let messages = match get_command_connector_messages(&host, command, parameters) {
-            Ok(messages) => messages,
+        let connector_data = get_command_connector_messages(&host, command, parameters).and_then(|messages| {
+            command.get_file_upload(host.clone(), parameters.clone()).map(|file_upload| (messages, file_upload))
+        });
+
+        let (messages, file_upload) = match connector_data {
+            Ok(connector_data) => connector_data,
             Err(error) => {
                 log::error!("Command \"{}\" failed: {}", command_id, error);
                 state_update_sender.send(StateUpdateMessage {
@@ -127,26 +154,131 @@ impl CommandHandler {
 
         self.invocation_id_counter += 1;
 
-        self.request_sender.as_ref().unwrap().send(ConnectorRequest {
+        let command_request = ConnectorRequest {
             connector_spec: command.get_connector_spec(),
             source_id: command.get_module_spec().id,
             host: host.clone(),
             request_type: RequestType::Command,
             messages: messages,
             response_handler: Self::get_response_handler(
-                host,
+                host.clone(),
                 command.box_clone(),
                 self.invocation_id_counter,
-                state_update_sender
+                state_update_sender.clone()
             ),
-            cache_policy: CachePolicy::BypassCache, 
-        }).unwrap_or_else(|error| {
-            log::error!("Couldn't send message to connector: {}", error);
-        });
+            cache_policy: CachePolicy::BypassCache,
+            cache_sensitivity: command.get_metadata_self().cache_sensitivity,
+            long_running: true,
+            ..Default::default()
+        };
+
+        let file_upload = match file_upload {
+            Some(file_upload) => file_upload,
+            None => {
+                self.request_sender.as_ref().unwrap().send(command_request).unwrap_or_else(|error| {
+                    log::error!("Couldn't send message to connector: {}", error);
+                });
+                return self.invocation_id_counter;
+            }
+        };
+
+        // File is copied to the cache directory with metadata, so it can be uploaded like an edited file.
+        match Self::create_upload_file(&host, &file_upload) {
+            Ok(local_file_path) => {
+                log::debug!("[{}] Uploading {} to {} before executing command \"{}\"", host_id, file_upload.local_path, file_upload.remote_path, command_id);
+
+                self.request_sender.as_ref().unwrap().send(ConnectorRequest {
+                    connector_spec: command.get_connector_spec(),
+                    source_id: command.get_module_spec().id,
+                    host: host.clone(),
+                    request_type: RequestType::Upload,
+                    messages: vec![local_file_path.clone()],
+                    response_handler: Self::get_response_handler_upload_before_command(
+                        host,
+                        command.box_clone(),
+                        self.invocation_id_counter,
+                        local_file_path,
+                        command_request,
+                        self.request_sender.as_ref().unwrap().clone(),
+                        state_update_sender
+                    ),
+                    cache_policy: CachePolicy::BypassCache,
+                    cache_sensitivity: command.get_metadata_self().cache_sensitivity,
+                    long_running: true,
+                    ..Default::default()
+                }).unwrap_or_else(|error| {
+                    log::error!("Couldn't send message to connector: {}", error);
+                });
+            },
+            Err(error) => {
+                log::error!("Couldn't read file {}: {}", file_upload.local_path, error);
+                state_update_sender.send(StateUpdateMessage {
+                    host_name: host.name,
+                    display_options: command.get_display_options(),
+                    module_spec: command.get_module_spec(),
+                    command_result: Some(CommandResult::new_critical_error(format!("Couldn't read file {}: {}", file_upload.local_path, error))
+                                                      .with_invocation_id(self.invocation_id_counter)),
+                    ..Default::default()
+                }).unwrap_or_else(|error| {
+                    log::error!("Couldn't send message to state manager: {}", error);
+                });
+            }
+        }
 
         self.invocation_id_counter
     }
 
+    fn create_upload_file(host: &Host, file_upload: &FileUpload) -> std::io::Result<String> {
+        let contents = std::fs::read(&file_upload.local_path)?;
+        let metadata = file_handler::FileMetadata {
+            download_time: chrono::Utc::now(),
+            local_path: None,
+            remote_path: file_upload.remote_path.clone(),
+            remote_file_hash: sha256::digest(contents.as_slice()),
+            owner_uid: 0,
+            owner_gid: 0,
+            permissions: file_upload.permissions,
+            temporary: true,
+        };
+        file_handler::create_file(host, &file_upload.remote_path, metadata, contents)
+    }
+
+    /// Sends the actual command request after the file was uploaded.
+    fn get_response_handler_upload_before_command(host: Host, command: Command, invocation_id: u64,
+                                                   local_file_path: String,
+                                                   command_request: ConnectorRequest,
+                                                   request_sender: mpsc::Sender<ConnectorRequest>,
+                                                   state_update_sender: mpsc::Sender<StateUpdateMessage>) -> ResponseHandlerCallback {
+
+        Box::new(move |responses| {
+            if let Err(error) = file_handler::remove_file(&local_file_path) {
+                log::error!("Couldn't remove temporary file {}: {}", local_file_path, error);
+            }
+
+            match responses.into_iter().next().unwrap_or_else(|| Err(String::from("No response"))) {
+                Ok(_) => {
+                    request_sender.send(command_request).unwrap_or_else(|error| {
+                        log::error!("Couldn't send message to connector: {}", error);
+                    });
+                },
+                Err(error) => {
+                    let error_message = format!("Error uploading file: {}", error);
+                    log::error!("{}", error_message);
+
+                    state_update_sender.send(StateUpdateMessage {
+                        host_name: host.name,
+                        display_options: command.get_display_options(),
+                        module_spec: command.get_module_spec(),
+                        command_result: Some(CommandResult::new_critical_error(error_message).with_invocation_id(invocation_id)),
+                        ..Default::default()
+                    }).unwrap_or_else(|error| {
+                        log::error!("Couldn't send message to state manager: {}", error);
+                    });
+                }
+            }
+        })
+    }
+
     // Return value contains host's commands. `parameters` is not set since provided by data point later on.
     pub fn get_commands_for_host(&self, host_id: String) -> HashMap<String, CommandData> {
         if let Some(command_collection) = self.commands.get(&host_id) {
@@ -165,6 +297,13 @@ impl CommandHandler {
         CommandData::new(command_id.clone(), command.get_display_options())
     }
 
+    pub fn get_affected_monitors(&self, host_id: &String, command_id: &String) -> Vec<String> {
+        self.commands.get(host_id)
+                     .and_then(|command_collection| command_collection.get(command_id))
+                     .map(|command| command.get_affected_monitors())
+                     .unwrap_or_default()
+    }
+
     fn get_response_handler(host: Host, command: Command, invocation_id: u64, state_update_sender: mpsc::Sender<StateUpdateMessage>) -> ResponseHandlerCallback {
         Box::new(move |results| {
             let (responses, errors): (Vec<_>, Vec<_>) =  results.into_iter().partition(Result::is_ok);
@@ -257,6 +396,9 @@ impl CommandHandler {
                 self.state_update_sender.as_ref().unwrap().clone()
             ),
             cache_policy: CachePolicy::BypassCache,
+            cache_sensitivity: command.get_metadata_self().cache_sensitivity,
+            long_running: true,
+            ..Default::default()
         }).unwrap();
 
         (self.invocation_id_counter, local_file_path)
@@ -340,6 +482,9 @@ impl CommandHandler {
                             host, command.box_clone(), self.invocation_id_counter, metadata, false, state_update_sender
                         ),
                         cache_policy: CachePolicy::BypassCache,
+                        cache_sensitivity: command.get_metadata_self().cache_sensitivity,
+                        long_running: true,
+                        ..Default::default()
                     }).unwrap();
                 }
             },
@@ -513,6 +658,9 @@ impl CommandHandler {
                 self.state_update_sender.as_ref().unwrap().clone()
             ),
             cache_policy: CachePolicy::BypassCache,
+            cache_sensitivity: command.get_metadata_self().cache_sensitivity,
+            long_running: true,
+            ..Default::default()
         }).unwrap_or_else(|error| {
             log::error!("Couldn't send message to connector: {}", error);
         });
@@ -560,6 +708,7 @@ impl CommandHandler {
                             }
                             else {
                                 metadata.update_hash(local_file_hash);
+                                let cache_sensitivity = command.get_metadata_self().cache_sensitivity;
                                 request_sender.send(ConnectorRequest {
                                     connector_spec: command.get_connector_spec(),
                                     source_id: command.get_module_spec().id,
@@ -570,6 +719,9 @@ impl CommandHandler {
                                         host, command, 0, metadata, true, state_update_sender
                                     ),
                                     cache_policy: CachePolicy::BypassCache,
+                                    cache_sensitivity: cache_sensitivity,
+                                    long_running: true,
+                                    ..Default::default()
                                 }).unwrap_or_else(|error| {
                                     log::error!("Couldn't send message to connector: {}", error);
                                 });
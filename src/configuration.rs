@@ -4,22 +4,38 @@ use std::io::Write;
 use std::os::unix::prelude::PermissionsExt;
 use std::path::{Path, PathBuf};
 use std::{ fs, io, collections::HashMap };
+use crate::cache::CacheFileFormat;
 use crate::host::HostSetting;
 use crate::file_handler;
+use crate::inventory;
+use crate::module::{ModuleSpecification, INSTANCE_ID_SETTING};
+use crate::utils::string_manipulation;
+
+pub mod migration;
+pub mod watcher;
 
 const MAIN_CONFIG_FILE: &str = "config.yml";
 const HOSTS_FILE: &str = "hosts.yml";
 const GROUPS_FILE: &str = "groups.yml";
 pub const INTERNAL: &str = "internal";
+/// Generic modules whose `command` setting is shell code and is therefore not interpolated.
+const SHELL_COMMAND_MODULES: [&str; 2] = ["script", "nagios-plugin"];
 
 
-#[derive(Serialize, Deserialize, Default, Clone)]
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct Configuration {
+    /// Version of the configuration file format. Older configurations are migrated on read.
+    #[serde(default)]
+    pub config_version: u32,
     pub preferences: Preferences,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub display_options: Option<DisplayOptions>,
     pub cache_settings: CacheSettings,
+    #[serde(default)]
+    pub connection_settings: ConnectionSettings,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dynamic_inventory: Option<DynamicInventorySettings>,
 }
 
 #[derive(Serialize, Deserialize, Default, Clone)]
@@ -34,7 +50,7 @@ pub struct Hosts {
     pub hosts: HashMap<String, HostSettings>,
 }
 
-#[derive(Serialize, Deserialize, Default, Clone)]
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct Preferences {
     #[serde(default)]
@@ -52,7 +68,7 @@ pub struct Preferences {
     pub terminal_args: Vec<String>,
 }
 
-#[derive(Serialize, Deserialize, Default, Clone)]
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct DisplayOptions {
     pub qtquick_style: String,
@@ -60,7 +76,7 @@ pub struct DisplayOptions {
     pub categories: HashMap<String, Category>,
 }
 
-#[derive(Serialize, Deserialize, Default, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct CacheSettings {
     /// Enable cache. Set false to disable completely and make sure cache file is empty.
@@ -74,9 +90,116 @@ pub struct CacheSettings {
     pub prefer_cache: bool,
     /// How long entries in cache are considered valid.
     pub time_to_live: u64,
+    /// Yaml or Binary.
+    #[serde(default)]
+    pub file_format: CacheFileFormat,
+    /// Encrypt the cache file with a key stored in the system keyring.
+    /// If the keyring isn't available, cache is kept only in memory.
+    #[serde(default)]
+    pub encrypt_file: bool,
+    /// Least recently used entries are left out of the cache file if it would grow larger. 0 means no limit.
+    #[serde(default = "CacheSettings::default_max_file_size_kb")]
+    pub max_file_size_kb: usize,
+    /// Least recently used entries are evicted if there are more entries. 0 means no limit.
+    #[serde(default = "CacheSettings::default_max_entries")]
+    pub max_entries: usize,
 }
 
-#[derive(Serialize, Deserialize, Default, Clone)]
+impl CacheSettings {
+    pub fn default_max_file_size_kb() -> usize {
+        51200
+    }
+
+    pub fn default_max_entries() -> usize {
+        10000
+    }
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        CacheSettings {
+            enable_cache: false,
+            provide_initial_value: false,
+            initial_value_time_to_live: 0,
+            prefer_cache: false,
+            time_to_live: 0,
+            file_format: CacheFileFormat::default(),
+            encrypt_file: false,
+            max_file_size_kb: Self::default_max_file_size_kb(),
+            max_entries: Self::default_max_entries(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ConnectionSettings {
+    /// Maximum number of requests processed at the same time over all hosts.
+    #[serde(default = "ConnectionSettings::default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+    /// Maximum number of requests processed at the same time for a single host. Keeps one host from using all capacity.
+    #[serde(default = "ConnectionSettings::default_max_concurrent_requests_per_host")]
+    pub max_concurrent_requests_per_host: usize,
+    /// Seconds to wait for a monitor request to finish, and separately for the host to become free, before giving up.
+    #[serde(default = "ConnectionSettings::default_request_timeout")]
+    pub request_timeout: u64,
+    /// Seconds to wait for a command started by the user or a file transfer to finish. 0 means no limit.
+    #[serde(default)]
+    pub command_timeout: u64,
+}
+
+impl ConnectionSettings {
+    pub fn default_max_concurrent_requests() -> usize {
+        32
+    }
+
+    pub fn default_max_concurrent_requests_per_host() -> usize {
+        2
+    }
+
+    pub fn default_request_timeout() -> u64 {
+        60
+    }
+}
+
+impl Default for ConnectionSettings {
+    fn default() -> Self {
+        ConnectionSettings {
+            max_concurrent_requests: Self::default_max_concurrent_requests(),
+            max_concurrent_requests_per_host: Self::default_max_concurrent_requests_per_host(),
+            request_timeout: Self::default_request_timeout(),
+            command_timeout: 0,
+        }
+    }
+}
+
+/// Executable that outputs hosts as JSON using Ansible's dynamic inventory format.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct DynamicInventorySettings {
+    /// Path to the executable. Relative paths are relative to the configuration directory.
+    pub command: String,
+    #[serde(default = "DynamicInventorySettings::default_arguments")]
+    pub arguments: Vec<String>,
+    /// Groups that are added to every host from the inventory.
+    #[serde(default)]
+    pub groups: Vec<String>,
+    /// In seconds. The executable is killed if it doesn't finish in time.
+    #[serde(default = "DynamicInventorySettings::default_timeout")]
+    pub timeout: u64,
+}
+
+impl DynamicInventorySettings {
+    pub fn default_arguments() -> Vec<String> {
+        vec![String::from("--list")]
+    }
+
+    pub fn default_timeout() -> u64 {
+        30
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct Category {
     pub priority: u16,
@@ -92,7 +215,7 @@ pub struct Category {
     pub collapsible_commands: Option<Vec<String>>,
 }
 
-#[derive(Serialize, Deserialize, Default, Clone)]
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct HostSettings {
     #[serde(default)]
@@ -103,20 +226,36 @@ pub struct HostSettings {
     pub fqdn: String,
     #[serde(default, skip_serializing_if = "Configuration::is_default")]
     pub settings: Vec<HostSetting>,
-    // Currently, you have to use config groups instead of setting these directly on host.
-    // So these are never written but will be populated from groups on config read.
+    /// Variables that can be referenced in module settings with ${name}. These override variables from groups.
+    #[serde(default, skip_serializing_if = "Configuration::is_default")]
+    pub vars: HashMap<String, String>,
+    // These contain the effective module settings and are populated from groups on config read.
+    // So these are never written. Host-level settings are written from the host_* fields below.
     #[serde(default, skip_serializing_if = "Configuration::always")]
     pub monitors: HashMap<String, MonitorConfig>,
     #[serde(default, skip_serializing_if = "Configuration::always")]
     pub commands: HashMap<String, CommandConfig>,
     #[serde(default, skip_serializing_if = "Configuration::always")]
     pub connectors: HashMap<String, ConnectorConfig>,
+    /// Module settings defined directly for the host in hosts.yml. These override settings from groups.
+    #[serde(default, rename = "monitors", skip_deserializing, skip_serializing_if = "Configuration::is_default")]
+    pub host_monitors: HashMap<String, MonitorConfig>,
+    #[serde(default, rename = "commands", skip_deserializing, skip_serializing_if = "Configuration::is_default")]
+    pub host_commands: HashMap<String, CommandConfig>,
+    #[serde(default, rename = "connectors", skip_deserializing, skip_serializing_if = "Configuration::is_default")]
+    pub host_connectors: HashMap<String, ConnectorConfig>,
+    /// Host comes from dynamic inventory. These are read-only and never written to hosts.yml.
+    #[serde(skip)]
+    pub is_dynamic: bool,
 }
 
 #[derive(Serialize, Deserialize, Default, Clone)]
 pub struct ConfigGroup {
     #[serde(default, skip_serializing_if = "Configuration::is_default")]
     pub host_settings: Vec<HostSetting>,
+    /// Variables that can be referenced in module settings with ${name}.
+    #[serde(default, skip_serializing_if = "Configuration::is_default")]
+    pub vars: HashMap<String, String>,
     #[serde(default, skip_serializing_if = "Configuration::is_default")]
     pub monitors: HashMap<String, MonitorConfig>,
     #[serde(default, skip_serializing_if = "Configuration::is_default")]
@@ -139,6 +278,9 @@ impl HostSettings {
 #[derive(Serialize, Deserialize, Clone, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct MonitorConfig {
+    /// Module to use if it differs from the monitor ID. Allows multiple instances of generic modules, e.g. "script".
+    #[serde(default, skip_serializing_if = "Configuration::is_default")]
+    pub module: String,
     #[serde(default = "MonitorConfig::default_version", skip_serializing_if = "Configuration::version_is_latest")]
     pub version: String,
     #[serde(default = "MonitorConfig::default_enabled", skip_serializing_if = "MonitorConfig::is_enabled")]
@@ -161,11 +303,26 @@ impl MonitorConfig {
     pub fn is_enabled(enabled: &Option<bool>) -> bool {
         enabled.clone().unwrap_or(true)
     }
+
+    pub fn get_module_spec(&self, monitor_id: &String) -> ModuleSpecification {
+        let module_id = if self.module.is_empty() { monitor_id } else { &self.module };
+        ModuleSpecification::new(module_id, &self.version)
+    }
+
+    /// Instances of generic modules also receive the monitor ID since it can't be derived from the module.
+    pub fn get_module_settings(&self, monitor_id: &String) -> HashMap<String, String> {
+        let mut settings = self.settings.clone();
+        if !self.module.is_empty() {
+            settings.insert(INSTANCE_ID_SETTING.to_string(), monitor_id.clone());
+        }
+        settings
+    }
 }
 
 impl Default for MonitorConfig {
     fn default() -> Self {
         MonitorConfig {
+            module: String::new(),
             version: MonitorConfig::default_version(),
             enabled: MonitorConfig::default_enabled(),
             is_critical: None,
@@ -177,6 +334,9 @@ impl Default for MonitorConfig {
 #[derive(Serialize, Deserialize, Clone, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct CommandConfig {
+    /// Module to use if it differs from the command ID. Allows multiple instances of generic modules, e.g. "script".
+    #[serde(default, skip_serializing_if = "Configuration::is_default")]
+    pub module: String,
     #[serde(default = "CommandConfig::default_version", skip_serializing_if = "Configuration::version_is_latest")]
     pub version: String,
     #[serde(default, skip_serializing_if = "Configuration::is_default")]
@@ -187,11 +347,25 @@ impl CommandConfig {
     pub fn default_version() -> String {
         String::from("latest")
     }
+
+    pub fn get_module_spec(&self, command_id: &String) -> ModuleSpecification {
+        let module_id = if self.module.is_empty() { command_id } else { &self.module };
+        ModuleSpecification::new(module_id, &self.version)
+    }
+
+    pub fn get_module_settings(&self, command_id: &String) -> HashMap<String, String> {
+        let mut settings = self.settings.clone();
+        if !self.module.is_empty() {
+            settings.insert(INSTANCE_ID_SETTING.to_string(), command_id.clone());
+        }
+        settings
+    }
 }
 
 impl Default for CommandConfig {
     fn default() -> Self {
         CommandConfig {
+            module: String::new(),
             version: CommandConfig::default_version(),
             settings: HashMap::new(),
         }
@@ -217,21 +391,13 @@ impl Configuration {
         let main_config_file_path = config_dir.join(MAIN_CONFIG_FILE);
         let hosts_file_path = config_dir.join(HOSTS_FILE);
         let groups_file_path = config_dir.join(GROUPS_FILE);
-        let old_templates_file_path = config_dir.join("templates.yml");
 
         // If main configuration is missing, this is probably the first run, so create initial configurations.
         if let Err(_) = fs::metadata(&main_config_file_path) {
             Self::write_initial_config(&config_dir)?;
         }
-        else if let Ok(_) = fs::metadata(config_dir.join("templates.yml")) {
-            log::warn!("Old templates.yml configuration file found. Renaming old configuration files and reinitializing.");
-
-            // This is the old groups.yml file. Rename old files with .old suffix and do a new init.
-            fs::rename(&main_config_file_path, config_dir.join(format!("{}.old", MAIN_CONFIG_FILE)))?;
-            fs::rename(&hosts_file_path, config_dir.join(format!("{}.old", HOSTS_FILE)))?;
-            fs::rename(old_templates_file_path, config_dir.join("templates.yml.old"))?;
-
-            Self::write_initial_config(&config_dir)?;
+        else {
+            migration::migrate(&config_dir)?;
         }
 
         // Make sure directory is protected from reading by others.
@@ -261,6 +427,33 @@ impl Configuration {
         let all_groups = serde_yaml::from_str::<Groups>(groups_contents.as_str())
                                     .map_err(|error| io::Error::new(io::ErrorKind::Other, error.to_string()))?;
 
+        if let Some(dynamic_inventory) = main_config.dynamic_inventory.as_ref() {
+            log::info!("Reading dynamic inventory from {}", dynamic_inventory.command);
+            match inventory::dynamic::read(dynamic_inventory, &config_dir) {
+                Ok(dynamic_hosts) => {
+                    for (host_id, mut host_config) in dynamic_hosts.hosts.into_iter() {
+                        if hosts.hosts.contains_key(&host_id) {
+                            log::warn!("Host {} from dynamic inventory is already defined in {}, ignoring", host_id, HOSTS_FILE);
+                            continue;
+                        }
+
+                        // Inventory groups are only used if they are also defined in groups.yml.
+                        host_config.groups.retain(|group_id| all_groups.groups.contains_key(group_id));
+                        for group_id in dynamic_inventory.groups.iter().rev() {
+                            if !host_config.groups.contains(group_id) {
+                                host_config.groups.insert(0, group_id.clone());
+                            }
+                        }
+
+                        host_config.is_dynamic = true;
+                        hosts.hosts.insert(host_id, host_config);
+                    }
+                },
+                // Static configuration is still usable, so this is not considered fatal.
+                Err(error) => log::error!("Failed to read dynamic inventory: {}", error),
+            }
+        }
+
         // Check there are no invalid group references.
         let invalid_groups = hosts.hosts.values()
             .flat_map(|host_config| host_config.groups.clone())
@@ -272,7 +465,12 @@ impl Configuration {
             return Err(io::Error::new(io::ErrorKind::Other, error_message));
         }
 
-        for (_, host_config) in hosts.hosts.iter_mut() {
+        for (host_id, host_config) in hosts.hosts.iter_mut() {
+            // Host-level settings are stored separately so they can be written back as is.
+            host_config.host_monitors.extend(std::mem::take(&mut host_config.monitors));
+            host_config.host_commands.extend(std::mem::take(&mut host_config.commands));
+            host_config.host_connectors.extend(std::mem::take(&mut host_config.connectors));
+
             for group_id in host_config.groups.clone().iter() {
                 let group_config = all_groups.groups.get(group_id).unwrap();
 
@@ -302,6 +500,31 @@ impl Configuration {
                     host_config.connectors.insert(connector_id.clone(), merged_config);
                 });
             }
+
+            // Merge host-level settings last so they take precedence.
+            for (monitor_id, new_config) in host_config.host_monitors.iter() {
+                let mut merged_config = host_config.monitors.get(monitor_id).cloned().unwrap_or(MonitorConfig::default());
+                merged_config.settings.extend(new_config.settings.clone());
+                merged_config.is_critical = new_config.is_critical.or(merged_config.is_critical);
+                host_config.monitors.insert(monitor_id.clone(), merged_config);
+            }
+
+            for (command_id, new_config) in host_config.host_commands.iter() {
+                let mut merged_config = host_config.commands.get(command_id).cloned().unwrap_or(CommandConfig::default());
+                merged_config.settings.extend(new_config.settings.clone());
+                if !Configuration::version_is_latest(&new_config.version) {
+                    merged_config.version = new_config.version.clone();
+                }
+                host_config.commands.insert(command_id.clone(), merged_config);
+            }
+
+            for (connector_id, new_config) in host_config.host_connectors.iter() {
+                let mut merged_config = host_config.connectors.get(connector_id).cloned().unwrap_or(ConnectorConfig::default());
+                merged_config.settings.extend(new_config.settings.clone());
+                host_config.connectors.insert(connector_id.clone(), merged_config);
+            }
+
+            Self::interpolate_settings(host_id, host_config, &all_groups);
         }
 
         Ok((main_config, hosts, all_groups))
@@ -385,7 +608,14 @@ impl Configuration {
         let hosts_config_file = fs::OpenOptions::new().write(true).truncate(true).open(hosts_file_path.clone());
         match hosts_config_file {
             Ok(mut file) => {
-                let hosts_config = serde_yaml::to_string(hosts).unwrap();
+                let static_hosts = Hosts {
+                    hosts: hosts.hosts.iter()
+                        .filter(|(_, host_config)| !host_config.is_dynamic)
+                        .map(|(host_id, host_config)| (host_id.clone(), host_config.clone()))
+                        .collect(),
+                };
+
+                let hosts_config = serde_yaml::to_string(&static_hosts).unwrap();
                 if let Err(error) = file.write_all(hosts_config.as_bytes()) {
                     let message = format!("Failed to write host configuration file {}: {}", hosts_file_path.to_string_lossy(), error);
                     return Err(io::Error::new(io::ErrorKind::Other, message));
@@ -449,8 +679,11 @@ impl Configuration {
             Ok(mut file) => {
                 // Display options are currently not really user-configurable.
                 let config_without_display_options = Configuration {
+                    config_version: config.config_version,
                     preferences: config.preferences.clone(),
                     cache_settings: config.cache_settings.clone(),
+                    connection_settings: config.connection_settings.clone(),
+                    dynamic_inventory: config.dynamic_inventory.clone(),
                     display_options: None,
                 };
 
@@ -472,6 +705,72 @@ impl Configuration {
         Ok(())
     }
 
+    /// Replaces variable references in effective module settings.
+    /// Variables are looked up from built-ins (host.name, host.fqdn, host.address), host variables, group variables
+    /// and lastly, environment variables. Group variables are merged in the same order as other group settings.
+    /// Shell code in the `command` setting of generic modules is left as is, so e.g. `${HOME}` is expanded on the host.
+    /// Undefined variables are left as is, since older configurations can contain a literal `${`, e.g. in passwords.
+    fn interpolate_settings(host_id: &String, host_config: &mut HostSettings, all_groups: &Groups) {
+        let mut variables = HashMap::<String, String>::new();
+        for group_id in host_config.groups.iter() {
+            variables.extend(all_groups.groups[group_id].vars.clone());
+        }
+        variables.extend(host_config.vars.clone());
+        variables.insert(String::from("host.name"), host_id.clone());
+        variables.insert(String::from("host.fqdn"), host_config.fqdn.clone());
+        variables.insert(String::from("host.address"), host_config.address.clone());
+
+        let interpolate = |module_type: &str, module_id: &String, value: &String| {
+            string_manipulation::interpolate(value, |name| {
+                let result = variables.get(name).cloned().or_else(|| std::env::var(name).ok());
+                if result.is_none() {
+                    log::warn!("[{}] Undefined variable \"{}\" in {} {} is left as is", host_id, name, module_type, module_id);
+                }
+                result
+            })
+        };
+
+        let is_shell_command = |module_id: &String, module: &String, setting: &String| {
+            let module = if module.is_empty() { module_id } else { module };
+            setting == "command" && SHELL_COMMAND_MODULES.contains(&module.as_str())
+        };
+
+        for (monitor_id, monitor_config) in host_config.monitors.iter_mut() {
+            for (setting, value) in monitor_config.settings.iter_mut() {
+                if !is_shell_command(monitor_id, &monitor_config.module, setting) {
+                    *value = interpolate("monitor", monitor_id, value);
+                }
+            }
+        }
+
+        for (command_id, command_config) in host_config.commands.iter_mut() {
+            for (setting, value) in command_config.settings.iter_mut() {
+                if !is_shell_command(command_id, &command_config.module, setting) {
+                    *value = interpolate("command", command_id, value);
+                }
+            }
+        }
+
+        for (connector_id, connector_config) in host_config.connectors.iter_mut() {
+            for value in connector_config.settings.values_mut() {
+                *value = interpolate("connector", connector_id, value);
+            }
+        }
+    }
+
+    /// Returns IDs of hosts that were added, removed or whose effective configuration changed.
+    pub fn get_changed_hosts(old_hosts: &Hosts, new_hosts: &Hosts) -> Vec<String> {
+        let mut changed_hosts = old_hosts.hosts.keys()
+            .chain(new_hosts.hosts.keys())
+            .filter(|host_id| old_hosts.hosts.get(*host_id) != new_hosts.hosts.get(*host_id))
+            .cloned()
+            .collect::<Vec<String>>();
+
+        changed_hosts.sort();
+        changed_hosts.dedup();
+        changed_hosts
+    }
+
     fn is_default<T: Default + PartialEq>(t: &T) -> bool {
         t == &T::default()
     }
@@ -0,0 +1,230 @@
+use std::{
+    fs,
+    io,
+    path::Path,
+    path::PathBuf,
+};
+
+use serde_yaml::{Mapping, Value};
+
+use super::{MAIN_CONFIG_FILE, HOSTS_FILE, GROUPS_FILE};
+
+/// Version of the configuration file format. Increment when adding a migration.
+pub const CURRENT_CONFIG_VERSION: u32 = 2;
+
+const OLD_TEMPLATES_FILE: &str = "templates.yml";
+
+/// Migration from version N is at index N. Migrations operate on the raw YAML documents
+/// so they don't depend on the current configuration structs.
+const MIGRATIONS: [(&str, MigrationStep); CURRENT_CONFIG_VERSION as usize] = [
+    ("Rename templates to groups", migrate_v0_templates_to_groups),
+    ("Add config_version", migrate_v1_add_config_version),
+];
+
+type MigrationStep = fn(&mut ConfigDocuments) -> Result<(), String>;
+
+
+/// Raw contents of the configuration files.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigDocuments {
+    pub main_config: Value,
+    pub hosts: Value,
+    pub groups: Value,
+}
+
+impl ConfigDocuments {
+    pub fn parse(main_config: &str, hosts: &str, groups: &str) -> Result<Self, String> {
+        Ok(ConfigDocuments {
+            main_config: serde_yaml::from_str(main_config).map_err(|error| format!("{}: {}", MAIN_CONFIG_FILE, error))?,
+            hosts: serde_yaml::from_str(hosts).map_err(|error| format!("{}: {}", HOSTS_FILE, error))?,
+            groups: serde_yaml::from_str(groups).map_err(|error| format!("{}: {}", GROUPS_FILE, error))?,
+        })
+    }
+
+    /// Versions before 2 didn't have config_version. Version 0 used templates.yml instead of groups.yml.
+    pub fn get_version(&self, has_templates_file: bool) -> u32 {
+        match self.main_config.get("config_version").and_then(|version| version.as_u64()) {
+            Some(version) => version as u32,
+            None => if has_templates_file { 0 } else { 1 },
+        }
+    }
+}
+
+/// Upgrades configuration files in place if they are from an older version.
+/// Original files are copied to a backup directory before writing anything.
+/// Returns true if a migration was done.
+pub fn migrate(config_dir: &Path) -> io::Result<bool> {
+    let main_config_file_path = config_dir.join(MAIN_CONFIG_FILE);
+    let hosts_file_path = config_dir.join(HOSTS_FILE);
+    let old_templates_file_path = config_dir.join(OLD_TEMPLATES_FILE);
+    let has_templates_file = old_templates_file_path.exists();
+
+    let groups_file_path = if has_templates_file {
+        old_templates_file_path.clone()
+    }
+    else {
+        config_dir.join(GROUPS_FILE)
+    };
+
+    let mut documents = ConfigDocuments::parse(
+        &fs::read_to_string(&main_config_file_path)?,
+        &fs::read_to_string(&hosts_file_path)?,
+        &fs::read_to_string(&groups_file_path)?,
+    ).map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+
+    let version = documents.get_version(has_templates_file);
+    if version == CURRENT_CONFIG_VERSION {
+        return Ok(false);
+    }
+    else if version > CURRENT_CONFIG_VERSION {
+        let message = format!("Configuration version {} is newer than the supported version {}. Please upgrade Lightkeeper.",
+                              version, CURRENT_CONFIG_VERSION);
+        return Err(io::Error::new(io::ErrorKind::Other, message));
+    }
+
+    let backup_dir = backup(config_dir, version, &[&main_config_file_path, &hosts_file_path, &groups_file_path])?;
+    log::info!("Configuration backup created in {}", backup_dir.display());
+
+    migrate_documents(&mut documents, version).map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+
+    let to_yaml = |value: &Value| serde_yaml::to_string(value).map_err(|error| io::Error::new(io::ErrorKind::Other, error.to_string()));
+    fs::write(&main_config_file_path, to_yaml(&documents.main_config)?)?;
+    fs::write(&hosts_file_path, to_yaml(&documents.hosts)?)?;
+    fs::write(config_dir.join(GROUPS_FILE), to_yaml(&documents.groups)?)?;
+
+    if has_templates_file {
+        fs::remove_file(&old_templates_file_path)?;
+    }
+
+    log::info!("Configuration migrated from version {} to {}", version, CURRENT_CONFIG_VERSION);
+    Ok(true)
+}
+
+/// Runs all migrations starting from `from_version`.
+pub fn migrate_documents(documents: &mut ConfigDocuments, from_version: u32) -> Result<(), String> {
+    for (version, (description, migration)) in MIGRATIONS.iter().enumerate().skip(from_version as usize) {
+        log::info!("Migrating configuration from version {} to {}: {}", version, version + 1, description);
+        migration(documents).map_err(|error| format!("Migration from version {} failed: {}", version, error))?;
+    }
+    Ok(())
+}
+
+fn backup(config_dir: &Path, version: u32, files: &[&PathBuf]) -> io::Result<PathBuf> {
+    let timestamp = chrono::Local::now().format("%Y%m%d%H%M%S");
+    let backup_dir = config_dir.join("backups").join(format!("v{}-{}", version, timestamp));
+    fs::create_dir_all(&backup_dir)?;
+
+    for file_path in files {
+        fs::copy(file_path, backup_dir.join(file_path.file_name().unwrap()))?;
+    }
+    Ok(backup_dir)
+}
+
+/// Groups used to be called templates. Both the top-level key of the file and host-level references are renamed.
+fn migrate_v0_templates_to_groups(documents: &mut ConfigDocuments) -> Result<(), String> {
+    let groups_root = documents.groups.as_mapping_mut().ok_or("Invalid groups file")?;
+    rename_key(groups_root, "templates", "groups");
+
+    let hosts = documents.hosts.get_mut("hosts").and_then(|hosts| hosts.as_mapping_mut()).ok_or("Invalid hosts file")?;
+    for (_, host) in hosts.iter_mut() {
+        if let Some(host) = host.as_mapping_mut() {
+            rename_key(host, "templates", "groups");
+        }
+    }
+
+    Ok(())
+}
+
+fn migrate_v1_add_config_version(documents: &mut ConfigDocuments) -> Result<(), String> {
+    set_version(documents, 2)
+}
+
+fn set_version(documents: &mut ConfigDocuments, version: u32) -> Result<(), String> {
+    let main_config = documents.main_config.as_mapping_mut().ok_or("Invalid main configuration file")?;
+    main_config.insert(Value::from("config_version"), Value::from(version));
+    Ok(())
+}
+
+fn rename_key(mapping: &mut Mapping, old_key: &str, new_key: &str) {
+    if let Some(value) = mapping.remove(old_key) {
+        if !mapping.contains_key(new_key) {
+            mapping.insert(Value::from(new_key), value);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::{Configuration, Hosts, Groups};
+
+    fn read_fixture(version: u32) -> ConfigDocuments {
+        let fixture_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("test/fixtures/config/v{}", version));
+        let groups_file = if version == 0 { OLD_TEMPLATES_FILE } else { GROUPS_FILE };
+
+        ConfigDocuments::parse(
+            &fs::read_to_string(fixture_dir.join(MAIN_CONFIG_FILE)).unwrap(),
+            &fs::read_to_string(fixture_dir.join(HOSTS_FILE)).unwrap(),
+            &fs::read_to_string(fixture_dir.join(groups_file)).unwrap(),
+        ).unwrap()
+    }
+
+    #[test]
+    fn test_fixture_versions() {
+        assert_eq!(read_fixture(0).get_version(true), 0);
+        assert_eq!(read_fixture(1).get_version(false), 1);
+        assert_eq!(read_fixture(2).get_version(false), 2);
+    }
+
+    #[test]
+    fn test_each_step() {
+        for version in 0..CURRENT_CONFIG_VERSION {
+            let mut documents = read_fixture(version);
+            let (_, migration) = MIGRATIONS[version as usize];
+            migration(&mut documents).unwrap();
+            assert_eq!(documents, read_fixture(version + 1), "Migration from version {}", version);
+        }
+    }
+
+    #[test]
+    fn test_all_steps() {
+        for version in 0..CURRENT_CONFIG_VERSION {
+            let mut documents = read_fixture(version);
+            migrate_documents(&mut documents, version).unwrap();
+            assert_eq!(documents, read_fixture(CURRENT_CONFIG_VERSION), "Migration from version {}", version);
+        }
+    }
+
+    #[test]
+    fn test_current_version_is_valid() {
+        let documents = read_fixture(CURRENT_CONFIG_VERSION);
+        serde_yaml::from_value::<Configuration>(documents.main_config).unwrap();
+        serde_yaml::from_value::<Hosts>(documents.hosts).unwrap();
+        serde_yaml::from_value::<Groups>(documents.groups).unwrap();
+    }
+
+    #[test]
+    fn test_example_config_is_current() {
+        let main_config = serde_yaml::from_str::<Value>(include_str!("../../config.example.yml")).unwrap();
+        assert_eq!(main_config["config_version"].as_u64(), Some(CURRENT_CONFIG_VERSION as u64));
+    }
+
+    #[test]
+    fn test_migrate_files() {
+        let config_dir = std::env::temp_dir().join(format!("lightkeeper-migration-test-{}", std::process::id()));
+        let fixture_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("test/fixtures/config/v0");
+        fs::create_dir_all(&config_dir).unwrap();
+        for file_name in [MAIN_CONFIG_FILE, HOSTS_FILE, OLD_TEMPLATES_FILE] {
+            fs::copy(fixture_dir.join(file_name), config_dir.join(file_name)).unwrap();
+        }
+
+        assert!(migrate(&config_dir).unwrap());
+        assert!(!config_dir.join(OLD_TEMPLATES_FILE).exists());
+        assert_eq!(fs::read_dir(config_dir.join("backups")).unwrap().count(), 1);
+        // Already migrated.
+        assert!(!migrate(&config_dir).unwrap());
+
+        fs::remove_dir_all(&config_dir).unwrap();
+    }
+}
@@ -0,0 +1,125 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io,
+    path::Path,
+    sync::Arc,
+    sync::Mutex,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+
+use inotify::{Inotify, WatchMask};
+
+use super::{MAIN_CONFIG_FILE, HOSTS_FILE, GROUPS_FILE};
+
+/// Events are collected for this long before notifying. Editors often write files in multiple steps.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+
+/// Watches the configuration directory for changes made outside of the application.
+/// Watching stops when this is dropped.
+pub struct ConfigWatcher {
+    config_dir: String,
+    stop_flag: Arc<AtomicBool>,
+    /// Content hashes of the configuration files as the application last saw or wrote them.
+    known_hashes: Arc<Mutex<HashMap<String, String>>>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl ConfigWatcher {
+    /// Calls `on_change` from the watcher thread when any of the configuration files has changed and no more events
+    /// have been received during the last poll interval.
+    /// Writes acknowledged with `acknowledge_writes()` don't count as changes.
+    pub fn start<Callback>(config_dir: &String, on_change: Callback) -> io::Result<Self>
+        where Callback: Fn() + Send + 'static {
+
+        let mut inotify = Inotify::init()?;
+        // Watching the directory instead of the files, since editors and git commonly replace files instead of writing to them.
+        inotify.watches().add(
+            Path::new(config_dir),
+            WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO | WatchMask::CREATE | WatchMask::DELETE
+        )?;
+
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let thread_stop_flag = stop_flag.clone();
+        let known_hashes = Arc::new(Mutex::new(Self::hash_files(config_dir)));
+        let thread_known_hashes = known_hashes.clone();
+        let thread_config_dir = config_dir.clone();
+
+        let thread = thread::spawn(move || {
+            let mut buffer = [0; 4096];
+            let mut has_pending_changes = false;
+
+            while !thread_stop_flag.load(Ordering::Relaxed) {
+                thread::sleep(POLL_INTERVAL);
+
+                let has_new_changes = match inotify.read_events(&mut buffer) {
+                    Ok(events) => events.filter_map(|event| event.name)
+                                        .any(|name| [MAIN_CONFIG_FILE, HOSTS_FILE, GROUPS_FILE].iter().any(|file| name == *file)),
+                    Err(error) if error.kind() == io::ErrorKind::WouldBlock => false,
+                    Err(error) => {
+                        log::error!("Stopped watching configuration files: {}", error);
+                        return;
+                    }
+                };
+
+                if has_new_changes {
+                    has_pending_changes = true;
+                }
+                else if has_pending_changes {
+                    has_pending_changes = false;
+
+                    // Events are also received for the application's own writes.
+                    let current_hashes = Self::hash_files(&thread_config_dir);
+                    let mut known_hashes = thread_known_hashes.lock().unwrap();
+                    if *known_hashes != current_hashes {
+                        *known_hashes = current_hashes;
+                        drop(known_hashes);
+                        log::info!("Configuration files changed");
+                        on_change();
+                    }
+                }
+            }
+
+            log::debug!("Gracefully exiting configuration watcher thread");
+        });
+
+        log::debug!("Watching configuration directory {}", config_dir);
+
+        Ok(ConfigWatcher {
+            config_dir: config_dir.clone(),
+            stop_flag: stop_flag,
+            known_hashes: known_hashes,
+            thread: Some(thread),
+        })
+    }
+
+    /// Call after the application has written configuration files so the watcher doesn't report them as changed.
+    pub fn acknowledge_writes(&self) {
+        *self.known_hashes.lock().unwrap() = Self::hash_files(&self.config_dir);
+    }
+
+    fn hash_files(config_dir: &String) -> HashMap<String, String> {
+        [MAIN_CONFIG_FILE, HOSTS_FILE, GROUPS_FILE].iter().map(|file_name| {
+            // Missing files are also a state that can change.
+            let hash = fs::read(Path::new(config_dir).join(file_name)).map(sha256::digest).unwrap_or_default();
+            (file_name.to_string(), hash)
+        }).collect()
+    }
+
+    pub fn stop(&mut self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+
+        if let Some(thread) = self.thread.take() {
+            thread.join().unwrap();
+        }
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -7,31 +7,50 @@ use std::{
     thread,
 };
 
-use rayon::prelude::*;
+use std::time::Duration;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::timeout;
 
 use crate::Host;
-use crate::configuration::{CacheSettings, Hosts};
+use crate::configuration::{CacheSettings, ConnectionSettings, HostSettings, Hosts};
 use crate::file_handler;
 use crate::module::ModuleFactory;
-use crate::module::ModuleSpecification;
+use crate::module::{Metadata, ModuleSpecification};
 use crate::module::connection::*;
-use crate::cache::{Cache, CacheScope};
+use crate::cache::{keyring, Cache, CacheFileOptions, CacheScope, CacheSensitivity, CacheStatistics, PurgeFilter};
+use crate::utils::CommandBatch;
 
 pub type ResponseHandlerCallback = Box<dyn FnOnce(Vec<Result<ResponseMessage, String>>) + Send + 'static>;
 type ConnectorStates = HashMap<ModuleSpecification, Arc<Mutex<Connector>>>;
+/// Set once the connector is ready, so the request can be aborted if it times out.
+type AbortSlot = Arc<Mutex<Option<AbortHandle>>>;
+
 
+/// How long to wait for unfinished requests when exiting.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+/// Limits the size of a single batched command.
+const MAX_BATCH_SIZE: usize = 32;
 
-const MAX_WORKER_THREADS: usize = 8;
+/// Held while a request is being processed, including any blocking work that continues after the request was abandoned.
+struct RequestPermits {
+    _request_permit: OwnedSemaphorePermit,
+    _host_permit: Option<OwnedSemaphorePermit>,
+}
 
 
 // Default needs to be implemented because of Qt QObject requirements.
 #[derive(Default)]
 pub struct ConnectionManager {
-    stateful_connectors: Option<HashMap<String, ConnectorStates>>,
+    /// Shared with the request processing thread so that hosts can be reconfigured without restarting it.
+    stateful_connectors: Arc<Mutex<HashMap<String, ConnectorStates>>>,
     request_receiver: Option<mpsc::Receiver<ConnectorRequest>>,
     request_sender_prototype: Option<mpsc::Sender<ConnectorRequest>>,
     receiver_thread: Option<thread::JoinHandle<()>>,
+    /// Shared with the request processing thread so that it can be inspected and purged while running.
+    command_cache: Option<Arc<Mutex<Cache<String, ResponseMessage>>>>,
     cache_settings: CacheSettings,
+    connection_settings: ConnectionSettings,
 
     // Shared resources.
     module_factory: Arc<ModuleFactory>,
@@ -40,65 +59,101 @@ pub struct ConnectionManager {
 impl ConnectionManager {
     pub fn new(module_factory: Arc<ModuleFactory>) -> Self {
         ConnectionManager {
-            stateful_connectors: Some(HashMap::new()),
             module_factory: module_factory,
             ..Default::default()
         }
     }
 
-    pub fn configure(&mut self, hosts_config: &Hosts, cache_settings: &CacheSettings) {
+    pub fn configure(&mut self, hosts_config: &Hosts, cache_settings: &CacheSettings, connection_settings: &ConnectionSettings) {
         if self.receiver_thread.is_some() {
             self.stop();
         }
 
-        self.stateful_connectors = Some(HashMap::new());
         self.cache_settings = cache_settings.clone();
-        let stateful_connectors = self.stateful_connectors.as_mut().unwrap();
+        self.connection_settings = connection_settings.clone();
+
+        let mut stateful_connectors = self.stateful_connectors.lock().unwrap();
+        stateful_connectors.clear();
 
         for (host_id, host_config) in hosts_config.hosts.iter() {
-            stateful_connectors.entry(host_id.clone()).or_insert(HashMap::new());
-            let host_connectors = stateful_connectors.get_mut(host_id).unwrap();
+            stateful_connectors.insert(host_id.clone(), self.create_host_connectors(host_config));
+        }
+        drop(stateful_connectors);
 
-            for (monitor_id, monitor_config) in host_config.monitors.iter() {
-                let monitor_spec = ModuleSpecification::new(monitor_id.as_str(), monitor_config.version.as_str());
-                let monitor = self.module_factory.new_monitor(&monitor_spec, &monitor_config.settings);
+        let (sender, receiver) = mpsc::channel::<ConnectorRequest>();
+        self.request_receiver = Some(receiver);
+        self.request_sender_prototype = Some(sender);
+    }
 
-                if let Some(mut connector_spec) = monitor.get_connector_spec() {
-                    connector_spec.module_type = String::from("connector");
+    /// Replaces connectors of the given hosts while the request processing thread keeps running.
+    /// Connections of other hosts are left untouched. Hosts missing from `hosts_config` are removed.
+    pub fn reconfigure_hosts(&mut self, hosts_config: &Hosts, host_ids: &[String]) {
+        for host_id in host_ids.iter() {
+            let new_connectors = hosts_config.hosts.get(host_id).map(|host_config| self.create_host_connectors(host_config));
+            let mut stateful_connectors = self.stateful_connectors.lock().unwrap();
+
+            // Ongoing requests keep their own reference to the old connector, so it is dropped after they finish.
+            match new_connectors {
+                Some(new_connectors) => {
+                    log::debug!("[{}] Reconfiguring connectors", host_id);
+                    stateful_connectors.insert(host_id.clone(), new_connectors);
+                },
+                None => {
+                    log::debug!("[{}] Removing connectors", host_id);
+                    stateful_connectors.remove(host_id);
+                }
+            }
+        }
+    }
 
-                    let connector_settings = match host_config.connectors.get(&connector_spec.id) {
-                        Some(config) => config.settings.clone(),
-                        None => HashMap::new(),
-                    };
+    fn create_host_connectors(&self, host_config: &HostSettings) -> ConnectorStates {
+        let mut host_connectors = ConnectorStates::new();
 
-                    let connector = self.module_factory.new_connector(&connector_spec, &connector_settings);
-                    if !connector.get_metadata_self().is_stateless {
-                        host_connectors.entry(connector_spec).or_insert_with(|| Arc::new(Mutex::new(connector)));
-                    }
+        for (monitor_id, monitor_config) in host_config.monitors.iter() {
+            let monitor_spec = monitor_config.get_module_spec(monitor_id);
+            // Invalid modules are reported by MonitorManager.
+            let monitor = match self.module_factory.new_monitor(&monitor_spec, &monitor_config.get_module_settings(monitor_id)) {
+                Ok(monitor) => monitor,
+                Err(_) => continue,
+            };
+
+            if let Some(mut connector_spec) = monitor.get_connector_spec() {
+                connector_spec.module_type = String::from("connector");
+
+                let connector_settings = match host_config.connectors.get(&connector_spec.id) {
+                    Some(config) => config.settings.clone(),
+                    None => HashMap::new(),
+                };
+
+                let connector = self.module_factory.new_connector(&connector_spec, &connector_settings);
+                if !connector.get_metadata_self().is_stateless {
+                    host_connectors.entry(connector_spec).or_insert_with(|| Arc::new(Mutex::new(connector)));
                 }
             }
+        }
 
-            for (command_id, command_config) in host_config.commands.iter() {
-                let command_spec = ModuleSpecification::new(command_id, &command_config.version);
-                let command = self.module_factory.new_command(&command_spec, &command_config.settings);
-
-                if let Some(connector_spec) = command.get_connector_spec() {
-                    let connector_settings = match host_config.connectors.get(&connector_spec.id) {
-                        Some(config) => config.settings.clone(),
-                        None => HashMap::new(),
-                    };
+        for (command_id, command_config) in host_config.commands.iter() {
+            let command_spec = command_config.get_module_spec(command_id);
+            // Invalid modules are reported by CommandHandler.
+            let command = match self.module_factory.new_command(&command_spec, &command_config.get_module_settings(command_id)) {
+                Ok(command) => command,
+                Err(_) => continue,
+            };
+
+            if let Some(connector_spec) = command.get_connector_spec() {
+                let connector_settings = match host_config.connectors.get(&connector_spec.id) {
+                    Some(config) => config.settings.clone(),
+                    None => HashMap::new(),
+                };
 
-                    let connector = self.module_factory.new_connector(&connector_spec, &connector_settings);
-                    if !connector.get_metadata_self().is_stateless {
-                        host_connectors.entry(connector_spec).or_insert_with(|| Arc::new(Mutex::new(connector)));
-                    }
+                let connector = self.module_factory.new_connector(&connector_spec, &connector_settings);
+                if !connector.get_metadata_self().is_stateless {
+                    host_connectors.entry(connector_spec).or_insert_with(|| Arc::new(Mutex::new(connector)));
                 }
             }
         }
 
-        let (sender, receiver) = mpsc::channel::<ConnectorRequest>();
-        self.request_receiver = Some(receiver);
-        self.request_sender_prototype = Some(sender);
+        host_connectors
     }
 
     pub fn new_request_sender(&mut self) -> mpsc::Sender<ConnectorRequest> {
@@ -106,15 +161,65 @@ impl ConnectionManager {
     }
 
     pub fn start_processing_requests(&mut self) {
+        self.load_cache(&self.cache_settings.clone());
+
         let thread = Self::process_requests(
-            self.stateful_connectors.take().unwrap(),
+            self.stateful_connectors.clone(),
             self.request_receiver.take().unwrap(),
             self.module_factory.clone(),
-            self.cache_settings.clone()
+            self.command_cache.clone().unwrap(),
+            self.cache_settings.clone(),
+            self.connection_settings.clone()
         );
         self.receiver_thread = Some(thread);
     }
 
+    /// Reads the cache file. Done automatically when starting to process requests.
+    pub fn load_cache(&mut self, cache_settings: &CacheSettings) {
+        self.cache_settings = cache_settings.clone();
+        self.command_cache = Some(Arc::new(Mutex::new(Self::initialize_cache(cache_settings.clone()))));
+    }
+
+    pub fn get_cache_statistics(&self) -> CacheStatistics {
+        match self.command_cache.as_ref() {
+            Some(command_cache) => command_cache.lock().unwrap().get_statistics(),
+            None => CacheStatistics::default(),
+        }
+    }
+
+    /// Returns the number of removed entries. Cache file is updated immediately.
+    pub fn purge_cache(&self, target: &CachePurgeTarget) -> usize {
+        let command_cache = match self.command_cache.as_ref() {
+            Some(command_cache) => command_cache,
+            None => return 0,
+        };
+
+        let filter = match target {
+            CachePurgeTarget::All => PurgeFilter::KeyPrefix(String::new()),
+            // Global entries aren't specific to any host and are left alone.
+            CachePurgeTarget::Host(host_id) => PurgeFilter::KeyPrefix(format!("{}|", host_id)),
+            CachePurgeTarget::Module(module_id) => PurgeFilter::Source(module_id.clone()),
+            CachePurgeTarget::KeyPrefix(prefix) => PurgeFilter::KeyPrefix(prefix.clone()),
+        };
+
+        let mut command_cache = command_cache.lock().unwrap();
+        let count = command_cache.purge_matching(&filter);
+        log::info!("Purged {} cache entries ({:?})", count, target);
+
+        Self::write_cache(&self.cache_settings, &command_cache);
+        count
+    }
+
+    fn write_cache(cache_settings: &CacheSettings, command_cache: &Cache<String, ResponseMessage>) {
+        if cache_settings.enable_cache {
+            match command_cache.write_to_disk() {
+                Ok(count) => log::debug!("Wrote {} entries to cache file", count),
+                // Failing to write the file is not critical.
+                Err(error) => log::error!("{}", error),
+            }
+        }
+    }
+
     pub fn stop(&mut self) {
         self.new_request_sender()
             .send(ConnectorRequest::exit_token())
@@ -130,16 +235,19 @@ impl ConnectionManager {
     }
 
     fn process_requests(
-        mut stateful_connectors: HashMap<String, ConnectorStates>,
+        stateful_connectors: Arc<Mutex<HashMap<String, ConnectorStates>>>,
         receiver: mpsc::Receiver<ConnectorRequest>,
         module_factory: Arc<ModuleFactory>,
-        cache_settings: CacheSettings) -> thread::JoinHandle<()> {
+        command_cache: Arc<Mutex<Cache<String, ResponseMessage>>>,
+        cache_settings: CacheSettings,
+        connection_settings: ConnectionSettings) -> thread::JoinHandle<()> {
 
         thread::spawn(move || {
-            let worker_pool = rayon::ThreadPoolBuilder::new().num_threads(MAX_WORKER_THREADS).build().unwrap();
-            log::debug!("Created worker pool with {} threads", MAX_WORKER_THREADS);
-
-            let command_cache = Arc::new(Mutex::new(Self::initialize_cache(cache_settings.clone())));
+            let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+            let request_limiter = Arc::new(Semaphore::new(connection_settings.max_concurrent_requests.max(1)));
+            let mut host_limiters = HashMap::<String, Arc<Semaphore>>::new();
+            log::debug!("Processing at most {} requests at the same time, {} per host",
+                        connection_settings.max_concurrent_requests, connection_settings.max_concurrent_requests_per_host);
 
             loop {
                 let request = match receiver.recv() {
@@ -153,86 +261,229 @@ impl ConnectionManager {
                 if request.request_type == RequestType::Exit {
                     log::debug!("Gracefully exiting connection manager thread");
 
-                    if cache_settings.enable_cache {
-                        match command_cache.lock().unwrap().write_to_disk() {
-                            Ok(count) => log::debug!("Wrote {} entries to cache file", count),
-                            // Failing to write the file is not critical.
-                            Err(error) => log::error!("{}", error),
-                        }
-                    }
+                    Self::write_cache(&cache_settings, &command_cache.lock().unwrap());
 
+                    // Requests that are stuck, e.g. because of an unresponsive host, won't delay exiting.
+                    runtime.shutdown_timeout(SHUTDOWN_TIMEOUT);
                     return;
                 }
 
+                let (request, response_handler) = request.split();
+
+                // Handled before spawning other requests so that requests sent after this one won't get evicted responses.
+                if request.request_type == RequestType::EvictCache {
+                    Self::evict_cache_entries(&request, &module_factory, &mut command_cache.lock().unwrap());
+                    continue;
+                }
+
                 // Requests with no connector dependency.
                 if request.connector_spec.is_none() {
-                    (request.response_handler)(Vec::new());
+                    (response_handler)(Vec::new());
+                    continue;
                 }
-                else {
-                    let mut connector_spec = request.connector_spec.as_ref().unwrap().clone();
-                    connector_spec.module_type = String::from("connector");
-                    let connector_metadata = module_factory.get_connector_module_metadata(&connector_spec);
-                    let request_messages = request.messages.clone();
-
-                    // Stateless connectors.
-                    if connector_metadata.is_stateless {
-                        // Wrapping to Arc<Mutex<>> to allow passing to Self::process_request.
-                        let mutex_request = Arc::new(Mutex::new(request));
-
-                        worker_pool.install(|| {
-                            let responses = request_messages.par_iter().map(|request_message| {
-                                if request_message.is_empty() {
-                                    return Ok(ResponseMessage::empty());
-                                }
-
-                                log::debug!("Worker {} processing a stateless request", rayon::current_thread_index().unwrap());
-                                let connector = Arc::new(Mutex::new(module_factory.new_connector(&connector_spec, &HashMap::new())));
-                                Self::process_request(mutex_request.clone(), request_message, connector.clone(), command_cache.clone())
-                            }).collect();
-
-                            let request = Arc::try_unwrap(mutex_request).unwrap().into_inner().unwrap();
-                            (request.response_handler)(responses);
-                        });
+
+                // Host limiters are never removed, but there's only one per host.
+                let host_limiter = host_limiters.entry(request.host.name.clone())
+                                                .or_insert_with(|| Arc::new(Semaphore::new(connection_settings.max_concurrent_requests_per_host.max(1))))
+                                                .clone();
+
+                let request = Arc::new(request);
+                let request_limiter = request_limiter.clone();
+                let request_timeout = Duration::from_secs(connection_settings.request_timeout);
+                let command_timeout = Duration::from_secs(connection_settings.command_timeout);
+                let stateful_connectors = stateful_connectors.clone();
+                let module_factory = module_factory.clone();
+                let command_cache = command_cache.clone();
+
+                runtime.spawn(async move {
+                    let host_name = request.host.name.clone();
+
+                    // Semaphores are fair, so every host waiting for a global permit gets its turn in order.
+                    // Per-host limit makes sure that a host with a lot of requests can't fill the whole queue.
+                    // Long running requests aren't limited per host, so they won't keep the host's monitors from refreshing.
+                    let host_permit = if request.long_running {
+                        Ok(None)
                     }
-                    // Stateful connectors.
                     else {
-                        // TODO: This will block the thread unnecessarily. Need better solution. Imagine
-                        // MAX_WORKERS amount of requests sequentially for the same host and connector.
-                        let host_connectors = stateful_connectors.get_mut(&request.host.name).unwrap();
-                        let connector_mutex = host_connectors.get_mut(&connector_spec).unwrap();
-
-                        let mut connector = connector_mutex.lock().unwrap();
-                        if !connector.is_connected() {
-                            if let Err(error) = connector.connect(&request.host.ip_address) {
-                                log::error!("[{}] Error while connecting {}: {}", request.host.name, request.host.ip_address, error);
-                                (request.response_handler)(vec![Err(format!("Error while connecting: {}", error))]);
-                                continue;
+                        timeout(request_timeout, host_limiter.acquire_owned()).await.map(|permit| permit.ok())
+                    };
+
+                    let responses = match host_permit {
+                        Err(_) => {
+                            log::warn!("[{}] Host is busy, dropping request for {}", host_name, request.source_id);
+                            vec![Err(format!("Timed out after {} seconds waiting for previous requests to finish", request_timeout.as_secs()))]
+                        },
+                        Ok(host_permit) => {
+                            let permits = Arc::new(RequestPermits {
+                                _request_permit: request_limiter.acquire_owned().await.unwrap(),
+                                _host_permit: host_permit,
+                            });
+
+                            let time_limit = match request.long_running {
+                                true if command_timeout.is_zero() => None,
+                                true => Some(command_timeout),
+                                false => Some(request_timeout),
+                            };
+
+                            let abort_slot = AbortSlot::default();
+                            let work = Self::process_connector_request(request.clone(), stateful_connectors, module_factory, command_cache, permits, abort_slot.clone());
+                            match time_limit {
+                                None => work.await,
+                                Some(time_limit) => match timeout(time_limit, work).await {
+                                    Ok(responses) => responses,
+                                    Err(_) => {
+                                        // Otherwise, blocking work would keep running and keep the connector locked.
+                                        if let Some(abort) = abort_slot.lock().unwrap().take() {
+                                            log::warn!("[{}] Request timed out, aborting connection", host_name);
+                                            abort();
+                                        }
+                                        else {
+                                            log::warn!("[{}] Request timed out", host_name);
+                                        }
+                                        vec![Err(format!("Request timed out after {} seconds", time_limit.as_secs()))]
+                                    },
+                                },
                             }
                         }
-                        drop(connector);
-
-                        let request_mutex = Arc::new(Mutex::new(request));
-                        worker_pool.install(|| {
-                            let responses = request_messages.iter().map(|request_message| {
-                                if request_message.is_empty() {
-                                    return Ok(ResponseMessage::empty());
-                                }
-
-                                log::debug!("Worker {} processing a stateful request", rayon::current_thread_index().unwrap());
-                                Self::process_request(request_mutex.clone(), request_message, connector_mutex.clone(), command_cache.clone())
-                            }).collect();
-
-                            let request = Arc::try_unwrap(request_mutex).unwrap().into_inner().unwrap();
-                            (request.response_handler)(responses);
-                        });
+                    };
+
+                    // Handlers can do some processing, so they don't run on the async worker threads.
+                    let _ = tokio::task::spawn_blocking(move || (response_handler)(responses)).await;
+                });
+            }
+        })
+    }
+
+    /// Returns responses in the same order as request messages.
+    /// Dropping the future stops waiting for the responses, but blocking work in progress keeps the permits until it's done.
+    async fn process_connector_request(request: Arc<RequestDetails>, stateful_connectors: Arc<Mutex<HashMap<String, ConnectorStates>>>,
+                                       module_factory: Arc<ModuleFactory>, command_cache: Arc<Mutex<Cache<String, ResponseMessage>>>,
+                                       permits: Arc<RequestPermits>, abort_slot: AbortSlot) -> Vec<Result<ResponseMessage, String>> {
+
+        let mut connector_spec = request.connector_spec.as_ref().unwrap().clone();
+        connector_spec.module_type = String::from("connector");
+        let connector_metadata = module_factory.get_connector_module_metadata(&connector_spec);
+
+        // Stateless connectors.
+        if connector_metadata.is_stateless {
+            let mut responses = Vec::new();
+            for request_message in request.messages.iter() {
+                if request_message.is_empty() {
+                    responses.push(Ok(ResponseMessage::empty()));
+                    continue;
+                }
+
+                let connector = Arc::new(Mutex::new(module_factory.new_connector(&connector_spec, &HashMap::new())));
+                responses.push(Self::process_request(&request, &connector_metadata, request_message, connector, &command_cache, &permits).await);
+            }
+            return responses;
+        }
+
+        // Stateful connectors.
+        let connector_mutex = match stateful_connectors.lock().unwrap()
+                                                   .get(&request.host.name)
+                                                   .and_then(|host_connectors| host_connectors.get(&connector_spec)) {
+            Some(connector_mutex) => connector_mutex.clone(),
+            None => {
+                // Host was removed or reconfigured after the request was sent.
+                log::warn!("[{}] Connector {} is not available", request.host.name, connector_spec);
+                return vec![Err(format!("Connector {} is not available", connector_spec))];
+            }
+        };
+
+        let connector = connector_mutex.clone();
+        let request_clone = request.clone();
+        let connect_result = Self::run_blocking(&permits, move || {
+            let mut connector = connector.lock().unwrap();
+            if !connector.is_connected() {
+                if let Err(error) = connector.connect(&request_clone.host.ip_address) {
+                    log::error!("[{}] Error while connecting {}: {}", request_clone.host.name, request_clone.host.ip_address, error);
+                    return Err(format!("Error while connecting: {}", error));
+                }
+            }
+            *abort_slot.lock().unwrap() = connector.get_abort_handle();
+            Ok(connector.supports_batching())
+        }).await.and_then(|result| result);
+
+        let supports_batching = match connect_result {
+            Ok(supports_batching) => supports_batching,
+            Err(error) => return vec![Err(error)],
+        };
+
+        if supports_batching && request.request_type == RequestType::Command && request.messages.len() > 1 {
+            return Self::process_batch_request(&request, &connector_metadata, connector_mutex, &command_cache, &permits).await;
+        }
+
+        let mut responses = Vec::new();
+        for request_message in request.messages.iter() {
+            if request_message.is_empty() {
+                responses.push(Ok(ResponseMessage::empty()));
+                continue;
+            }
+
+            responses.push(Self::process_request(&request, &connector_metadata, request_message, connector_mutex.clone(), &command_cache, &permits).await);
+        }
+        responses
+    }
+
+    /// Runs blocking work, e.g. connecting or file transfers, in a separate thread.
+    /// The permits are held until the work is done even if the request is abandoned, so the limits also cover stuck work.
+    async fn run_blocking<T: Send + 'static>(permits: &Arc<RequestPermits>, work: impl FnOnce() -> T + Send + 'static) -> Result<T, String> {
+        let permits = permits.clone();
+        tokio::task::spawn_blocking(move || {
+            let _permits = permits;
+            work()
+        }).await.map_err(|error| format!("Request failed: {}", error))
+    }
+
+    /// Uses the non-blocking version of the connector if available.
+    /// Connector is locked only while starting, so requests that don't need to block don't have to wait for each other.
+    async fn send_message(request_message: &str, connector: Arc<Mutex<Connector>>, permits: &Arc<RequestPermits>) -> Result<ResponseMessage, String> {
+        let connector_clone = connector.clone();
+        let message = request_message.to_string();
+        let response_future = Self::run_blocking(permits, move || connector_clone.lock().unwrap().send_message_async(&message)).await?;
+
+        if let Some(response_future) = response_future {
+            match response_future.await {
+                Ok(response) => return Ok(response),
+                Err(error) => {
+                    let connector_clone = connector.clone();
+                    let is_connected = Self::run_blocking(permits, move || connector_clone.lock().unwrap().is_connected()).await?;
+                    // Otherwise, the message wasn't sent at all and the blocking version will reconnect and try again.
+                    if is_connected {
+                        return Err(error);
                     }
+                    log::warn!("{}", error);
                 }
             }
-        })
+        }
+
+        let message = request_message.to_string();
+        Self::run_blocking(permits, move || connector.lock().unwrap().send_message(&message)).await?
     }
 
     fn initialize_cache(cache_settings: CacheSettings) -> Cache<String, ResponseMessage> {
-        let mut new_command_cache = Cache::<String, ResponseMessage>::new(cache_settings.time_to_live, cache_settings.initial_value_time_to_live);
+        let mut file_options = CacheFileOptions {
+            format: cache_settings.file_format.clone(),
+            encryption_key: None,
+            max_file_size: cache_settings.max_file_size_kb * 1024,
+            memory_only: false,
+        };
+
+        if cache_settings.enable_cache && cache_settings.encrypt_file {
+            match keyring::get_or_create_key() {
+                Ok(key) => file_options.encryption_key = Some(key),
+                Err(error) => {
+                    // Plaintext file is never written if encryption was requested.
+                    log::error!("Couldn't get cache encryption key, keeping cache only in memory: {}", error);
+                    file_options.memory_only = true;
+                }
+            }
+        }
+
+        let mut new_command_cache = Cache::<String, ResponseMessage>::new(cache_settings.time_to_live, cache_settings.initial_value_time_to_live)
+                                          .with_max_entries(cache_settings.max_entries)
+                                          .with_file_options(file_options);
 
         if cache_settings.enable_cache {
             match new_command_cache.read_from_disk() {
@@ -252,24 +503,17 @@ impl ConnectionManager {
     }
 
 
-    fn process_request(request: Arc<Mutex<ConnectorRequest>>, request_message: &String, connector: Arc<Mutex<Connector>>,
-                       command_cache: Arc<Mutex<Cache<String, ResponseMessage>>>) -> Result<ResponseMessage, String> { 
-
-        let request = request.lock().unwrap();
-        let mut connector = connector.lock().unwrap();
-        let mut command_cache = command_cache.lock().unwrap();
+    /// Cache is locked only while reading and writing it, so other requests aren't blocked while this one is waiting for the connector.
+    async fn process_request(request: &Arc<RequestDetails>, connector_metadata: &Metadata, request_message: &String, connector: Arc<Mutex<Connector>>,
+                             command_cache: &Arc<Mutex<Cache<String, ResponseMessage>>>, permits: &Arc<RequestPermits>) -> Result<ResponseMessage, String> {
 
         match &request.request_type {
             RequestType::Command => {
                 log::debug!("[{}] Processing command: {}", request.host.name, request_message);
 
-                let cache_key = match connector.get_metadata_self().cache_scope {
-                    CacheScope::Global => format!("{}|{}", connector.get_module_spec(), request_message),
-                    CacheScope::Host => format!("{}|{}|{}", request.host.name, connector.get_module_spec(), request_message),
-                };
-
+                let cache_key = Self::get_cache_key_for_metadata(request, connector_metadata, request_message);
                 let cached_response = if request.cache_policy == CachePolicy::OnlyCache || request.cache_policy == CachePolicy::PreferCache {
-                    command_cache.get(&cache_key)
+                    Self::get_cached_response(request, &mut command_cache.lock().unwrap(), &cache_key, request_message)
                 }
                 else {
                     None
@@ -284,55 +528,176 @@ impl ConnectionManager {
                         return Ok(ResponseMessage::not_found());
                     }
 
-                    let response_result = connector.send_message(request_message);
+                    let response_result = Self::send_message(request_message, connector, permits).await;
 
-                    if response_result.is_ok() {
-                        let response = response_result.as_ref().unwrap().clone();
-                        if response.return_code != 0 {
-                            log::debug!("Command returned non-zero exit code: {}", response.return_code)
-                        }
-                        else {
-                            // Doesn't cache failed commands.
-                            let mut cached_response = response.clone();
-                            cached_response.is_from_cache = true;
-                            command_cache.insert(cache_key, cached_response);
-                        }
-                        Ok(response)
-                    }
-                    else {
-                        response_result
+                    if let Ok(response) = response_result.as_ref() {
+                        Self::cache_response(request, &mut command_cache.lock().unwrap(), cache_key, request_message, response);
                     }
+                    response_result
                 }
             },
             RequestType::Download => {
                 log::debug!("[{}] Downloading file: {}", request.host.name, request_message);
-                match connector.download_file(request_message) {
-                    Ok((metadata, contents)) => {
-                        match file_handler::create_file(&request.host, request_message, metadata, contents) {
-                            Ok(file_path) => Ok(ResponseMessage::new_success(file_path)),
-                            Err(error) => Err(error.to_string()),
-                        }
-                    },
-                    Err(error) => Err(error.to_string()),
-                }
+                let request = request.clone();
+                let request_message = request_message.clone();
+                Self::run_blocking(permits, move || {
+                    match connector.lock().unwrap().download_file(&request_message) {
+                        Ok((metadata, contents)) => {
+                            match file_handler::create_file(&request.host, &request_message, metadata, contents) {
+                                Ok(file_path) => Ok(ResponseMessage::new_success(file_path)),
+                                Err(error) => Err(error.to_string()),
+                            }
+                        },
+                        Err(error) => Err(error.to_string()),
+                    }
+                }).await?
             },
             RequestType::Upload => {
-                let local_file_path = request_message;
+                let local_file_path = request_message.clone();
                 log::debug!("[{}] Uploading file: {}", request.host.name, local_file_path);
-                match file_handler::read_file(local_file_path) {
-                    Ok((metadata, contents)) => {
-                        let result = connector.upload_file(&metadata, contents);
+                Self::run_blocking(permits, move || {
+                    match file_handler::read_file(&local_file_path) {
+                        Ok((metadata, contents)) => {
+                            let result = connector.lock().unwrap().upload_file(&metadata, contents);
+
+                            match result {
+                                Ok(_) => Ok(ResponseMessage::empty()),
+                                Err(error) => Err(error.to_string()),
+                            }
+                        },
+                        Err(error) => Err(error.to_string()),
+                    }
+                }).await?
+            },
+            // Exit and EvictCache are handled earlier.
+            RequestType::Exit | RequestType::EvictCache => panic!(),
+        }
+    }
 
-                        match result {
-                            Ok(_) => Ok(ResponseMessage::empty()),
-                            Err(error) => Err(error.to_string()),
-                        }
-                    },
-                    Err(error) => Err(error.to_string()),
+    /// Sends all commands of the request that aren't cached in as few messages as possible.
+    /// If a batch fails or its output is incomplete, the unfinished commands are sent separately.
+    async fn process_batch_request(request: &Arc<RequestDetails>, connector_metadata: &Metadata, connector: Arc<Mutex<Connector>>,
+                                   command_cache: &Arc<Mutex<Cache<String, ResponseMessage>>>, permits: &Arc<RequestPermits>) -> Vec<Result<ResponseMessage, String>> {
+
+        let request_messages = &request.messages;
+        let mut responses = request_messages.iter().map(|request_message| {
+            if request_message.is_empty() {
+                return Some(Ok(ResponseMessage::empty()));
+            }
+
+            if request.cache_policy == CachePolicy::OnlyCache || request.cache_policy == CachePolicy::PreferCache {
+                let cache_key = Self::get_cache_key_for_metadata(request, connector_metadata, request_message);
+                if let Some(cached_response) = Self::get_cached_response(request, &mut command_cache.lock().unwrap(), &cache_key, request_message) {
+                    log::debug!("[{}] Using cached response for command {}", request.host.name, request_message);
+                    return Some(Ok(cached_response));
                 }
-            },
-            // Exit is handled earlier.
-            RequestType::Exit => panic!(),
+            }
+
+            if request.cache_policy == CachePolicy::OnlyCache {
+                return Some(Ok(ResponseMessage::not_found()));
+            }
+
+            None
+        }).collect::<Vec<_>>();
+
+        // Same command can be requested by multiple monitors, but it's executed only once. Order is preserved.
+        let mut pending_messages = Vec::<String>::new();
+        for (response, request_message) in responses.iter().zip(request_messages.iter()) {
+            if response.is_none() && !pending_messages.contains(request_message) {
+                pending_messages.push(request_message.clone());
+            }
+        }
+
+        let mut new_responses = HashMap::<String, Result<ResponseMessage, String>>::new();
+        for batch_messages in pending_messages.chunks(MAX_BATCH_SIZE) {
+            let mut batch_results = vec![None; batch_messages.len()];
+
+            if batch_messages.len() > 1 {
+                let batch = CommandBatch::new(batch_messages.to_vec());
+                log::debug!("[{}] Processing {} commands in a batch", request.host.name, batch_messages.len());
+
+                match Self::send_message(&batch.to_script(), connector.clone(), permits).await {
+                    Ok(response) => batch_results = batch.split_output(&response.message),
+                    Err(error) => log::error!("[{}] Batched command failed, sending commands separately: {}", request.host.name, error),
+                }
+            }
+
+            for (request_message, batch_result) in batch_messages.iter().zip(batch_results) {
+                let response_result = match batch_result {
+                    Some((output, return_code)) => Ok(ResponseMessage::new(output, return_code)),
+                    None => {
+                        log::debug!("[{}] Processing command: {}", request.host.name, request_message);
+                        Self::send_message(request_message, connector.clone(), permits).await
+                    }
+                };
+
+                if let Ok(response) = response_result.as_ref() {
+                    let cache_key = Self::get_cache_key_for_metadata(request, connector_metadata, request_message);
+                    Self::cache_response(request, &mut command_cache.lock().unwrap(), cache_key, request_message, response);
+                }
+                new_responses.insert(request_message.clone(), response_result);
+            }
+        }
+
+        responses.iter_mut().zip(request_messages.iter()).map(|(response, request_message)| {
+            response.take().unwrap_or_else(|| new_responses[request_message].clone())
+        }).collect()
+    }
+
+    fn get_cache_key_for_metadata(request: &RequestDetails, connector_metadata: &Metadata, request_message: &String) -> String {
+        match connector_metadata.cache_scope {
+            CacheScope::Global => format!("{}|{}", connector_metadata.module_spec, request_message),
+            CacheScope::Host => format!("{}|{}|{}", request.host.name, connector_metadata.module_spec, request_message),
+        }
+    }
+
+    /// Removes cached responses of the request's messages. Only host-specific entries are removed,
+    /// since a command run on one host doesn't change global entries.
+    fn evict_cache_entries(request: &RequestDetails, module_factory: &ModuleFactory, command_cache: &mut Cache<String, ResponseMessage>) {
+        let mut connector_spec = match request.connector_spec.clone() {
+            Some(connector_spec) => connector_spec,
+            None => return,
+        };
+        connector_spec.module_type = String::from("connector");
+
+        let connector_metadata = module_factory.get_connector_module_metadata(&connector_spec);
+        if let CacheScope::Global = connector_metadata.cache_scope {
+            return;
+        }
+
+        let mut count = 0;
+        for request_message in request.messages.iter() {
+            if command_cache.remove(&Self::get_cache_key_for_metadata(request, &connector_metadata, request_message)) {
+                count += 1;
+            }
+        }
+        log::debug!("[{}] Evicted {} cached responses of {}", request.host.name, count, request.source_id);
+    }
+
+    fn get_cached_response(request: &RequestDetails, command_cache: &mut Cache<String, ResponseMessage>,
+                           cache_key: &String, request_message: &String) -> Option<ResponseMessage> {
+        command_cache.lookup(cache_key, request.get_source_id(request_message)).map(|(mut response, age)| {
+            response.cache_age = Some(age.as_secs());
+            response
+        })
+    }
+
+    fn cache_response(request: &RequestDetails, command_cache: &mut Cache<String, ResponseMessage>,
+                      cache_key: String, request_message: &String, response: &ResponseMessage) {
+        if response.return_code != 0 {
+            log::debug!("Command returned non-zero exit code: {}", response.return_code)
+        }
+        else {
+            // Doesn't cache failed commands.
+            let mut cached_response = response.clone();
+            cached_response.is_from_cache = true;
+
+            if request.cache_sensitivity == CacheSensitivity::Sensitive {
+                command_cache.insert_memory_only(cache_key, cached_response, request.get_source_id(request_message));
+            }
+            else {
+                command_cache.insert(cache_key, cached_response, request.get_source_id(request_message));
+            }
         }
     }
 }
@@ -345,18 +710,53 @@ pub struct ConnectorRequest {
     pub request_type: RequestType,
     pub response_handler: ResponseHandlerCallback,
     pub cache_policy: CachePolicy,
+    /// Sensitive responses aren't written to the cache file.
+    pub cache_sensitivity: CacheSensitivity,
+    /// Source module of each message if they differ, e.g. in combined requests. Otherwise, empty.
+    pub message_source_ids: Vec<String>,
+    /// Commands started by the user and file transfers can take a long time.
+    /// They use `command_timeout` instead of `request_timeout` and don't count towards the per-host limit.
+    pub long_running: bool,
 }
 
 impl ConnectorRequest {
     pub fn exit_token() -> Self {
+        ConnectorRequest {
+            request_type: RequestType::Exit,
+            ..Default::default()
+        }
+    }
+
+    /// Response handler doesn't have to be shareable between threads, so it's kept separate from the rest of the request.
+    fn split(self) -> (RequestDetails, ResponseHandlerCallback) {
+        let details = RequestDetails {
+            connector_spec: self.connector_spec,
+            source_id: self.source_id,
+            host: self.host,
+            messages: self.messages,
+            request_type: self.request_type,
+            cache_policy: self.cache_policy,
+            cache_sensitivity: self.cache_sensitivity,
+            message_source_ids: self.message_source_ids,
+            long_running: self.long_running,
+        };
+        (details, self.response_handler)
+    }
+}
+
+impl Default for ConnectorRequest {
+    fn default() -> Self {
         ConnectorRequest {
             connector_spec: None,
             source_id: String::new(),
             host: Host::new(&String::new(), &String::from("127.0.0.1"), &String::new(), &Vec::new()).unwrap(),
             messages: Vec::new(),
-            request_type: RequestType::Exit,
+            request_type: RequestType::Command,
             response_handler: Box::new(|_| ()),
             cache_policy: CachePolicy::BypassCache,
+            cache_sensitivity: CacheSensitivity::Normal,
+            message_source_ids: Vec::new(),
+            long_running: false,
         }
     }
 }
@@ -367,11 +767,35 @@ impl Debug for ConnectorRequest {
     }
 }
 
+/// ConnectorRequest without the response handler.
+struct RequestDetails {
+    connector_spec: Option<ModuleSpecification>,
+    source_id: String,
+    host: Host,
+    messages: Vec<String>,
+    request_type: RequestType,
+    cache_policy: CachePolicy,
+    cache_sensitivity: CacheSensitivity,
+    message_source_ids: Vec<String>,
+    long_running: bool,
+}
+
+impl RequestDetails {
+    /// Returns the module that the message belongs to.
+    fn get_source_id(&self, message: &String) -> &str {
+        self.messages.iter().position(|request_message| request_message == message)
+                            .and_then(|index| self.message_source_ids.get(index))
+                            .unwrap_or(&self.source_id)
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum RequestType {
     Command,
     Download,
     Upload,
+    /// Removes cached responses of the messages. Response handler isn't called.
+    EvictCache,
     Exit,
 }
 
@@ -380,4 +804,13 @@ pub enum CachePolicy {
     BypassCache,
     PreferCache,
     OnlyCache,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CachePurgeTarget {
+    All,
+    Host(String),
+    /// Responses requested by the module.
+    Module(String),
+    KeyPrefix(String),
 }
\ No newline at end of file
@@ -29,6 +29,7 @@ pub struct DisplayOptions {
     pub override_summary_monitor_id: String,
 
     /// Display confirmation dialog with this text.
+    /// Placeholders {1}, {2}, etc. are replaced with command parameters, including user inputs.
     pub confirmation_text: String,
 
     pub user_parameters: Vec<UserInputField>,
@@ -104,6 +105,7 @@ pub enum UserInputFieldType {
 }
 
 #[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct UserInputField {
     pub field_type: UserInputFieldType,
     pub label: String,
@@ -169,4 +171,18 @@ impl UserInputField {
             option_descriptions: Vec::new(),
         }
     }
+
+    pub fn option<Stringable: ToString>(label: Stringable, default_value: Stringable, options: Vec<(Stringable, Stringable)>) -> Self {
+        let (options, option_descriptions) = options.into_iter().map(|(option, description)| (option.to_string(), description.to_string())).unzip();
+        UserInputField {
+            field_type: UserInputFieldType::Option,
+            label: label.to_string(),
+            default_value: default_value.to_string(),
+            units: Vec::new(),
+            validator_regexp: String::new(),
+            additional_validator_regexp: String::new(),
+            options: options,
+            option_descriptions: option_descriptions,
+        }
+    }
 }
\ No newline at end of file
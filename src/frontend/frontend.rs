@@ -41,21 +41,35 @@ pub struct HostDisplayData {
     pub status: HostStatus,
     pub ip_address: IpAddr,
     pub monitoring_data: HashMap<String, MonitoringData>,
-    pub new_monitoring_data: Option<MonitoringData>,
     pub command_results: HashMap<String, CommandResult>,
-    pub new_command_results: Option<CommandResult>,
-    pub new_errors: Vec<ErrorMessage>,
-    pub just_initialized: bool,
-    pub just_initialized_from_cache: bool,
     pub is_initialized: bool,
-    pub stop: bool,
 }
 
 impl HostDisplayData {
-    pub fn stop() -> Self {
-        HostDisplayData {
-            stop: true,
-            ..Default::default()
+    /// Updates the state the same way HostManager did when it created the delta.
+    pub fn apply(&mut self, delta: &HostDisplayDelta) {
+        self.status = delta.status;
+        self.is_initialized = delta.is_initialized;
+
+        if let Some(platform) = &delta.platform {
+            self.platform = platform.clone();
+        }
+
+        if let Some(new_monitoring_data) = &delta.new_monitoring_data {
+            match self.monitoring_data.get_mut(&new_monitoring_data.monitor_id) {
+                Some(monitoring_data) => {
+                    for data_point in new_monitoring_data.values.iter() {
+                        monitoring_data.push_value(data_point.clone());
+                    }
+                },
+                None => {
+                    self.monitoring_data.insert(new_monitoring_data.monitor_id.clone(), new_monitoring_data.clone());
+                }
+            }
+        }
+
+        if let Some(command_result) = &delta.new_command_results {
+            self.command_results.insert(command_result.command_id.clone(), command_result.clone());
         }
     }
 }
@@ -69,14 +83,34 @@ impl Default for HostDisplayData {
             status: HostStatus::Down,
             ip_address: IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
             monitoring_data: HashMap::new(),
-            new_monitoring_data: None,
             command_results: HashMap::new(),
-            new_command_results: None,
-            new_errors: Vec::new(),
-            just_initialized: false,
-            just_initialized_from_cache: false,
             is_initialized: false,
-            stop: false,
         }
     }
-}
\ No newline at end of file
+}
+
+/// Changes to a host's state caused by a single state update.
+#[derive(Clone, Default)]
+pub struct HostDisplayDelta {
+    pub name: String,
+    pub status: HostStatus,
+    /// Only set if platform info was updated.
+    pub platform: Option<PlatformInfo>,
+    /// Contains only the new data point.
+    pub new_monitoring_data: Option<MonitoringData>,
+    pub new_command_results: Option<CommandResult>,
+    pub new_errors: Vec<ErrorMessage>,
+    pub just_initialized: bool,
+    pub just_initialized_from_cache: bool,
+    pub is_initialized: bool,
+}
+
+/// Messages from HostManager to frontends. Deltas are sent for every state update
+/// and full syncs when host state has been (re)created, e.g. after configuration changes.
+#[derive(Clone)]
+pub enum HostUpdate {
+    /// Replaces all data of the host.
+    FullSync(HostDisplayData),
+    Delta(HostDisplayDelta),
+    Stop,
+}
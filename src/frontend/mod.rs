@@ -2,6 +2,8 @@ pub mod frontend;
 pub use frontend::Frontend;
 pub use frontend::DisplayData;
 pub use frontend::HostDisplayData;
+pub use frontend::HostDisplayDelta;
+pub use frontend::HostUpdate;
 
 pub mod display_options;
 pub use display_options::DisplayOptions;
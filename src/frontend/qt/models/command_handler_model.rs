@@ -7,7 +7,7 @@ use qmetaobject::*;
 
 use crate::command_handler::{CommandHandler, CommandData};
 use crate::configuration;
-use crate::connection_manager::{CachePolicy, ConnectionManager};
+use crate::connection_manager::{CachePolicy, CachePurgeTarget, ConnectionManager};
 use crate::host_manager;
 use crate::module::command::UIAction;
 use crate::monitor_manager::MonitorManager;
@@ -20,12 +20,17 @@ pub struct CommandHandlerModel {
 
     stop: qt_method!(fn(&mut self)),
     reconfigure: qt_method!(fn(&mut self, config: QVariant, hosts_config: QVariant)),
+    // Reconfigures only the given hosts. Existing connections to other hosts are kept.
+    // Reconfigured hosts are refreshed if refresh_hosts_on_start is enabled.
+    reconfigureHosts: qt_method!(fn(&mut self, hosts_config: QVariant, host_ids: QStringList)),
 
     get_all_host_categories: qt_method!(fn(&self, host_id: QString) -> QVariantList),
     get_category_commands: qt_method!(fn(&self, host_id: QString, category: QString) -> QVariantList),
     get_commands_on_level: qt_method!(fn(&self, host_id: QString, category: QString, parent_id: QString, multivalue_level: QString) -> QVariantList),
     get_child_command_count: qt_method!(fn(&self, host_id: QString, category: QString) -> u32),
     execute: qt_method!(fn(&self, host_id: QString, command_id: QString, parameters: QStringList)),
+    // Called after user input dialog. Asks for confirmation too if the command has confirmation text.
+    execute_with_inputs: qt_method!(fn(&self, host_id: QString, command_id: QString, parameters: QStringList)),
     execute_confirmed: qt_method!(fn(&self, host_id: QString, command_id: QString, parameters: QStringList)),
     executePlain: qt_method!(fn(&self, host_id: QString, command_id: QString, parameters: QStringList) -> u64),
     saveAndUploadFile: qt_method!(fn(&self, host_id: QString, command_id: QString, local_file_path: QString, contents: QString) -> u64),
@@ -38,11 +43,16 @@ pub struct CommandHandlerModel {
     force_initialize_hosts: qt_method!(fn(&self)),
 
     // Monitor refresh methods.
-    force_refresh_monitors_of_command: qt_method!(fn(&self, host_id: QString, command_id: QString) -> QVariantList),
+    force_refresh_monitors_of_command: qt_method!(fn(&self, host_id: QString, command_id: QString, succeeded: bool) -> QVariantList),
     cached_refresh_monitors_of_category: qt_method!(fn(&self, host_id: QString, category: QString) -> QVariantList),
     refresh_monitors_of_category: qt_method!(fn(&self, host_id: QString, category: QString) -> QVariantList),
     force_refresh_monitors_of_category: qt_method!(fn(&self, host_id: QString, category: QString) -> QVariantList),
 
+    // Cache diagnostics.
+    getCacheStatistics: qt_method!(fn(&self) -> QString),
+    // Target type is one of: all, host, module, prefix. Returns the number of removed entries.
+    purgeCache: qt_method!(fn(&self, target_type: QString, value: QString) -> u32),
+
     // Signal to open a dialog. Since execution is async, invocation_id is used to retrieve the matching result.
     details_dialog_opened: qt_signal!(invocation_id: u64),
     input_dialog_opened: qt_signal!(input_specs: QString, host_id: QString, command_id: QString, parameters: QStringList),
@@ -96,7 +106,7 @@ impl CommandHandlerModel {
         self.host_manager.borrow_mut().configure(&hosts_config);
         self.host_manager.borrow_mut().start_receiving_updates();
 
-        self.connection_manager.configure(&hosts_config, &main_config.cache_settings);
+        self.connection_manager.configure(&hosts_config, &main_config.cache_settings, &main_config.connection_settings);
         self.connection_manager.start_processing_requests();
 
         self.monitor_manager.configure(
@@ -112,6 +122,23 @@ impl CommandHandlerModel {
         );
     }
 
+    fn reconfigureHosts(&mut self, hosts_config: QVariant, host_ids: QStringList) {
+        let hosts_config = configuration::Hosts::from_qvariant(hosts_config).unwrap();
+        let host_ids: Vec<String> = host_ids.into_iter().map(|host_id| host_id.to_string()).collect();
+
+        // Host state has to be in place before monitors send their initial values.
+        self.host_manager.borrow_mut().reconfigure_hosts(&hosts_config, &host_ids);
+        self.connection_manager.reconfigure_hosts(&hosts_config, &host_ids);
+        self.monitor_manager.reconfigure_hosts(&hosts_config, &host_ids);
+        self.command_handler.reconfigure_hosts(&hosts_config, &host_ids);
+
+        if self.configuration.preferences.refresh_hosts_on_start {
+            for host_id in host_ids.into_iter().filter(|host_id| hosts_config.hosts.contains_key(host_id)) {
+                self.force_initialize_host(QString::from(host_id));
+            }
+        }
+    }
+
     // Return CommandDatas relevant to category as QVariants.
     fn get_category_commands(&self, host_id: QString, category: QString) -> QVariantList {
         let category_string = category.to_string();
@@ -195,8 +222,18 @@ impl CommandHandlerModel {
             let input_specs: QString = QString::from(serde_json::to_string(&display_options.user_parameters).unwrap());
             self.input_dialog_opened(input_specs, host_id, command_id, parameters);
         }
-        else if !display_options.confirmation_text.is_empty() {
-            self.confirmation_dialog_opened(QString::from(display_options.confirmation_text), host_id, command_id, parameters);
+        else {
+            self.execute_with_inputs(host_id, command_id, parameters);
+        }
+    }
+
+    fn execute_with_inputs(&mut self, host_id: QString, command_id: QString, parameters: QStringList) {
+        let display_options = self.command_handler.get_command_for_host(&host_id.to_string(), &command_id.to_string()).display_options;
+
+        if !display_options.confirmation_text.is_empty() {
+            let parameter_values: Vec<String> = parameters.into_iter().map(|qvar| qvar.to_string()).collect();
+            let text = fill_confirmation_text(&display_options.confirmation_text, &parameter_values);
+            self.confirmation_dialog_opened(QString::from(text), host_id, command_id, parameters);
         }
         else {
             self.execute_confirmed(host_id, command_id, parameters);
@@ -225,7 +262,7 @@ impl CommandHandlerModel {
                 }
             },
             UIAction::TextView => {
-                let target_id = parameters.first().unwrap().clone();
+                let target_id = parameters.first().cloned().unwrap_or_default();
                 let invocation_id = self.command_handler.execute(&host_id, &command_id, &parameters);
                 if invocation_id > 0 {
                     self.textViewOpened(QString::from(format!("{}: {}", command_id, target_id)), invocation_id)
@@ -333,7 +370,8 @@ impl CommandHandlerModel {
     }
 
     // Finds related monitors for a command and refresh them.
-    fn force_refresh_monitors_of_command(&mut self, host_id: QString, command_id: QString) -> QVariantList  {
+    // Cached responses of monitors affected by a successful command are evicted first.
+    fn force_refresh_monitors_of_command(&mut self, host_id: QString, command_id: QString, succeeded: bool) -> QVariantList  {
         let host_id = host_id.to_string();
         let command_id = command_id.to_string();
 
@@ -345,9 +383,22 @@ impl CommandHandlerModel {
         ::log::debug!("[{}] Refreshing monitors related to command {}", host_id, command_id);
 
         let command = self.command_handler.get_command_for_host(&host_id, &command_id);
-        let monitor_id = command.display_options.parent_id;
+        let mut monitor_ids = vec![command.display_options.parent_id];
+
+        if succeeded {
+            let affected_monitors = self.command_handler.get_affected_monitors(&host_id, &command_id);
+            self.monitor_manager.evict_cached_responses(&host_id, &affected_monitors);
 
-        let invocation_ids = self.monitor_manager.refresh_monitors_by_id(&host_id, &monitor_id, CachePolicy::BypassCache);
+            for monitor_id in affected_monitors {
+                if !monitor_ids.contains(&monitor_id) {
+                    monitor_ids.push(monitor_id);
+                }
+            }
+        }
+
+        let invocation_ids = monitor_ids.iter().flat_map(|monitor_id| {
+            self.monitor_manager.refresh_monitors_by_id(&host_id, monitor_id, CachePolicy::BypassCache)
+        }).collect::<Vec<_>>();
         QVariantList::from_iter(invocation_ids)
     }
 
@@ -374,4 +425,31 @@ impl CommandHandlerModel {
         self.monitor_manager.get_all_host_categories(&host_id.to_string())
                             .iter().map(|category| category.to_qvariant()).collect()
     }
-}
\ No newline at end of file
+
+    fn getCacheStatistics(&self) -> QString {
+        QString::from(serde_json::to_string(&self.connection_manager.get_cache_statistics()).unwrap())
+    }
+
+    fn purgeCache(&self, target_type: QString, value: QString) -> u32 {
+        let value = value.to_string();
+        let target = match target_type.to_string().as_str() {
+            "all" => CachePurgeTarget::All,
+            "host" => CachePurgeTarget::Host(value),
+            "module" => CachePurgeTarget::Module(value),
+            "prefix" => CachePurgeTarget::KeyPrefix(value),
+            other => {
+                ::log::error!("Invalid cache purge target: {}", other);
+                return 0;
+            }
+        };
+
+        self.connection_manager.purge_cache(&target) as u32
+    }
+}
+
+/// Replaces placeholders {1}, {2}, etc. with command parameters.
+fn fill_confirmation_text(text: &str, parameters: &[String]) -> String {
+    parameters.iter().enumerate().fold(text.to_string(), |text, (index, parameter)| {
+        text.replace(&format!("{{{}}}", index + 1), parameter)
+    })
+}
@@ -7,7 +7,9 @@ use crate::{
     configuration::Hosts,
     configuration::Groups,
     configuration::{HostSettings, self},
+    configuration::watcher::ConfigWatcher,
     module::Metadata,
+    inventory::Inventory,
 };
 
 
@@ -21,12 +23,20 @@ pub struct ConfigManagerModel {
     // Signals
     //
     file_write_error: qt_signal!(config_dir: QString, error_message: QString),
+    // Configuration files were changed outside of the application.
+    main_configuration_changed: qt_signal!(),
+    hosts_changed: qt_signal!(host_ids: QStringList),
+    configuration_reload_failed: qt_signal!(error_message: QString),
 
     //
     // Common
     //
     isSandboxed: qt_method!(fn(&self) -> bool),
     reloadConfiguration: qt_method!(fn(&self) -> QVariantList),
+    // Returns main configuration and hosts configuration.
+    getConfiguration: qt_method!(fn(&self) -> QVariantList),
+    // Starts watching for changes in configuration files. Shouldn't be run more than once.
+    watchConfigurationFiles: qt_method!(fn(&self)),
 
     //
     // Preferences
@@ -39,6 +49,8 @@ pub struct ConfigManagerModel {
     //
     add_host: qt_method!(fn(&self, host_name: QString)),
     removeHost: qt_method!(fn(&self, host_name: QString)),
+    // Hosts from dynamic inventory can't be modified.
+    isDynamicHost: qt_method!(fn(&self, host_name: QString) -> bool),
     // Returns host settings as JSON string, since it doesn't seem to be possible to return custom QObjects directly.
     get_host_settings: qt_method!(fn(&self, host_name: QString) -> QString),
     set_host_settings: qt_method!(fn(&self, old_host_name: QString, new_host_name: QString, host_settings_json: QString)),
@@ -51,6 +63,13 @@ pub struct ConfigManagerModel {
     add_host_to_group: qt_method!(fn(&self, host_name: QString, group_name: QString)),
     remove_host_from_group: qt_method!(fn(&self, host_name: QString, group_name: QString)),
 
+    //
+    // Host import
+    //
+    // Source type is "ssh-config" or "ansible". Returns a summary of changes or an error message.
+    preview_host_import: qt_method!(fn(&self, source_type: QString, path: QString) -> QString),
+    import_hosts: qt_method!(fn(&self, source_type: QString, path: QString)),
+
     //
     // Group configuration
     //
@@ -112,6 +131,7 @@ pub struct ConfigManagerModel {
     groups_config: Groups,
     groups_config_backup: Option<Groups>,
     module_metadatas: Vec<Metadata>,
+    config_watcher: Option<ConfigWatcher>,
 }
 
 #[allow(non_snake_case)]
@@ -123,10 +143,7 @@ impl ConfigManagerModel {
                module_metadatas: Vec<Metadata>) -> Self {
         
         let mut hosts_config = hosts_config;
-        // Sort host groups alphabetically.
-        for host in hosts_config.hosts.values_mut() {
-            host.groups.sort_by_key(|key| key.to_lowercase());
-        }
+        Self::sort_host_groups(&mut hosts_config);
 
         ConfigManagerModel {
             config_dir: config_dir,
@@ -163,6 +180,7 @@ impl ConfigManagerModel {
         if let Err(error) = Configuration::write_main_config(&self.config_dir, &self.main_config) {
             self.file_write_error(QString::from(self.config_dir.clone()), QString::from(error.to_string()));
         }
+        self.acknowledge_config_writes();
     }
 
     fn add_host(&mut self, host_name: QString) {
@@ -182,10 +200,20 @@ impl ConfigManagerModel {
 
     fn removeHost(&mut self, host_name: QString) {
         let host_name = host_name.to_string();
+        if self.hosts_config.hosts.get(&host_name).map(|host| host.is_dynamic).unwrap_or(false) {
+            ::log::warn!("Host {} is from dynamic inventory and can't be removed", host_name);
+            return;
+        }
+
         ::log::info!("Removing host {}", host_name);
         self.hosts_config.hosts.remove(&host_name).unwrap();
     }
 
+    fn isDynamicHost(&self, host_name: QString) -> bool {
+        let host_name = host_name.to_string();
+        self.hosts_config.hosts.get(&host_name).map(|host| host.is_dynamic).unwrap_or(false)
+    }
+
     fn isSandboxed(&self) -> bool {
         self.main_config.preferences.use_sandbox_mode
     }
@@ -193,7 +221,8 @@ impl ConfigManagerModel {
     fn reloadConfiguration(&mut self) -> QVariantList {
         ::log::info!("Reloading configuration...");
         match Configuration::read(&self.config_dir) {
-            Ok((main_config, hosts_config, groups_config)) => {
+            Ok((main_config, mut hosts_config, groups_config)) => {
+                Self::sort_host_groups(&mut hosts_config);
                 self.main_config = main_config;
                 self.hosts_config = hosts_config;
                 self.groups_config = groups_config;
@@ -203,6 +232,10 @@ impl ConfigManagerModel {
             }
         }
 
+        self.getConfiguration()
+    }
+
+    fn getConfiguration(&self) -> QVariantList {
         // How to do in one line?
         let mut result = QVariantList::default();
         result.push(self.main_config.clone().to_qvariant());
@@ -210,6 +243,66 @@ impl ConfigManagerModel {
         result
     }
 
+    fn watchConfigurationFiles(&mut self) {
+        if self.config_watcher.is_some() {
+            return;
+        }
+
+        let self_ptr = QPointer::from(&*self);
+        let on_change = qmetaobject::queued_callback(move |_: ()| {
+            if let Some(self_pinned) = self_ptr.as_pinned() {
+                self_pinned.borrow_mut().apply_external_changes();
+            }
+        });
+
+        match ConfigWatcher::start(&self.config_dir, move || on_change(())) {
+            Ok(watcher) => self.config_watcher = Some(watcher),
+            Err(error) => ::log::error!("Failed to watch configuration directory {}: {}", self.config_dir, error),
+        }
+    }
+
+    /// Changes written by the application itself are already applied, so they're ignored by the watcher.
+    fn acknowledge_config_writes(&self) {
+        if let Some(config_watcher) = self.config_watcher.as_ref() {
+            config_watcher.acknowledge_writes();
+        }
+    }
+
+    /// Reads and validates the changed configuration files and signals which hosts were affected.
+    /// If the new configuration is invalid, the current configuration stays in use.
+    fn apply_external_changes(&mut self) {
+        if self.hosts_config_backup.is_some() || self.groups_config_backup.is_some() {
+            ::log::warn!("Configuration is being edited. Ignoring changes in configuration files.");
+            return;
+        }
+
+        let (main_config, mut hosts_config, groups_config) = match Configuration::read(&self.config_dir) {
+            Ok(configs) => configs,
+            Err(error) => {
+                ::log::error!("Invalid configuration: {}", error);
+                self.configuration_reload_failed(QString::from(format!("Configuration was not reloaded: {}", error)));
+                return;
+            }
+        };
+
+        Self::sort_host_groups(&mut hosts_config);
+        let main_config_changed = main_config != self.main_config;
+        let changed_hosts = Configuration::get_changed_hosts(&self.hosts_config, &hosts_config);
+
+        self.main_config = main_config;
+        self.hosts_config = hosts_config;
+        self.groups_config = groups_config;
+
+        if main_config_changed {
+            ::log::info!("Main configuration changed");
+            self.main_configuration_changed();
+        }
+        else if !changed_hosts.is_empty() {
+            ::log::info!("Configuration changed for hosts: {}", changed_hosts.join(", "));
+            self.hosts_changed(changed_hosts.into_iter().map(QString::from).collect::<QStringList>());
+        }
+    }
+
     /// Updates preferences.use_sandbox_mode. Returns true if value was changed and was written to config.
     pub fn setSandboxed(&mut self, use_sandbox_mode: bool) -> bool {
         if self.main_config.preferences.use_sandbox_mode != use_sandbox_mode {
@@ -220,7 +313,10 @@ impl ConfigManagerModel {
                 self.main_config.preferences.terminal_args = Vec::new();
             }
 
-            if let Err(error) = Configuration::write_main_config(&self.config_dir, &self.main_config) {
+            let result = Configuration::write_main_config(&self.config_dir, &self.main_config);
+            self.acknowledge_config_writes();
+
+            if let Err(error) = result {
                 self.file_write_error(QString::from(self.config_dir.clone()), QString::from(error.to_string()));
                 false
             }
@@ -245,7 +341,8 @@ impl ConfigManagerModel {
         self.hosts_config_backup = None;
         if let Err(error) = Configuration::write_hosts_config(&self.config_dir, &self.hosts_config) {
             self.file_write_error(QString::from(self.config_dir.clone()), QString::from(error.to_string()));
-        }
+        }        }
+        self.acknowledge_config_writes();
     }
 
     fn begin_group_configuration(&mut self) {
@@ -260,7 +357,8 @@ impl ConfigManagerModel {
         self.groups_config_backup = None;
         if let Err(error) = Configuration::write_groups_config(&self.config_dir, &self.groups_config) {
             self.file_write_error(QString::from(self.config_dir.clone()), QString::from(error.to_string()));
-        }
+        }        }
+        self.acknowledge_config_writes();
     }
 
     fn get_host_settings(&self, host_name: QString) -> QString {
@@ -285,6 +383,34 @@ impl ConfigManagerModel {
         host_config.fqdn = host_settings.fqdn;
     }
 
+    fn preview_host_import(&self, source_type: QString, path: QString) -> QString {
+        match Inventory::read(&source_type.to_string(), &path.to_string()) {
+            Ok(inventory) => QString::from(inventory.preview(&self.hosts_config, &self.groups_config).to_string()),
+            Err(error) => QString::from(format!("Error: {}", error)),
+        }
+    }
+
+    fn import_hosts(&mut self, source_type: QString, path: QString) {
+        let inventory = match Inventory::read(&source_type.to_string(), &path.to_string()) {
+            Ok(inventory) => inventory,
+            Err(error) => {
+                self.file_write_error(QString::from(self.config_dir.clone()), QString::from(error));
+                return;
+            }
+        };
+
+        let preview = inventory.merge(&mut self.hosts_config, &mut self.groups_config);
+        ::log::info!("Imported {} new and {} updated hosts", preview.new_hosts.len(), preview.updated_hosts.len());
+
+        if let Err(error) = Configuration::write_groups_config(&self.config_dir, &self.groups_config) {
+            self.file_write_error(QString::from(self.config_dir.clone()), QString::from(error.to_string()));
+        }
+        else if let Err(error) = Configuration::write_hosts_config(&self.config_dir, &self.hosts_config) {
+            self.file_write_error(QString::from(self.config_dir.clone()), QString::from(error.to_string()));
+        }        }
+        self.acknowledge_config_writes();
+    }
+
     fn get_all_groups(&self) -> QStringList {
         let mut all_groups = self.groups_config.groups.keys().cloned().collect::<Vec<String>>();
         all_groups.sort();
@@ -626,6 +752,13 @@ impl ConfigManagerModel {
         }
     }
 
+    /// Sort host groups alphabetically.
+    fn sort_host_groups(hosts_config: &mut Hosts) {
+        for host in hosts_config.hosts.values_mut() {
+            host.groups.sort_by_key(|key| key.to_lowercase());
+        }
+    }
+
     fn get_all_module_settings(&self, module_type: QString, module_id: QString) -> QVariantMap {
         let module_id = module_id.to_string();
         let module_type = module_type.to_string();
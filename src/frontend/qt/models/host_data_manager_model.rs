@@ -20,6 +20,7 @@ pub struct HostDataManagerModel {
 
     receive_updates: qt_method!(fn(&self)),
     reset: qt_method!(fn(&mut self)),
+    resetHosts: qt_method!(fn(&mut self, host_ids: QStringList)),
     stop: qt_method!(fn(&mut self)),
     update_received: qt_signal!(host_id: QString),
 
@@ -56,9 +57,9 @@ pub struct HostDataManagerModel {
     pending_monitor_invocations: HashMap<String, HashMap<String, (Vec<u64>, usize)>>,
     configuration_preferences: configuration::Preferences,
     configuration_cache_settings: configuration::CacheSettings,
-    update_receiver: Option<mpsc::Receiver<frontend::HostDisplayData>>,
+    update_receiver: Option<mpsc::Receiver<frontend::HostUpdate>>,
     update_receiver_thread: Option<thread::JoinHandle<()>>,
-    update_sender_prototype: Option<mpsc::Sender<frontend::HostDisplayData>>,
+    update_sender_prototype: Option<mpsc::Sender<frontend::HostUpdate>>,
 }
 
 #[allow(non_snake_case)]
@@ -70,7 +71,7 @@ impl HostDataManagerModel {
 
         priorities.sort_by(|left, right| left.1.cmp(&right.1));
 
-        let (sender, receiver) = mpsc::channel::<frontend::HostDisplayData>();
+        let (sender, receiver) = mpsc::channel::<frontend::HostUpdate>();
 
         let model = HostDataManagerModel {
             display_data: display_data,
@@ -92,67 +93,87 @@ impl HostDataManagerModel {
         if self.update_receiver_thread.is_none() {
             let self_ptr = QPointer::from(&*self);
 
-            let set_data = qmetaobject::queued_callback(move |new_display_data: frontend::HostDisplayData| {
+            let set_data = qmetaobject::queued_callback(move |update: frontend::HostUpdate| {
                 if let Some(self_pinned) = self_ptr.as_pinned() {
+                    let delta = match update {
+                        frontend::HostUpdate::FullSync(host_display_data) => {
+                            let host_id = host_display_data.name.clone();
+                            self_pinned.borrow_mut().display_data.hosts.insert(host_id.clone(), host_display_data);
+                            self_pinned.borrow().update_received(QString::from(host_id));
+                            return;
+                        },
+                        frontend::HostUpdate::Delta(delta) => delta,
+                        // Handled by the receiver thread.
+                        frontend::HostUpdate::Stop => return,
+                    };
+
                     // HostDataModel cannot be passed between threads so parsing happens here.
 
-                    let maybe_old_data = self_pinned.borrow_mut().display_data.hosts.insert(
-                        new_display_data.name.clone(),
-                        new_display_data.clone()
-                    );
+                    // Needed for detecting monitor state changes.
+                    let old_criticality = delta.new_monitoring_data.as_ref().and_then(|new_monitor_data| {
+                        self_pinned.borrow().display_data.hosts.get(&delta.name)
+                                   .and_then(|old_data| old_data.monitoring_data.get(&new_monitor_data.monitor_id))
+                                   .and_then(|old_monitor_data| old_monitor_data.values.back())
+                                   .map(|old_data_point| old_data_point.criticality)
+                    });
+
+                    match self_pinned.borrow_mut().display_data.hosts.get_mut(&delta.name) {
+                        Some(host_display_data) => host_display_data.apply(&delta),
+                        None => {
+                            // Host was removed or its full state hasn't been received yet.
+                            ::log::debug!("Ignoring update for unknown host {}", delta.name);
+                            return;
+                        }
+                    }
 
-                    if new_display_data.just_initialized {
-                        ::log::debug!("Host {} initialized", new_display_data.name);
-                        self_pinned.borrow().host_initialized(QString::from(new_display_data.name.clone()));
+                    if delta.just_initialized {
+                        ::log::debug!("Host {} initialized", delta.name);
+                        self_pinned.borrow().host_initialized(QString::from(delta.name.clone()));
                     }
-                    else if new_display_data.just_initialized_from_cache {
-                        ::log::debug!("Host {} initialized from cache", new_display_data.name);
-                        self_pinned.borrow().host_initialized_from_cache(QString::from(new_display_data.name.clone()));
+                    else if delta.just_initialized_from_cache {
+                        ::log::debug!("Host {} initialized from cache", delta.name);
+                        self_pinned.borrow().host_initialized_from_cache(QString::from(delta.name.clone()));
                     }
 
-                    if let Some(command_result) = new_display_data.new_command_results {
+                    if let Some(command_result) = delta.new_command_results {
                         let json = QString::from(serde_json::to_string(&command_result).unwrap());
                         self_pinned.borrow().command_result_received(json);
                     }
 
-                    if let Some(new_monitor_data) = new_display_data.new_monitoring_data {
+                    if let Some(new_monitor_data) = delta.new_monitoring_data {
                         let last_data_point = new_monitor_data.values.back().unwrap();
 
                         // Invocation ID may be missing if no command was executed due to error.
                         if last_data_point.invocation_id > 0 {
-                            self_pinned.borrow_mut().remove_pending_monitor_invocation(&new_display_data.name,
+                            self_pinned.borrow_mut().remove_pending_monitor_invocation(&delta.name,
                                                                                        &new_monitor_data.display_options.category,
                                                                                        last_data_point.invocation_id);
                         }
 
-                        self_pinned.borrow().monitoring_data_received(QString::from(new_display_data.name.clone()),
+                        self_pinned.borrow().monitoring_data_received(QString::from(delta.name.clone()),
                                                                       QString::from(new_monitor_data.display_options.category.clone()),
                                                                       new_monitor_data.to_qvariant());
 
 
                         // Find out any monitor state changes and signal accordingly.
-                        if let Some(old_data) = maybe_old_data {
-                            let new_criticality = new_monitor_data.values.back().unwrap().criticality;
-
-                            if let Some(old_monitor_data) = old_data.monitoring_data.get(&new_monitor_data.monitor_id) {
-                                let old_criticality = old_monitor_data.values.back().unwrap().criticality;
-
-                                if new_criticality != old_criticality {
-                                    self_pinned.borrow().monitor_state_changed(
-                                        QString::from(new_display_data.name.clone()),
-                                        QString::from(new_monitor_data.monitor_id.clone()),
-                                        QString::from(new_criticality.to_string())
-                                    );
-                                }
+                        if let Some(old_criticality) = old_criticality {
+                            let new_criticality = last_data_point.criticality;
+
+                            if new_criticality != old_criticality {
+                                self_pinned.borrow().monitor_state_changed(
+                                    QString::from(delta.name.clone()),
+                                    QString::from(new_monitor_data.monitor_id.clone()),
+                                    QString::from(new_criticality.to_string())
+                                );
                             }
                         }
                     }
 
-                    for error in new_display_data.new_errors {
+                    for error in delta.new_errors {
                         self_pinned.borrow().error_received(QString::from(error.criticality.to_string()), QString::from(error.message));
                     }
 
-                    self_pinned.borrow().update_received(QString::from(new_display_data.name));
+                    self_pinned.borrow().update_received(QString::from(delta.name));
                 }
             });
 
@@ -172,7 +193,7 @@ impl HostDataManagerModel {
                 loop {
                     let received_data = receiver.recv().unwrap();
 
-                    if received_data.stop {
+                    if let frontend::HostUpdate::Stop = received_data {
                         ::log::debug!("Gracefully exiting UI state receiver thread");
                         return;
                     }
@@ -184,7 +205,7 @@ impl HostDataManagerModel {
         }
     }
 
-    pub fn new_update_sender(&self) -> mpsc::Sender<frontend::HostDisplayData> {
+    pub fn new_update_sender(&self) -> mpsc::Sender<frontend::HostUpdate> {
         self.update_sender_prototype.as_ref().unwrap().clone()
     }
 
@@ -193,9 +214,17 @@ impl HostDataManagerModel {
         self.pending_monitor_invocations.clear();
     }
 
+    /// Clears data of the given hosts only.
+    fn resetHosts(&mut self, host_ids: QStringList) {
+        for host_id in host_ids.into_iter().map(|host_id| host_id.to_string()) {
+            self.display_data.hosts.remove(&host_id);
+            self.pending_monitor_invocations.remove(&host_id);
+        }
+    }
+
     pub fn stop(&mut self) {
         self.new_update_sender()
-            .send(frontend::HostDisplayData::stop()).unwrap();
+            .send(frontend::HostUpdate::Stop).unwrap();
 
         if let Some(thread) = self.update_receiver_thread.take() {
             thread.join().unwrap();
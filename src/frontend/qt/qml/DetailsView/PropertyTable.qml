@@ -28,7 +28,7 @@ TableView {
     property int expandedCommandRow: -1
 
     // Automatic refresh is done after all commands have been executed.
-    // This keeps track which commands were executed.
+    // This keeps track which commands were executed and if they succeeded.
     property var pendingRefreshAfterCommand: []
     property int selectedRow: -1
 
@@ -59,7 +59,8 @@ TableView {
             let commandResult = JSON.parse(commandResultJson)
             root.model.end_command_cooldown(commandResult.invocation_id)
 
-            root.pendingRefreshAfterCommand.push(commandResult.command_id);
+            let succeeded = commandResult.criticality !== "Error" && commandResult.criticality !== "Critical"
+            root.pendingRefreshAfterCommand.push({ commandId: commandResult.command_id, succeeded: succeeded })
         }
     }
 
@@ -154,6 +155,9 @@ TableView {
 
                 property bool isSeparator: root.model.get_separator_label(row) !== ""
                 property var styledValue: JSON.parse(model.value)
+                // Non-numeric values of progress bar monitors are displayed as text, e.g. state of a RAID member.
+                property bool isProgress: styledValue.display_options.display_style === "ProgressBar" &&
+                                          !isNaN(parseInt(styledValue.data_point.value, 10))
 
                 visible: !isSeparator
                 implicitWidth: root.width * root.model.get_column_width(row, column)
@@ -165,7 +169,7 @@ TableView {
                     z: 2
 
                     Row {
-                        visible: styledValue.display_options.display_style === "ProgressBar"
+                        visible: isProgress
                         spacing: 5
 
                         ProgressBar {
@@ -189,6 +193,14 @@ TableView {
                         lineHeight: 0.9
                     }
 
+                    SmallText {
+                        visible: styledValue.display_options.display_style === "ProgressBar" && !isProgress
+                        text: ValueUnit.AsText(styledValue.data_point.value, "")
+
+                        anchors.verticalCenter: parent.verticalCenter
+                        lineHeight: 0.9
+                    }
+
                     PillText {
                         visible: styledValue.display_options.display_style === "CriticalityLevel"
                         anchors.verticalCenter: parent.verticalCenter
@@ -274,8 +286,8 @@ TableView {
                 cooldownTimer.stop()
 
                 // Refresh the monitor(s) related to commands that were executed.
-                for (const commandId of root.pendingRefreshAfterCommand) {
-                    CommandHandler.force_refresh_monitors_of_command(root.hostId, commandId)
+                for (const command of root.pendingRefreshAfterCommand) {
+                    CommandHandler.force_refresh_monitors_of_command(root.hostId, command.commandId, command.succeeded)
                 }
 
                 root.pendingRefreshAfterCommand = []
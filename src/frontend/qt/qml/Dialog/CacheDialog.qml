@@ -0,0 +1,131 @@
+import QtQuick 2.15
+import QtQuick.Layouts 1.11
+import QtQuick.Controls 2.15
+
+import "../StyleOverride"
+import "../Text"
+import "../js/Utils.js" as Utils
+import ".."
+
+// This component should be a direct child of main window.
+Dialog {
+    id: root
+    property var _statistics: JSON.parse(CommandHandler.getCacheStatistics())
+    property string _statusText: ""
+    property var _targetTypes: ["all", "host", "module", "prefix"]
+
+    modal: true
+    implicitWidth: 650
+    implicitHeight: 650
+    background: DialogBackground { }
+    standardButtons: Dialog.Close
+
+    onOpened: {
+        root._statusText = ""
+        refresh()
+    }
+
+    contentItem: ColumnLayout {
+        id: content
+        anchors.margins: Theme.margin_dialog()
+        anchors.bottomMargin: Theme.margin_dialog_bottom()
+        spacing: Theme.spacing_loose()
+
+        BigText {
+            text: "Cache"
+
+            Layout.alignment: Qt.AlignHCenter
+        }
+
+        RowLayout {
+            Layout.fillWidth: true
+
+            NormalText {
+                text: root._statistics.entry_count + " entries, " + root._statistics.memory_only_count + " only in memory" +
+                      (root._statistics.oldest_entry_age === null ? "" : ", oldest " + Utils.formatAge(root._statistics.oldest_entry_age))
+                Layout.fillWidth: true
+            }
+
+            Button {
+                text: "Refresh"
+                onClicked: refresh()
+            }
+        }
+
+        ListView {
+            clip: true
+            model: Object.keys(root._statistics.modules)
+            spacing: Theme.spacing_normal()
+            Layout.fillWidth: true
+            Layout.fillHeight: true
+
+            header: RowLayout {
+                width: parent.width
+
+                SmallText {
+                    text: "Module"
+                    color: Theme.color_dark_text()
+                    Layout.fillWidth: true
+                }
+
+                SmallText {
+                    text: "Entries / hits / misses / age"
+                    color: Theme.color_dark_text()
+                }
+            }
+
+            delegate: RowLayout {
+                property var _module: root._statistics.modules[modelData]
+                width: ListView.view.width
+
+                NormalText {
+                    text: modelData === "" ? "(unknown)" : modelData
+                    Layout.fillWidth: true
+                }
+
+                NormalText {
+                    text: parent._module.entry_count + " / " + parent._module.hits + " / " + parent._module.misses + " / " +
+                          (parent._module.newest_entry_age === null ? "-" :
+                              Utils.formatAge(parent._module.newest_entry_age) + "–" + Utils.formatAge(parent._module.oldest_entry_age))
+                }
+            }
+        }
+
+        RowLayout {
+            Layout.fillWidth: true
+
+            ComboBox {
+                id: targetTypeBox
+                model: ["All", "Host", "Module", "Key prefix"]
+                Layout.preferredWidth: 150
+            }
+
+            TextField {
+                id: targetField
+                enabled: targetTypeBox.currentIndex !== 0
+                placeholderText: "Host, module or key prefix..."
+                Layout.fillWidth: true
+            }
+
+            Button {
+                text: "Purge"
+                enabled: targetTypeBox.currentIndex === 0 || targetField.text !== ""
+
+                onClicked: {
+                    let count = CommandHandler.purgeCache(root._targetTypes[targetTypeBox.currentIndex], targetField.text)
+                    root._statusText = "Removed " + count + " entries"
+                    refresh()
+                }
+            }
+        }
+
+        SmallText {
+            text: root._statusText
+            color: Theme.color_dark_text()
+        }
+    }
+
+    function refresh() {
+        root._statistics = JSON.parse(CommandHandler.getCacheStatistics())
+    }
+}
@@ -17,6 +17,7 @@ Dialog {
     property var hostSettings: JSON.parse(ConfigManager.get_host_settings(hostId))
     property var _selectedGroups: ConfigManager.get_selected_groups(hostId)
     property var _availableGroups: ConfigManager.get_available_groups(hostId)
+    property bool _isReadOnly: ConfigManager.isDynamicHost(hostId)
     property int _contentWidth: 360
     property bool _loading: true
 
@@ -86,6 +87,16 @@ Dialog {
             Layout.alignment: Qt.AlignHCenter
         }
 
+        SmallText {
+            visible: root._isReadOnly
+            text: "This host is provided by the dynamic inventory and can't be modified here."
+            color: Theme.color_dark_text()
+            wrapMode: Text.WordWrap
+
+            Layout.alignment: Qt.AlignHCenter
+            Layout.preferredWidth: root._contentWidth
+        }
+
         Column {
             Layout.alignment: Qt.AlignHCenter
             Layout.preferredWidth: root._contentWidth
@@ -99,6 +110,7 @@ Dialog {
                 width: parent.width
                 placeholderText: "Unique name for host..."
                 text: root.hostId === "new-host-id" ? "" : root.hostId
+                readOnly: root._isReadOnly
                 validator: RegularExpressionValidator {
                     regularExpression: /[a-zA-Z\d\-\.]+/
                 }
@@ -119,6 +131,7 @@ Dialog {
                 width: parent.width
                 placeholderText: ""
                 text: root.hostSettings.address === undefined ? root.hostSettings.fqdn : root.hostSettings.address 
+                readOnly: root._isReadOnly
                 validator: RegularExpressionValidator {
                     regularExpression: /[\.\:a-zA-Z\d\-]+/
                 }
@@ -226,11 +239,12 @@ Dialog {
                 spacing: Theme.spacing_normal()
 
                 property bool isValidGroupSelection: tabView._selectedGroup !== "" && tabView._selectedGroupTab === tabView.currentIndex
+                property bool isEditable: !root._isReadOnly
 
                 ImageButton {
                     id: addButton
                     visible: tabView.currentIndex === 1
-                    enabled: parent.isValidGroupSelection
+                    enabled: parent.isValidGroupSelection && parent.isEditable
                     imageSource: "qrc:/main/images/button/add"
                     size: root.buttonSize
                     onClicked: {
@@ -244,7 +258,7 @@ Dialog {
                 ImageButton {
                     id: removeButton
                     visible: tabView.currentIndex === 0
-                    enabled: parent.isValidGroupSelection
+                    enabled: parent.isValidGroupSelection && parent.isEditable
                     imageSource: "qrc:/main/images/button/remove"
                     size: root.buttonSize
                     onClicked: {
@@ -338,7 +352,7 @@ Dialog {
     }
 
     function fieldsAreValid() {
-        return hostIdField.acceptableInput && hostAddressField.acceptableInput
+        return !root._isReadOnly && hostIdField.acceptableInput && hostAddressField.acceptableInput
     }
 
     function updateOkButton() {
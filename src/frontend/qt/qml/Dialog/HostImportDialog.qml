@@ -0,0 +1,115 @@
+import QtQuick 2.15
+import QtQuick.Layouts 1.11
+import QtQuick.Controls 2.15
+
+import "../StyleOverride"
+import "../Text"
+import ".."
+
+// This component should be a direct child of main window.
+Dialog {
+    id: root
+    property string _previewText: ""
+    property var _sourceTypes: ["ssh-config", "ansible"]
+    property var _defaultPaths: ["~/.ssh/config", ""]
+
+    modal: true
+    implicitWidth: 550
+    implicitHeight: 650
+    background: DialogBackground { }
+    standardButtons: Dialog.Ok | Dialog.Cancel
+
+    signal configurationChanged()
+
+    onOpened: {
+        root._previewText = ""
+        updateOkButton()
+    }
+
+    onAccepted: {
+        ConfigManager.import_hosts(root._sourceTypes[sourceTypeBox.currentIndex], pathField.text)
+        root.configurationChanged()
+    }
+
+    contentItem: ColumnLayout {
+        id: content
+        anchors.margins: Theme.margin_dialog()
+        anchors.bottomMargin: Theme.margin_dialog_bottom()
+        spacing: Theme.spacing_loose()
+
+        BigText {
+            text: "Import hosts"
+
+            Layout.alignment: Qt.AlignHCenter
+        }
+
+        RowLayout {
+            Layout.fillWidth: true
+
+            Label {
+                text: "Source"
+                Layout.fillWidth: true
+            }
+
+            ComboBox {
+                id: sourceTypeBox
+                model: ["SSH client configuration", "Ansible inventory (INI or YAML)"]
+                Layout.preferredWidth: 300
+
+                onCurrentIndexChanged: {
+                    pathField.text = root._defaultPaths[currentIndex]
+                    root._previewText = ""
+                    updateOkButton()
+                }
+            }
+        }
+
+        RowLayout {
+            Layout.fillWidth: true
+
+            Label {
+                text: "File"
+                Layout.fillWidth: true
+            }
+
+            TextField {
+                id: pathField
+                text: root._defaultPaths[0]
+                placeholderText: "Path to file..."
+                Layout.preferredWidth: 300
+
+                onTextChanged: {
+                    root._previewText = ""
+                    updateOkButton()
+                }
+            }
+        }
+
+        Button {
+            text: "Preview"
+            enabled: pathField.text !== ""
+            Layout.alignment: Qt.AlignRight
+
+            onClicked: {
+                root._previewText = ConfigManager.preview_host_import(root._sourceTypes[sourceTypeBox.currentIndex], pathField.text)
+                updateOkButton()
+            }
+        }
+
+        ScrollView {
+            clip: true
+            Layout.fillWidth: true
+            Layout.fillHeight: true
+
+            NormalText {
+                text: root._previewText === "" ? "Preview the changes before importing." : root._previewText
+                color: root._previewText === "" ? Theme.color_dark_text() : Theme.color_text()
+            }
+        }
+    }
+
+    // Importing is allowed only after a successful preview.
+    function updateOkButton() {
+        root.standardButton(Dialog.Ok).enabled = root._previewText !== "" && !root._previewText.startsWith("Error")
+    }
+}
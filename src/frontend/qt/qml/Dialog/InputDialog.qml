@@ -57,7 +57,7 @@ Dialog {
                     ComboBox {
                         id: comboBox
                         model: [''].concat(modelData.options)
-                        currentIndex: 0
+                        currentIndex: Math.max(0, model.indexOf(modelData.default_value || ""))
                     }
 
                     SmallText {
@@ -4,6 +4,7 @@ import Qt.labs.qmlmodels 1.0
 import QtGraphicalEffects 1.15
 
 import "Text"
+import "js/Utils.js" as Utils
 
 Item {
     id: root
@@ -32,6 +33,7 @@ Item {
                 property var monitorData: JSON.parse(modelData)
                 property string criticality: monitorData.values.slice(-1)[0].criticality.toLowerCase()
                 property bool isFromCache: monitorData.values.slice(-1)[0].is_from_cache
+                property var cacheAge: monitorData.values.slice(-1)[0].cache_age
                 property string monitorId: monitorData.display_options.display_text.toLowerCase()
                 property string color: getColor(criticality)
                 height: root.height
@@ -72,7 +74,7 @@ Item {
                 PixelatedText {
                     id: label
                     anchors.horizontalCenter: parent.horizontalCenter
-                    text: parent.isFromCache ? parent.monitorId + " (CACHED)" : parent.monitorId
+                    text: parent.isFromCache ? parent.monitorId + " (" + getCachedText(parent.cacheAge) + ")" : parent.monitorId
                     opacity: 0
                 }
 
@@ -97,6 +99,10 @@ Item {
         }
     }
 
+    function getCachedText(cacheAge) {
+        return cacheAge === null || cacheAge === undefined ? "CACHED" : "cached " + Utils.formatAge(cacheAge) + " ago"
+    }
+
     function getColor(criticality) {
         let color = _colors[criticality]
         if (typeof color !== "undefined") {
@@ -30,4 +30,18 @@ function getLocalTimezoneISOString(now) {
     const seconds = ('0' + date.getSeconds()).slice(-2);
 
     return year + '-' + month + '-' + day + 'T' + hours + ':' + minutes + ':' + seconds;
-}
\ No newline at end of file
+}
+
+/// Formats seconds as e.g. "45s", "12m", "3h" or "2d".
+function formatAge(seconds) {
+    if (seconds < 60) {
+        return seconds + "s"
+    }
+    else if (seconds < 3600) {
+        return Math.floor(seconds / 60) + "m"
+    }
+    else if (seconds < 86400) {
+        return Math.floor(seconds / 3600) + "h"
+    }
+    return Math.floor(seconds / 86400) + "d"
+}
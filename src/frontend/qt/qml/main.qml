@@ -58,7 +58,7 @@ ApplicationWindow {
             }
 
             ToolButton {
-                enabled: _hostTableModel.selectedRow >= 0
+                enabled: _hostTableModel.selectedRow >= 0 && !ConfigManager.isDynamicHost(_hostTableModel.getSelectedHostId())
                 opacity: Theme.opacity(enabled)
                 icon.source: "qrc:/main/images/button/remove"
                 onClicked: {
@@ -69,6 +69,13 @@ ApplicationWindow {
                 }
             }
 
+            ToolButton {
+                icon.source: "qrc:/main/images/button/document-open"
+                onClicked: {
+                    hostImportDialog.open()
+                }
+            }
+
             ToolSeparator {
             }
 
@@ -103,6 +110,13 @@ ApplicationWindow {
             ToolSeparator {
             }
 
+            ToolButton {
+                icon.source: "qrc:/main/images/button/search"
+                onClicked: {
+                    cacheDialog.open()
+                }
+            }
+
             ToolButton {
                 icon.source: "qrc:/main/images/button/configure"
                 onClicked: {
@@ -203,7 +217,7 @@ ApplicationWindow {
             inputDialog.inputSpecs = inputSpecs
             // TODO: need to clear previous connections?
             inputDialog.onInputValuesGiven.connect((inputValues) => {
-                CommandHandler.execute_confirmed(hostId, commandId, commandParams.concat(inputValues))
+                CommandHandler.execute_with_inputs(hostId, commandId, commandParams.concat(inputValues))
             })
             inputDialog.open()
         }
@@ -231,6 +245,22 @@ ApplicationWindow {
         }
     }
 
+    Connections {
+        target: ConfigManager
+
+        function onMain_configuration_changed() {
+            reloadConfiguration()
+        }
+
+        function onHosts_changed(hostIds) {
+            reloadHosts(hostIds)
+        }
+
+        function onConfiguration_reload_failed(message) {
+            snackbarContainer.addSnackbar("Critical", message)
+        }
+    }
+
     Connections {
         target: DesktopPortal
         function onOpenFileResponse(token) {
@@ -250,6 +280,8 @@ ApplicationWindow {
         HostDataManager.receive_updates()
         // Starts the thread that receives portal responses from D-Bus.
         DesktopPortal.receiveResponses()
+        // Configuration files can also be edited outside of the application.
+        ConfigManager.watchConfigurationFiles()
 
         console.log("Current color palette: ", palette)
 
@@ -362,6 +394,17 @@ ApplicationWindow {
         }
     }
 
+    HostImportDialog {
+        id: hostImportDialog
+        visible: false
+        anchors.centerIn: parent
+        bottomMargin: 0.12 * parent.height
+
+        onConfigurationChanged: {
+            reloadConfiguration()
+        }
+    }
+
     PreferencesDialog {
         id: preferencesDialog
         visible: false
@@ -373,6 +416,13 @@ ApplicationWindow {
         }
     }
 
+    CacheDialog {
+        id: cacheDialog
+        visible: false
+        anchors.centerIn: parent
+        bottomMargin: 0.12 * parent.height
+    }
+
     TextDialog {
         id: textDialog
         visible: false
@@ -389,4 +439,17 @@ ApplicationWindow {
         let configs = ConfigManager.reloadConfiguration()
         CommandHandler.reconfigure(configs[0], configs[1])
     }
+
+    // Reconfigures only the given hosts so connections to other hosts are kept.
+    function reloadHosts(hostIds) {
+        if (hostIds.includes(_hostTableModel.getSelectedHostId())) {
+            _hostTableModel.toggleRow(_hostTableModel.selectedRow)
+        }
+
+        HostDataManager.resetHosts(hostIds)
+        _hostTableModel.displayData = HostDataManager.getDisplayData()
+
+        let configs = ConfigManager.getConfiguration()
+        CommandHandler.reconfigureHosts(configs[1], hostIds)
+    }
 }
\ No newline at end of file
@@ -20,7 +20,7 @@ use crate::{
 
 pub struct QmlFrontend {
     theme: Option<ThemeModel>,
-    update_sender_prototype: mpsc::Sender<frontend::HostDisplayData>,
+    update_sender_prototype: mpsc::Sender<frontend::HostUpdate>,
     host_data_manager: Option<HostDataManagerModel>,
     config_manager: Option<ConfigManagerModel>,
 }
@@ -106,7 +106,7 @@ impl QmlFrontend {
         ExitReason::Quit
     }
 
-    pub fn new_update_sender(&self) -> mpsc::Sender<frontend::HostDisplayData> {
+    pub fn new_update_sender(&self) -> mpsc::Sender<frontend::HostUpdate> {
         self.update_sender_prototype.clone()
     }
 }
\ No newline at end of file
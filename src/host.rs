@@ -2,7 +2,6 @@
 use serde_derive::{ Serialize, Deserialize };
 use std::{
     net::IpAddr,
-    net::Ipv4Addr,
     net::ToSocketAddrs,
     str::FromStr,
 };
@@ -23,8 +22,8 @@ impl Host {
         let mut new = Host {
             name: name.clone(),
             fqdn: fqdn.clone(),
-            ip_address: match Ipv4Addr::from_str(ip_address) {
-                Ok(address) => IpAddr::V4(address),
+            ip_address: match IpAddr::from_str(ip_address) {
+                Ok(address) => address,
                 Err(error) => return Err(format!("{}", error)),
             },
             platform: PlatformInfo::new(),
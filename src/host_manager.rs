@@ -23,8 +23,6 @@ use crate::{
     configuration,
 };
 
-const DATA_POINT_BUFFER_SIZE: usize = 4;
-
 
 pub struct HostManager {
     hosts: Arc<Mutex<HostCollection>>,
@@ -32,7 +30,7 @@ pub struct HostManager {
     data_sender_prototype: Option<mpsc::Sender<StateUpdateMessage>>,
     data_receiver: Option<mpsc::Receiver<StateUpdateMessage>>,
     receiver_thread: Option<thread::JoinHandle<()>>,
-    frontend_state_sender: Arc<Mutex<Vec<mpsc::Sender<frontend::HostDisplayData>>>>,
+    frontend_state_sender: Arc<Mutex<Vec<mpsc::Sender<frontend::HostUpdate>>>>,
 }
 
 impl HostManager {
@@ -77,6 +75,37 @@ impl HostManager {
         self.data_receiver = Some(receiver);
     }
 
+    /// Replaces or removes the given hosts. State of other hosts is kept and the receiver thread keeps running.
+    pub fn reconfigure_hosts(&mut self, config: &configuration::Hosts, host_ids: &[String]) {
+        let mut hosts = self.hosts.lock().unwrap();
+
+        for host_id in host_ids.iter() {
+            hosts.remove(host_id);
+
+            if let Some(host_config) = config.hosts.get(host_id) {
+                log::debug!("Reconfiguring host {}", host_id);
+
+                let host = match Host::new(&host_id, &host_config.address, &host_config.fqdn, &host_config.settings.clone()) {
+                    Ok(host) => host,
+                    Err(error) => {
+                        log::error!("{}", error);
+                        continue;
+                    }
+                };
+                if let Err(error) = hosts.add(host, HostStatus::Pending) {
+                    log::error!("{}", error.to_string());
+                    continue;
+                }
+
+                // Frontend has cleared the host's old data, so the new state is sent in full.
+                Self::send_full_sync(&self.frontend_state_sender.lock().unwrap(), &hosts.hosts[host_id]);
+            }
+            else {
+                log::debug!("Removed host {}", host_id);
+            }
+        }
+    }
+
     pub fn stop(&mut self) {
         self.new_state_update_sender()
             .send(StateUpdateMessage::stop())
@@ -112,11 +141,20 @@ impl HostManager {
         self.data_sender_prototype.as_ref().unwrap().clone()
     }
 
-    pub fn add_observer(&mut self, sender: mpsc::Sender<frontend::HostDisplayData>) {
+    pub fn add_observer(&mut self, sender: mpsc::Sender<frontend::HostUpdate>) {
         self.frontend_state_sender.lock().unwrap().push(sender);
     }
 
+    /// Sends the full state of every host to observers before any deltas.
     pub fn start_receiving_updates(&mut self) {
+        let hosts = self.hosts.lock().unwrap();
+        let observers = self.frontend_state_sender.lock().unwrap();
+        for host_state in hosts.hosts.values() {
+            Self::send_full_sync(&observers, host_state);
+        }
+        drop(observers);
+        drop(hosts);
+
         let thread = Self::_start_receiving_updates(
             self.hosts.clone(),
             self.data_receiver.take().unwrap(),
@@ -129,7 +167,7 @@ impl HostManager {
     fn _start_receiving_updates(
         hosts: Arc<Mutex<HostCollection>>,
         receiver: mpsc::Receiver<StateUpdateMessage>,
-        observers: Arc<Mutex<Vec<mpsc::Sender<frontend::HostDisplayData>>>>) -> thread::JoinHandle<()> {
+        observers: Arc<Mutex<Vec<mpsc::Sender<frontend::HostUpdate>>>>) -> thread::JoinHandle<()> {
 
         thread::spawn(move || {
             loop {
@@ -160,13 +198,15 @@ impl HostManager {
                 host_state.just_initialized_from_cache = false;
                 let mut new_monitoring_data: Option<MonitoringData> = None;
                 let mut new_command_results: Option<CommandResult> = None;
+                let mut new_platform: Option<platform_info::PlatformInfo> = None;
 
                 if let Some(message_data_point) = state_update.data_point {
 
                     // Specially structured data point for passing platform info here.
                     if message_data_point.is_internal() {
                         if let Ok(platform) = Self::read_platform_info(&message_data_point) {
-                            host_state.host.platform = platform;
+                            host_state.host.platform = platform.clone();
+                            new_platform = Some(platform);
                             log::debug!("[{}] Platform info updated", host_state.host.name);
 
                             // TODO: handle multiple platform info's.
@@ -185,22 +225,17 @@ impl HostManager {
                     else {
                         // Check first if there already exists a key for monitor id.
                         if let Some(monitoring_data) = host_state.monitor_data.get_mut(&state_update.module_spec.id) {
-
-                            monitoring_data.values.push_back(message_data_point.clone());
-
-                            if monitoring_data.values.len() > DATA_POINT_BUFFER_SIZE {
-                                monitoring_data.values.pop_front();
-                            }
+                            monitoring_data.push_value(message_data_point.clone());
                         }
                         else {
                             let mut new_data = MonitoringData::new(state_update.module_spec.id.clone(), state_update.display_options);
-                            new_data.values.push_back(message_data_point.clone());
+                            new_data.push_value(message_data_point.clone());
                             host_state.monitor_data.insert(state_update.module_spec.id.clone(), new_data);
                         }
 
-                        // Also add to a list of new data points.
+                        // Only the new data point is sent to the frontend.
                         let mut new = host_state.monitor_data.get(&state_update.module_spec.id).unwrap().clone();
-                        new.values = VecDeque::from(vec![message_data_point.clone()]);
+                        new.values = VecDeque::from(vec![message_data_point]);
                         new_monitoring_data = Some(new);
                     }
                 }
@@ -212,29 +247,34 @@ impl HostManager {
 
                 host_state.update_status();
 
-                // Send the state update to the front end.
+                // Send only the changes to the front end.
+                let delta = frontend::HostDisplayDelta {
+                    name: host_state.host.name.clone(),
+                    status: host_state.status,
+                    platform: new_platform,
+                    new_monitoring_data: new_monitoring_data,
+                    new_command_results: new_command_results,
+                    new_errors: state_update.errors,
+                    just_initialized: host_state.just_initialized,
+                    just_initialized_from_cache: host_state.just_initialized_from_cache,
+                    is_initialized: host_state.is_initialized,
+                };
+
                 for observer in observers.lock().unwrap().iter() {
-                    observer.send(frontend::HostDisplayData {
-                        name: host_state.host.name.clone(),
-                        domain_name: host_state.host.fqdn.clone(),
-                        platform: host_state.host.platform.clone(),
-                        ip_address: host_state.host.ip_address.clone(),
-                        monitoring_data: host_state.monitor_data.clone(),
-                        new_monitoring_data: new_monitoring_data.clone(),
-                        command_results: host_state.command_results.clone(),
-                        new_command_results: new_command_results.clone(),
-                        new_errors: state_update.errors.clone(),
-                        status: host_state.status,
-                        just_initialized: host_state.just_initialized,
-                        just_initialized_from_cache: host_state.just_initialized_from_cache,
-                        is_initialized: host_state.is_initialized,
-                        stop: false,
-                    }).unwrap();
+                    observer.send(frontend::HostUpdate::Delta(delta.clone())).unwrap();
                 }
             }
         })
     }
 
+    fn send_full_sync(observers: &[mpsc::Sender<frontend::HostUpdate>], host_state: &HostState) {
+        for observer in observers.iter() {
+            observer.send(frontend::HostUpdate::FullSync(host_state.to_display_data())).unwrap_or_else(|error| {
+                log::error!("Couldn't send full sync to frontend: {}", error);
+            });
+        }
+    }
+
     pub fn get_display_data(&self) -> frontend::DisplayData {
         let mut display_data = frontend::DisplayData::new();
 
@@ -254,22 +294,7 @@ impl HostManager {
         }
 
         for (host_name, state) in hosts.hosts.iter() {
-            display_data.hosts.insert(host_name.clone(), frontend::HostDisplayData {
-                name: state.host.name.clone(),
-                domain_name: state.host.fqdn.clone(),
-                platform: state.host.platform.clone(),
-                ip_address: state.host.ip_address.clone(),
-                monitoring_data: state.monitor_data.clone(),
-                new_monitoring_data: None,
-                command_results: state.command_results.clone(),
-                new_command_results: None,
-                new_errors: Vec::new(),
-                status: state.status,
-                just_initialized: state.just_initialized,
-                just_initialized_from_cache: state.just_initialized_from_cache,
-                is_initialized: state.is_initialized,
-                stop: false,
-            });
+            display_data.hosts.insert(host_name.clone(), state.to_display_data());
         }
 
         display_data.table_headers = vec![String::from("Status"), String::from("Name"), String::from("FQDN"), String::from("IP address")];
@@ -292,6 +317,9 @@ impl HostManager {
                 "architecture" => {
                     platform.architecture = platform_info::Architecture::from(&data.value)
                 },
+                "agent_report_version" => {
+                    platform.agent_report_version = data.value.parse::<u32>().map_err(|error| error.to_string())?
+                },
                 _ => return Err(String::from("Invalid platform info data"))
             }
         }
@@ -347,6 +375,10 @@ impl HostCollection {
         Ok(())
     }
 
+    fn remove(&mut self, host_name: &String) {
+        self.hosts.remove(host_name);
+    }
+
     fn clear(&mut self) {
         self.hosts.clear();
     }
@@ -377,6 +409,19 @@ impl HostState {
         }
     }
 
+    fn to_display_data(&self) -> frontend::HostDisplayData {
+        frontend::HostDisplayData {
+            name: self.host.name.clone(),
+            domain_name: self.host.fqdn.clone(),
+            platform: self.host.platform.clone(),
+            ip_address: self.host.ip_address.clone(),
+            monitoring_data: self.monitor_data.clone(),
+            command_results: self.command_results.clone(),
+            status: self.status,
+            is_initialized: self.is_initialized,
+        }
+    }
+
     fn update_status(&mut self) {
         let critical_monitor = &self.monitor_data.iter().find(|(_, data)| {
             // There should always be some monitoring data available at this point.
@@ -0,0 +1,220 @@
+pub mod ssh_config;
+pub mod ansible;
+pub mod dynamic;
+
+use std::{
+    collections::HashMap,
+    fmt,
+    net::IpAddr,
+    str::FromStr,
+};
+
+use crate::configuration::{
+    ConfigGroup,
+    ConnectorConfig,
+    Groups,
+    HostSettings,
+    Hosts,
+};
+
+/// Groups that are added to every imported host if they exist in the configuration.
+const BASE_GROUPS: [&str; 2] = ["defaults", "linux"];
+
+
+/// Hosts and groups read from an external source, e.g. ~/.ssh/config or an Ansible inventory.
+/// Imported module settings are set on host-level (host_connectors etc.) and on the groups.
+#[derive(Default, Clone)]
+pub struct Inventory {
+    pub hosts: HashMap<String, HostSettings>,
+    pub groups: HashMap<String, ConfigGroup>,
+}
+
+impl Inventory {
+    /// Reads inventory of the given type. Supported types are "ssh-config" and "ansible".
+    pub fn read(source_type: &str, path: &String) -> Result<Inventory, String> {
+        match source_type {
+            "ssh-config" => ssh_config::read(path),
+            "ansible" => ansible::read(path),
+            _ => Err(format!("Unsupported inventory type: {}", source_type)),
+        }
+    }
+
+    /// Compares imported hosts and groups against current configuration without changing anything.
+    pub fn preview(&self, hosts_config: &Hosts, groups_config: &Groups) -> ImportPreview {
+        let mut preview = ImportPreview::default();
+
+        for (host_id, imported_host) in self.hosts.iter() {
+            match hosts_config.hosts.get(host_id) {
+                None => preview.new_hosts.push(host_id.clone()),
+                Some(existing_host) => {
+                    let merged_host = Self::merge_host(existing_host, imported_host);
+                    if merged_host.address != existing_host.address ||
+                       merged_host.fqdn != existing_host.fqdn ||
+                       merged_host.groups != existing_host.groups ||
+                       merged_host.host_connectors != existing_host.host_connectors ||
+                       merged_host.vars != existing_host.vars {
+                        preview.updated_hosts.push(host_id.clone());
+                    }
+                    else {
+                        preview.unchanged_hosts.push(host_id.clone());
+                    }
+                }
+            }
+        }
+
+        preview.new_groups = self.groups.keys()
+            .filter(|group_id| !groups_config.groups.contains_key(*group_id))
+            .cloned()
+            .collect();
+
+        preview.new_hosts.sort();
+        preview.updated_hosts.sort();
+        preview.unchanged_hosts.sort();
+        preview.new_groups.sort();
+        preview
+    }
+
+    /// Merges imported hosts and groups into the configuration.
+    /// Existing groups are left untouched and existing hosts keep their current groups and settings
+    /// unless the imported data overrides them.
+    pub fn merge(&self, hosts_config: &mut Hosts, groups_config: &mut Groups) -> ImportPreview {
+        let preview = self.preview(hosts_config, groups_config);
+
+        for (group_id, imported_group) in self.groups.iter() {
+            if !groups_config.groups.contains_key(group_id) {
+                groups_config.groups.insert(group_id.clone(), imported_group.clone());
+            }
+        }
+
+        for (host_id, imported_host) in self.hosts.iter() {
+            let merged_host = match hosts_config.hosts.get(host_id) {
+                Some(existing_host) => Self::merge_host(existing_host, imported_host),
+                None => {
+                    let mut new_host = imported_host.clone();
+                    let base_groups = BASE_GROUPS.iter()
+                        .map(|group_id| group_id.to_string())
+                        .filter(|group_id| groups_config.groups.contains_key(group_id) && !new_host.groups.contains(group_id))
+                        .collect::<Vec<String>>();
+
+                    new_host.groups = base_groups.into_iter().chain(new_host.groups).collect();
+                    new_host
+                }
+            };
+
+            hosts_config.hosts.insert(host_id.clone(), merged_host);
+        }
+
+        preview
+    }
+
+    /// Applies group settings directly to hosts, in the order the groups are listed. Host-level settings take precedence.
+    /// Group memberships are kept, but the groups themselves are removed.
+    pub fn flatten_groups(&mut self) {
+        for host_settings in self.hosts.values_mut() {
+            let mut connectors = HashMap::<String, ConnectorConfig>::new();
+            let mut settings = Vec::new();
+            let mut vars = HashMap::<String, String>::new();
+
+            for group in host_settings.groups.iter().filter_map(|group_id| self.groups.get(group_id)) {
+                for (connector_id, connector_config) in group.connectors.iter() {
+                    connectors.entry(connector_id.clone()).or_default().settings.extend(connector_config.settings.clone());
+                }
+
+                if !group.host_settings.is_empty() {
+                    settings = group.host_settings.clone();
+                }
+
+                vars.extend(group.vars.clone());
+            }
+
+            for (connector_id, connector_config) in host_settings.host_connectors.iter() {
+                connectors.entry(connector_id.clone()).or_default().settings.extend(connector_config.settings.clone());
+            }
+
+            host_settings.host_connectors = connectors;
+            vars.extend(std::mem::take(&mut host_settings.vars));
+            host_settings.vars = vars;
+            if host_settings.settings.is_empty() {
+                host_settings.settings = settings;
+            }
+        }
+
+        self.groups.clear();
+    }
+
+    fn merge_host(existing_host: &HostSettings, imported_host: &HostSettings) -> HostSettings {
+        let mut merged_host = existing_host.clone();
+
+        if !HostSettings::is_default_address(&imported_host.address) || !imported_host.fqdn.is_empty() {
+            merged_host.address = imported_host.address.clone();
+            merged_host.fqdn = imported_host.fqdn.clone();
+        }
+
+        for group_id in imported_host.groups.iter() {
+            if !merged_host.groups.contains(group_id) {
+                merged_host.groups.push(group_id.clone());
+            }
+        }
+
+        for (connector_id, imported_connector) in imported_host.host_connectors.iter() {
+            merged_host.host_connectors.entry(connector_id.clone())
+                .or_insert_with(ConnectorConfig::default)
+                .settings.extend(imported_connector.settings.clone());
+        }
+
+        merged_host.vars.extend(imported_host.vars.clone());
+        merged_host
+    }
+
+    /// Sets address or FQDN depending on whether the value is an IP address.
+    pub(crate) fn set_host_address(host_settings: &mut HostSettings, address: &str) {
+        // IPv6 literals can be enclosed in brackets, e.g. in ssh config.
+        let address = address.trim_start_matches('[').trim_end_matches(']');
+        if IpAddr::from_str(address).is_ok() {
+            host_settings.address = address.to_string();
+            host_settings.fqdn = String::new();
+        }
+        else {
+            host_settings.address = HostSettings::default_address();
+            host_settings.fqdn = address.to_string();
+        }
+    }
+
+    pub(crate) fn set_connector_setting(connectors: &mut HashMap<String, ConnectorConfig>, connector_id: &str, key: &str, value: &str) {
+        connectors.entry(connector_id.to_string())
+                  .or_insert_with(ConnectorConfig::default)
+                  .settings.insert(key.to_string(), value.to_string());
+    }
+
+    /// Host and group names are used as identifiers in configuration, so only allow a safe subset of characters.
+    pub(crate) fn sanitize_name(name: &str) -> String {
+        name.chars().map(|char| if char.is_alphanumeric() || char == '-' || char == '.' { char } else { '-' }).collect()
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct ImportPreview {
+    pub new_hosts: Vec<String>,
+    pub updated_hosts: Vec<String>,
+    pub unchanged_hosts: Vec<String>,
+    pub new_groups: Vec<String>,
+}
+
+impl fmt::Display for ImportPreview {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sections = [
+            ("New hosts", &self.new_hosts),
+            ("Updated hosts", &self.updated_hosts),
+            ("Unchanged hosts", &self.unchanged_hosts),
+            ("New groups", &self.new_groups),
+        ];
+
+        for (title, entries) in sections {
+            writeln!(f, "{} ({}):", title, entries.len())?;
+            for entry in entries.iter() {
+                writeln!(f, "  {}", entry)?;
+            }
+        }
+        Ok(())
+    }
+}
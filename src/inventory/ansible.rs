@@ -0,0 +1,389 @@
+use std::{
+    collections::HashMap,
+    fs,
+};
+
+use serde_yaml::Value;
+
+use crate::configuration::{ConfigGroup, HostSettings};
+use crate::host::HostSetting;
+use super::Inventory;
+
+/// Groups that Ansible creates implicitly.
+const IMPLICIT_GROUPS: [&str; 2] = ["all", "ungrouped"];
+
+
+#[derive(Default)]
+pub(crate) struct AnsibleGroup {
+    pub hosts: Vec<String>,
+    pub vars: HashMap<String, String>,
+    pub children: Vec<String>,
+}
+
+/// Inventory in Ansible's own terms before converting to hosts and groups.
+#[derive(Default)]
+pub(crate) struct AnsibleInventory {
+    pub groups: HashMap<String, AnsibleGroup>,
+    pub host_vars: HashMap<String, HashMap<String, String>>,
+}
+
+/// Reads a static Ansible inventory. Both INI and YAML formats are supported.
+pub fn read(path: &String) -> Result<Inventory, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|error| format!("Failed to read {}: {}", path, error))?;
+
+    let is_yaml = path.ends_with(".yml") || path.ends_with(".yaml") ||
+                  (!path.ends_with(".ini") && serde_yaml::from_str::<Value>(&contents).map(|value| value.is_mapping()).unwrap_or(false));
+
+    let ansible_inventory = if is_yaml {
+        parse_yaml(&contents)?
+    }
+    else {
+        parse_ini(&contents)?
+    };
+
+    Ok(ansible_inventory.to_inventory())
+}
+
+pub(crate) fn parse_ini(contents: &str) -> Result<AnsibleInventory, String> {
+    let mut inventory = AnsibleInventory::default();
+    // Hosts before the first section belong to "ungrouped".
+    let mut section = (String::from("ungrouped"), String::from("hosts"));
+
+    for (line_number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if line.starts_with('[') && line.ends_with(']') {
+            let section_name = &line[1..line.len() - 1];
+            section = match section_name.split_once(':') {
+                Some((group_name, section_type)) => (group_name.to_string(), section_type.to_string()),
+                None => (section_name.to_string(), String::from("hosts")),
+            };
+            inventory.groups.entry(section.0.clone()).or_default();
+            continue;
+        }
+
+        let group = inventory.groups.entry(section.0.clone()).or_default();
+        match section.1.as_str() {
+            "hosts" => {
+                let tokens = split_quoted(line);
+                let host_vars = parse_key_values(&tokens[1..]);
+                for host_name in expand_host_pattern(&tokens[0])? {
+                    group.hosts.push(host_name.clone());
+                    inventory.host_vars.entry(host_name).or_default().extend(host_vars.clone());
+                }
+            },
+            "vars" => {
+                group.vars.extend(parse_key_values(&split_quoted(line)));
+            },
+            "children" => {
+                group.children.push(line.to_string());
+            },
+            _ => return Err(format!("Invalid section type on line {}: {}", line_number + 1, section.1)),
+        }
+    }
+
+    Ok(inventory)
+}
+
+pub(crate) fn parse_yaml(contents: &str) -> Result<AnsibleInventory, String> {
+    let root = serde_yaml::from_str::<Value>(contents).map_err(|error| error.to_string())?;
+    let mut inventory = AnsibleInventory::default();
+
+    let root_groups = root.as_mapping().ok_or(String::from("Inventory root is not a mapping"))?;
+    for (group_name, group_value) in root_groups.iter() {
+        parse_yaml_group(&mut inventory, &value_to_string(group_name), group_value)?;
+    }
+
+    Ok(inventory)
+}
+
+fn parse_yaml_group(inventory: &mut AnsibleInventory, group_name: &String, group_value: &Value) -> Result<(), String> {
+    inventory.groups.entry(group_name.clone()).or_default();
+
+    if let Some(hosts) = group_value.get("hosts").and_then(|hosts| hosts.as_mapping()) {
+        for (host_pattern, host_vars_value) in hosts.iter() {
+            let host_vars = mapping_to_strings(host_vars_value);
+            for host_name in expand_host_pattern(&value_to_string(host_pattern))? {
+                inventory.groups.get_mut(group_name).unwrap().hosts.push(host_name.clone());
+                inventory.host_vars.entry(host_name).or_default().extend(host_vars.clone());
+            }
+        }
+    }
+
+    if let Some(vars) = group_value.get("vars") {
+        inventory.groups.get_mut(group_name).unwrap().vars.extend(mapping_to_strings(vars));
+    }
+
+    if let Some(children) = group_value.get("children").and_then(|children| children.as_mapping()) {
+        for (child_name, child_value) in children.iter() {
+            let child_name = value_to_string(child_name);
+            inventory.groups.get_mut(group_name).unwrap().children.push(child_name.clone());
+            parse_yaml_group(inventory, &child_name, child_value)?;
+        }
+    }
+
+    Ok(())
+}
+
+impl AnsibleInventory {
+    /// Converts to hosts and groups. Group membership is flattened so hosts in child groups are also members of the parent groups.
+    /// Parent groups are listed first so that settings from child groups take precedence, as in Ansible.
+    pub(crate) fn to_inventory(&self) -> Inventory {
+        let mut inventory = Inventory::default();
+
+        for (group_name, group) in self.groups.iter() {
+            let group_id = Inventory::sanitize_name(group_name);
+            let config_group = Self::vars_to_group(&group.vars);
+
+            // Implicit groups are only created if they contain relevant variables.
+            let is_empty = config_group.connectors.is_empty() && config_group.host_settings.is_empty() && config_group.vars.is_empty();
+            if !(IMPLICIT_GROUPS.contains(&group_name.as_str()) && is_empty) {
+                inventory.groups.insert(group_id, config_group);
+            }
+        }
+
+        let mut all_host_names = self.host_vars.keys().cloned().collect::<Vec<String>>();
+        all_host_names.sort();
+
+        for host_name in all_host_names {
+            let mut host_groups = self.groups.iter()
+                .filter(|(_, group)| group.hosts.contains(&host_name))
+                .map(|(group_name, _)| group_name.clone())
+                .collect::<Vec<String>>();
+
+            // Add parent groups.
+            let mut index = 0;
+            while index < host_groups.len() {
+                let child_name = host_groups[index].clone();
+                for (group_name, group) in self.groups.iter() {
+                    if group.children.contains(&child_name) && !host_groups.contains(group_name) {
+                        host_groups.push(group_name.clone());
+                    }
+                }
+                index += 1;
+            }
+
+            // Every group is a descendant of "all".
+            if !host_groups.contains(&String::from("all")) {
+                host_groups.push(String::from("all"));
+            }
+
+            host_groups.sort_by_key(|group_name| (self.get_depth(group_name), group_name.clone()));
+
+            let mut host_settings = HostSettings {
+                groups: host_groups.iter()
+                    .map(|group_name| Inventory::sanitize_name(group_name))
+                    .filter(|group_id| inventory.groups.contains_key(group_id))
+                    .collect(),
+                ..Default::default()
+            };
+
+            let host_vars = self.host_vars.get(&host_name).cloned().unwrap_or_default();
+            Inventory::set_host_address(&mut host_settings, host_vars.get("ansible_host").unwrap_or(&host_name));
+
+            let host_group = Self::vars_to_group(&host_vars);
+            host_settings.host_connectors = host_group.connectors;
+            host_settings.vars = host_group.vars;
+            if !host_group.host_settings.is_empty() {
+                host_settings.settings = host_group.host_settings;
+            }
+
+            inventory.hosts.insert(Inventory::sanitize_name(&host_name), host_settings);
+        }
+
+        inventory
+    }
+
+    /// Distance from the "all" group.
+    fn get_depth(&self, group_name: &String) -> usize {
+        let mut depth = 0;
+        let mut current = vec![group_name.clone()];
+
+        while !current.is_empty() && !current.contains(&String::from("all")) && depth < self.groups.len() {
+            current = self.groups.iter()
+                .filter(|(_, group)| current.iter().any(|child_name| group.children.contains(child_name)))
+                .map(|(parent_name, _)| parent_name.clone())
+                .collect();
+            depth += 1;
+        }
+
+        depth
+    }
+
+    /// Maps connection variables to ssh-connector settings and host settings.
+    /// Other variables, excluding Ansible's own, are kept as configuration variables.
+    fn vars_to_group(vars: &HashMap<String, String>) -> ConfigGroup {
+        let mut group = ConfigGroup::default();
+
+        for (key, value) in vars.iter() {
+            match key.as_str() {
+                "ansible_port" | "ansible_ssh_port" =>
+                    Inventory::set_connector_setting(&mut group.connectors, "ssh", "port", value),
+                "ansible_user" | "ansible_ssh_user" =>
+                    Inventory::set_connector_setting(&mut group.connectors, "ssh", "username", value),
+                "ansible_ssh_private_key_file" | "ansible_private_key_file" =>
+                    Inventory::set_connector_setting(&mut group.connectors, "ssh", "private_key_path", value),
+                "ansible_become" => {
+                    if ["true", "yes", "1"].contains(&value.to_lowercase().as_str()) {
+                        group.host_settings = vec![HostSetting::UseSudo];
+                    }
+                },
+                // Other variables can be referenced in module settings.
+                _ => {
+                    if !key.starts_with("ansible_") {
+                        group.vars.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+
+        group
+    }
+}
+
+/// Expands numeric ranges in host patterns, e.g. "web[01:03].example.com".
+fn expand_host_pattern(pattern: &str) -> Result<Vec<String>, String> {
+    let (prefix, rest) = match pattern.split_once('[') {
+        Some(split) => split,
+        None => return Ok(vec![pattern.to_string()]),
+    };
+
+    let (range, suffix) = rest.split_once(']').ok_or(format!("Invalid host pattern: {}", pattern))?;
+    let (start, end) = range.split_once(':').ok_or(format!("Invalid host range: {}", pattern))?;
+    // Ranges with step (e.g. [1:10:2]) are not supported.
+    let start_number = start.parse::<u32>().map_err(|_| format!("Unsupported host range: {}", pattern))?;
+    let end_number = end.parse::<u32>().map_err(|_| format!("Unsupported host range: {}", pattern))?;
+    let width = if start.starts_with('0') { start.len() } else { 0 };
+
+    let mut result = Vec::new();
+    for number in start_number..=end_number {
+        for expanded_suffix in expand_host_pattern(suffix)? {
+            result.push(format!("{}{:0width$}{}", prefix, number, expanded_suffix, width = width));
+        }
+    }
+    Ok(result)
+}
+
+/// Splits by whitespace while keeping quoted values together. Quotes are removed.
+fn split_quoted(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quote_char: Option<char> = None;
+
+    for char in line.chars() {
+        match quote_char {
+            Some(quote) if char == quote => quote_char = None,
+            Some(_) => current.push(char),
+            None if char == '"' || char == '\'' => quote_char = Some(char),
+            None if char.is_whitespace() => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            },
+            None => current.push(char),
+        }
+    }
+
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn parse_key_values(tokens: &[String]) -> HashMap<String, String> {
+    tokens.iter()
+          .filter_map(|token| token.split_once('='))
+          .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+          .collect()
+}
+
+pub(crate) fn mapping_to_strings(value: &Value) -> HashMap<String, String> {
+    match value.as_mapping() {
+        Some(mapping) => mapping.iter().map(|(key, value)| (value_to_string(key), value_to_string(value))).collect(),
+        None => HashMap::new(),
+    }
+}
+
+pub(crate) fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        Value::Bool(boolean) => boolean.to_string(),
+        Value::Number(number) => number.to_string(),
+        Value::Null => String::new(),
+        // Complex values are not used for anything, so a serialized form is good enough.
+        _ => serde_yaml::to_string(value).unwrap_or_default().trim().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_host_pattern() {
+        assert_eq!(expand_host_pattern("web[01:03].example.com").unwrap(),
+                   vec!["web01.example.com", "web02.example.com", "web03.example.com"]);
+        assert_eq!(expand_host_pattern("db[8:10]").unwrap(), vec!["db8", "db9", "db10"]);
+        assert_eq!(expand_host_pattern("rack[1:2]-node[1:2]").unwrap(), vec!["rack1-node1", "rack1-node2", "rack2-node1", "rack2-node2"]);
+        assert_eq!(expand_host_pattern("plain").unwrap(), vec!["plain"]);
+        assert!(expand_host_pattern("web[1:3:2]").is_err());
+        assert!(expand_host_pattern("web[1:3").is_err());
+    }
+
+    #[test]
+    fn test_ini_children() {
+        let inventory = parse_ini("
+            [web]
+            web[01:02] ansible_user=deploy
+
+            [db]
+            db01 ansible_host=10.0.0.5
+
+            [production:children]
+            web
+            db
+
+            [production:vars]
+            ansible_port=2222
+        ").unwrap().to_inventory();
+
+        assert_eq!(inventory.hosts.len(), 3);
+        assert_eq!(inventory.hosts["web01"].groups, vec!["production", "web"]);
+        assert_eq!(inventory.hosts["db01"].groups, vec!["production", "db"]);
+        assert_eq!(inventory.hosts["db01"].address, "10.0.0.5");
+        assert_eq!(inventory.hosts["web02"].host_connectors["ssh"].settings["username"], "deploy");
+        assert_eq!(inventory.groups["production"].connectors["ssh"].settings["port"], "2222");
+    }
+
+    #[test]
+    fn test_yaml_nested_children() {
+        let inventory = parse_yaml("
+            all:
+              vars:
+                ansible_user: admin
+              children:
+                datacenter:
+                  children:
+                    web:
+                      hosts:
+                        web[1:2]:
+                    db:
+                      hosts:
+                        db1:
+                          ansible_port: 2200
+                      vars:
+                        ansible_become: true
+        ").unwrap().to_inventory();
+
+        assert_eq!(inventory.hosts.len(), 3);
+        assert_eq!(inventory.hosts["web1"].groups, vec!["all", "datacenter", "web"]);
+        assert_eq!(inventory.hosts["db1"].groups, vec!["all", "datacenter", "db"]);
+        assert_eq!(inventory.hosts["db1"].host_connectors["ssh"].settings["port"], "2200");
+        assert!(inventory.groups["db"].host_settings == vec![HostSetting::UseSudo]);
+        assert!(!inventory.groups.contains_key("ungrouped"));
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    io::Read,
+    path::Path,
+    process,
+    thread,
+    time::Duration,
+    time::Instant,
+};
+
+use serde_yaml::Value;
+
+use crate::configuration::DynamicInventorySettings;
+use super::Inventory;
+use super::ansible::{AnsibleInventory, mapping_to_strings, value_to_string};
+
+
+/// Runs the inventory executable and parses its output.
+/// Output follows Ansible's dynamic inventory convention (output of `--list`).
+/// Since groups from the inventory only exist for membership, group variables are applied to hosts directly.
+pub fn read(settings: &DynamicInventorySettings, config_dir: &Path) -> Result<Inventory, String> {
+    let command_path = config_dir.join(&settings.command);
+
+    let output = run_with_timeout(
+        process::Command::new(&command_path).args(&settings.arguments),
+        Duration::from_secs(settings.timeout)
+    ).map_err(|error| format!("Failed to run {}: {}", command_path.display(), error))?;
+
+    if !output.status.success() {
+        return Err(format!("{} exited with {}: {}", command_path.display(), output.status, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    let ansible_inventory = parse_json(&String::from_utf8_lossy(&output.stdout))?;
+    let mut inventory = ansible_inventory.to_inventory();
+    inventory.flatten_groups();
+    Ok(inventory)
+}
+
+/// Like `Command::output()`, but kills the process if it doesn't exit in time.
+/// Otherwise, a hanging executable would block startup and configuration reloads.
+fn run_with_timeout(command: &mut process::Command, timeout: Duration) -> Result<process::Output, String> {
+    let mut child = command.stdin(process::Stdio::null())
+                           .stdout(process::Stdio::piped())
+                           .stderr(process::Stdio::piped())
+                           .spawn()
+                           .map_err(|error| error.to_string())?;
+
+    // Pipes are read in separate threads so that the process doesn't block on a full pipe.
+    let stdout_reader = read_in_thread(child.stdout.take());
+    let stderr_reader = read_in_thread(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|error| error.to_string())? {
+            break status;
+        }
+
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("Timed out after {} seconds", timeout.as_secs()));
+        }
+
+        thread::sleep(Duration::from_millis(100));
+    };
+
+    Ok(process::Output {
+        status: status,
+        stdout: stdout_reader.join().unwrap_or_default(),
+        stderr: stderr_reader.join().unwrap_or_default(),
+    })
+}
+
+fn read_in_thread<T: Read + Send + 'static>(pipe: Option<T>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
+
+/// Groups are either a list of hosts or an object with "hosts", "vars" and "children".
+/// Host variables are under "_meta.hostvars".
+pub(crate) fn parse_json(contents: &str) -> Result<AnsibleInventory, String> {
+    // JSON is also valid YAML, which allows sharing the parsing helpers with static inventories.
+    let root = serde_yaml::from_str::<Value>(contents).map_err(|error| format!("Invalid inventory output: {}", error))?;
+    let root_mapping = root.as_mapping().ok_or(String::from("Inventory output is not a JSON object"))?;
+    let mut inventory = AnsibleInventory::default();
+
+    for (group_name, group_value) in root_mapping.iter() {
+        let group_name = value_to_string(group_name);
+        if group_name == "_meta" {
+            if let Some(host_vars) = group_value.get("hostvars").and_then(|host_vars| host_vars.as_mapping()) {
+                for (host_name, vars) in host_vars.iter() {
+                    inventory.host_vars.entry(value_to_string(host_name)).or_default().extend(mapping_to_strings(vars));
+                }
+            }
+            continue;
+        }
+
+        let group = inventory.groups.entry(group_name).or_default();
+        let host_list = match group_value {
+            Value::Sequence(_) => Some(group_value),
+            _ => group_value.get("hosts"),
+        };
+
+        if let Some(hosts) = host_list.and_then(|hosts| hosts.as_sequence()) {
+            group.hosts.extend(hosts.iter().map(value_to_string));
+        }
+
+        if let Some(vars) = group_value.get("vars") {
+            group.vars.extend(mapping_to_strings(vars));
+        }
+
+        if let Some(children) = group_value.get("children").and_then(|children| children.as_sequence()) {
+            group.children.extend(children.iter().map(value_to_string));
+        }
+    }
+
+    // Hosts listed only in groups have no variables but still need an entry.
+    let listed_hosts = inventory.groups.values().flat_map(|group| group.hosts.clone()).collect::<Vec<String>>();
+    for host_name in listed_hosts {
+        inventory.host_vars.entry(host_name).or_default();
+    }
+
+    Ok(inventory)
+}
@@ -0,0 +1,240 @@
+use std::{
+    collections::HashMap,
+    fs,
+};
+
+use crate::configuration::HostSettings;
+use super::Inventory;
+
+/// Keywords that are mapped to ssh-connector settings.
+const SUPPORTED_KEYWORDS: [&str; 5] = ["hostname", "port", "user", "identityfile", "proxyjump"];
+
+
+struct HostBlock {
+    patterns: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+/// Reads hosts from OpenSSH client configuration (e.g. ~/.ssh/config).
+/// Every non-wildcard Host-entry becomes a host. Wildcard entries (e.g. "Host *") are applied to matching hosts
+/// in the same way as the ssh client does: the first obtained value for each option is used.
+pub fn read(path: &String) -> Result<Inventory, String> {
+    let contents = fs::read_to_string(expand_home(path))
+        .map_err(|error| format!("Failed to read {}: {}", path, error))?;
+
+    parse(&contents)
+}
+
+pub fn parse(contents: &str) -> Result<Inventory, String> {
+    let mut blocks = Vec::<HostBlock>::new();
+    // Options before the first Host-line apply to all hosts.
+    let mut current_block = HostBlock { patterns: vec![String::from("*")], options: Vec::new() };
+    let mut skip_block = false;
+
+    for (line_number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (keyword, value) = match split_keyword(line) {
+            Some(keyword_and_value) => keyword_and_value,
+            None => return Err(format!("Invalid line {}: {}", line_number + 1, line)),
+        };
+
+        match keyword.as_str() {
+            "host" => {
+                blocks.push(current_block);
+                current_block = HostBlock {
+                    patterns: value.split_whitespace().map(|pattern| pattern.trim_matches('"').to_string()).collect(),
+                    options: Vec::new(),
+                };
+                skip_block = false;
+            },
+            "match" => {
+                log::warn!("Match-blocks are not supported, skipping line {}", line_number + 1);
+                blocks.push(current_block);
+                current_block = HostBlock { patterns: Vec::new(), options: Vec::new() };
+                skip_block = true;
+            },
+            "include" => {
+                log::warn!("Include-directives are not supported, skipping line {}", line_number + 1);
+            },
+            _ => {
+                if !skip_block && SUPPORTED_KEYWORDS.contains(&keyword.as_str()) {
+                    current_block.options.push((keyword, value.trim_matches('"').to_string()));
+                }
+            }
+        }
+    }
+    blocks.push(current_block);
+
+    let mut inventory = Inventory::default();
+
+    let host_aliases = blocks.iter()
+        .flat_map(|block| block.patterns.iter())
+        .filter(|pattern| !pattern.contains(['*', '?', '!']))
+        .cloned()
+        .collect::<Vec<String>>();
+
+    for alias in host_aliases {
+        let host_id = Inventory::sanitize_name(&alias);
+        if inventory.hosts.contains_key(&host_id) {
+            continue;
+        }
+
+        // First obtained value is used.
+        let mut options = HashMap::<String, String>::new();
+        for block in blocks.iter().filter(|block| block_matches(block, &alias)) {
+            for (keyword, value) in block.options.iter() {
+                options.entry(keyword.clone()).or_insert(value.clone());
+            }
+        }
+
+        let mut host_settings = HostSettings::default();
+        let hostname = options.get("hostname").cloned().unwrap_or(alias.clone()).replace("%h", &alias);
+        Inventory::set_host_address(&mut host_settings, &hostname);
+
+        if let Some(port) = options.get("port") {
+            Inventory::set_connector_setting(&mut host_settings.host_connectors, "ssh", "port", port);
+        }
+        if let Some(user) = options.get("user") {
+            Inventory::set_connector_setting(&mut host_settings.host_connectors, "ssh", "username", user);
+        }
+        if let Some(identity_file) = options.get("identityfile") {
+            Inventory::set_connector_setting(&mut host_settings.host_connectors, "ssh", "private_key_path", &expand_home(identity_file));
+        }
+        if let Some(proxy_jump) = options.get("proxyjump") {
+            if proxy_jump != "none" {
+                Inventory::set_connector_setting(&mut host_settings.host_connectors, "ssh", "proxy_jump", proxy_jump);
+            }
+        }
+
+        inventory.hosts.insert(host_id, host_settings);
+    }
+
+    Ok(inventory)
+}
+
+/// Options can be given as "Keyword value" or "Keyword=value". Keywords are case-insensitive.
+fn split_keyword(line: &str) -> Option<(String, String)> {
+    let separator_index = line.find(|char: char| char.is_whitespace() || char == '=')?;
+    let keyword = line[..separator_index].to_lowercase();
+    let value = line[separator_index..].trim_start_matches(|char: char| char.is_whitespace() || char == '=').trim_end();
+
+    if value.is_empty() {
+        None
+    }
+    else {
+        Some((keyword, value.to_string()))
+    }
+}
+
+fn block_matches(block: &HostBlock, alias: &str) -> bool {
+    let mut matches = false;
+    for pattern in block.patterns.iter() {
+        if let Some(negated_pattern) = pattern.strip_prefix('!') {
+            if wildcard_match(negated_pattern, alias) {
+                return false;
+            }
+        }
+        else if wildcard_match(pattern, alias) {
+            matches = true;
+        }
+    }
+    matches
+}
+
+/// Supports the same wildcards as ssh_config: "*" for zero or more characters and "?" for exactly one.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<char>>();
+    let text = text.chars().collect::<Vec<char>>();
+    let (mut pattern_index, mut text_index) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while text_index < text.len() {
+        if pattern_index < pattern.len() && (pattern[pattern_index] == '?' || pattern[pattern_index] == text[text_index]) {
+            pattern_index += 1;
+            text_index += 1;
+        }
+        else if pattern_index < pattern.len() && pattern[pattern_index] == '*' {
+            backtrack = Some((pattern_index, text_index));
+            pattern_index += 1;
+        }
+        else if let Some((star_index, star_text_index)) = backtrack {
+            pattern_index = star_index + 1;
+            text_index = star_text_index + 1;
+            backtrack = Some((star_index, star_text_index + 1));
+        }
+        else {
+            return false;
+        }
+    }
+
+    pattern[pattern_index..].iter().all(|char| *char == '*')
+}
+
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(relative_path), Ok(home)) => format!("{}/{}", home, relative_path),
+        _ => path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_ssh_setting(inventory: &Inventory, host_id: &str, key: &str) -> Option<String> {
+        inventory.hosts[host_id].host_connectors.get("ssh").and_then(|connector| connector.settings.get(key).cloned())
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*", "web01"));
+        assert!(wildcard_match("web*", "web01"));
+        assert!(wildcard_match("web??", "web01"));
+        assert!(wildcard_match("*.example.com", "db.example.com"));
+        assert!(wildcard_match("w*b*1", "web01"));
+        assert!(!wildcard_match("web?", "web01"));
+        assert!(!wildcard_match("db*", "web01"));
+        assert!(!wildcard_match("*.example.com", "example.com"));
+    }
+
+    #[test]
+    fn test_wildcard_blocks() {
+        let inventory = parse("
+            Host web01 web02
+                User deploy
+
+            Host * !web02
+                Port 2222
+
+            Host *
+                User admin
+                Port 22
+        ").unwrap();
+
+        assert_eq!(inventory.hosts.len(), 2);
+        assert_eq!(get_ssh_setting(&inventory, "web01", "username").unwrap(), "deploy");
+        assert_eq!(get_ssh_setting(&inventory, "web01", "port").unwrap(), "2222");
+        assert_eq!(get_ssh_setting(&inventory, "web02", "port").unwrap(), "22");
+    }
+
+    #[test]
+    fn test_host_address() {
+        let inventory = parse("
+            Host ipv4
+                HostName 10.0.0.1
+            Host ipv6
+                HostName fd00::1
+            Host named
+                HostName %h.example.com
+        ").unwrap();
+
+        assert_eq!(inventory.hosts["ipv4"].address, "10.0.0.1");
+        assert_eq!(inventory.hosts["ipv6"].address, "fd00::1");
+        assert!(inventory.hosts["ipv6"].fqdn.is_empty());
+        assert_eq!(inventory.hosts["named"].fqdn, "named.example.com");
+    }
+}
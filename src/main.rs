@@ -14,6 +14,7 @@ mod connection_manager;
 mod command_handler;
 mod file_handler;
 mod cache;
+mod inventory;
 
 use std::sync::Arc;
 use std::cell::RefCell;
@@ -23,11 +24,12 @@ use clap::Parser;
 
 use host_manager::HostManager;
 use monitor_manager::MonitorManager;
-use connection_manager::ConnectionManager;
+use connection_manager::{CachePurgeTarget, ConnectionManager};
 use command_handler::CommandHandler;
 use host::Host;
 use configuration::Configuration;
 use module::ModuleFactory;
+use inventory::Inventory;
 
 
 #[derive(Parser, Clone)]
@@ -40,6 +42,27 @@ struct Args {
     command_module_info: bool,
     #[clap(long)]
     connector_module_info: bool,
+    /// Import hosts from OpenSSH client configuration file (e.g. ~/.ssh/config).
+    #[clap(long)]
+    import_ssh_config: Option<String>,
+    /// Import hosts from Ansible inventory file (INI or YAML).
+    #[clap(long)]
+    import_ansible_inventory: Option<String>,
+    /// Write imported hosts to configuration. Otherwise, only a preview of the changes is shown.
+    #[clap(long)]
+    import_merge: bool,
+    /// Print cached entries, ages and hit rates per module.
+    #[clap(long)]
+    cache_stats: bool,
+    /// Remove cached responses of a host.
+    #[clap(long)]
+    cache_purge_host: Option<String>,
+    /// Remove cached responses requested by a module.
+    #[clap(long)]
+    cache_purge_module: Option<String>,
+    /// Remove cached responses whose cache key starts with the prefix.
+    #[clap(long)]
+    cache_purge_prefix: Option<String>,
 }
 
 #[derive(PartialEq)]
@@ -67,7 +90,10 @@ fn run(args: Args) -> ExitReason {
     log::info!("Lightkeeper starting...");
 
 
-    let module_factory = Arc::<ModuleFactory>::new(ModuleFactory::new());
+    let mut module_factory = ModuleFactory::new();
+    module_factory.load_scripted_modules(&args.config_dir);
+    module_factory.load_plugin_modules(&args.config_dir);
+    let module_factory = Arc::new(module_factory);
     if args.monitoring_module_info {
         print!("{}", module_factory.get_monitoring_module_info());
         return ExitReason::Quit;
@@ -89,11 +115,19 @@ fn run(args: Args) -> ExitReason {
         }
     };
 
+    if args.import_ssh_config.is_some() || args.import_ansible_inventory.is_some() {
+        return import_hosts(&args, hosts_config, group_config);
+    }
+
+    if args.cache_stats || args.cache_purge_host.is_some() || args.cache_purge_module.is_some() || args.cache_purge_prefix.is_some() {
+        return manage_cache(&args, module_factory, &main_config);
+    }
+
     let host_manager = Rc::new(RefCell::new(HostManager::new()));
     host_manager.borrow_mut().configure(&hosts_config);
 
     let mut connection_manager = ConnectionManager::new(module_factory.clone());
-    connection_manager.configure(&hosts_config, &main_config.cache_settings);
+    connection_manager.configure(&hosts_config, &main_config.cache_settings, &main_config.connection_settings);
 
     let mut monitor_manager = MonitorManager::new(main_config.cache_settings.clone(), host_manager.clone(), module_factory.clone());
     monitor_manager.configure(
@@ -127,4 +161,70 @@ fn run(args: Args) -> ExitReason {
     let exit_reason = frontend.start(command_handler, monitor_manager, connection_manager, host_manager, main_config.clone());
 
     exit_reason
-}
\ No newline at end of file
+}
+
+fn import_hosts(args: &Args, hosts_config: configuration::Hosts, group_config: configuration::Groups) -> ExitReason {
+    let inventory_result = match (&args.import_ssh_config, &args.import_ansible_inventory) {
+        (Some(path), _) => Inventory::read("ssh-config", path),
+        (_, Some(path)) => Inventory::read("ansible", path),
+        _ => return ExitReason::Quit,
+    };
+
+    let inventory = match inventory_result {
+        Ok(inventory) => inventory,
+        Err(error) => {
+            log::error!("Error while importing hosts: {}", error);
+            return ExitReason::Error;
+        }
+    };
+
+    if !args.import_merge {
+        print!("{}", inventory.preview(&hosts_config, &group_config));
+        println!("Run again with --import-merge to write the changes.");
+        return ExitReason::Quit;
+    }
+
+    let mut hosts_config = hosts_config;
+    let mut group_config = group_config;
+    let preview = inventory.merge(&mut hosts_config, &mut group_config);
+
+    if let Err(error) = Configuration::write_groups_config(&args.config_dir, &group_config) {
+        log::error!("{}", error);
+        return ExitReason::Error;
+    }
+    if let Err(error) = Configuration::write_hosts_config(&args.config_dir, &hosts_config) {
+        log::error!("{}", error);
+        return ExitReason::Error;
+    }
+
+    print!("{}", preview);
+    ExitReason::Quit
+}
+
+fn manage_cache(args: &Args, module_factory: Arc<ModuleFactory>, main_config: &Configuration) -> ExitReason {
+    // Loading a disabled cache would clear the cache file.
+    if !main_config.cache_settings.enable_cache {
+        log::error!("Cache is disabled");
+        return ExitReason::Error;
+    }
+
+    let mut connection_manager = ConnectionManager::new(module_factory);
+    connection_manager.load_cache(&main_config.cache_settings);
+
+    let purge_targets = [
+        args.cache_purge_host.clone().map(CachePurgeTarget::Host),
+        args.cache_purge_module.clone().map(CachePurgeTarget::Module),
+        args.cache_purge_prefix.clone().map(CachePurgeTarget::KeyPrefix),
+    ];
+
+    for target in purge_targets.into_iter().flatten() {
+        let count = connection_manager.purge_cache(&target);
+        println!("Removed {} entries ({:?})", count, target);
+    }
+
+    if args.cache_stats {
+        print!("{}", connection_manager.get_cache_statistics());
+    }
+
+    ExitReason::Quit
+}
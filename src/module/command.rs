@@ -3,11 +3,13 @@ pub mod command_module;
 pub use command_module::CommandModule;
 pub use command_module::Command;
 pub use command_module::CommandResult;
+pub use command_module::FileUpload;
 pub use command_module::UIAction;
 pub use command_module::BoxCloneableCommand;
 
 pub mod docker;
 pub mod linux;
 pub mod os;
+pub mod script;
 pub mod storage;
 pub mod systemd;
\ No newline at end of file
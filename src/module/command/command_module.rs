@@ -32,6 +32,12 @@ pub trait CommandModule : BoxCloneableCommand + MetadataSupport + Module {
         }
     }
 
+    /// IDs of monitors whose state can change when this command succeeds.
+    /// Their cached responses are evicted and they are refreshed after the command.
+    fn get_affected_monitors(&self) -> Vec<String> {
+        Vec::new()
+    }
+
     fn get_connector_message(&self, _host: Host, _parameters: Vec<String>) -> Result<String, String> {
         Err(String::new())
     }
@@ -40,6 +46,11 @@ pub trait CommandModule : BoxCloneableCommand + MetadataSupport + Module {
         Err(String::new())
     }
 
+    /// File that is uploaded before the connector messages are sent, e.g. a binary that they install.
+    fn get_file_upload(&self, _host: Host, _parameters: Vec<String>) -> Result<Option<FileUpload>, String> {
+        Ok(None)
+    }
+
     fn process_response(&self, _host: Host, response: &ResponseMessage) -> Result<CommandResult, String> {
         Ok(CommandResult::new_info(response.message.clone()))
     }
@@ -53,6 +64,15 @@ pub trait BoxCloneableCommand {
     fn box_clone(&self) -> Command;
 }
 
+/// Local file to upload to the host.
+#[derive(Clone, Debug)]
+pub struct FileUpload {
+    pub local_path: String,
+    /// Relative paths are relative to the user's home directory.
+    pub remote_path: String,
+    pub permissions: u32,
+}
+
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct CommandResult {
@@ -39,6 +39,13 @@ impl CommandModule for Build {
         }
     }
 
+    fn get_affected_monitors(&self) -> Vec<String> {
+        vec![
+            String::from("docker-compose"),
+            String::from("docker-images"),
+        ]
+    }
+
     fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
         let compose_file = parameters.first().unwrap();
         let service_name = parameters.get(2).unwrap();
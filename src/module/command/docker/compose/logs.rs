@@ -10,6 +10,7 @@ use lightkeeper_module::command_module;
     name="docker-compose-logs",
     version="0.0.1",
     description="Show docker-compose logs for services.",
+    cache_sensitivity="Sensitive",
 )]
 pub struct Logs {
 }
@@ -38,6 +38,14 @@ impl CommandModule for Pull {
         }
     }
 
+    fn get_affected_monitors(&self) -> Vec<String> {
+        vec![
+            String::from("docker-compose"),
+            String::from("docker-images"),
+            String::from("docker-image-updates"),
+        ]
+    }
+
     fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
         let compose_file = parameters.first().unwrap();
 
@@ -41,6 +41,13 @@ impl CommandModule for Stop {
         }
     }
 
+    fn get_affected_monitors(&self) -> Vec<String> {
+        vec![
+            String::from("docker-compose"),
+            String::from("docker-containers"),
+        ]
+    }
+
     fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
         let compose_file = parameters.first().unwrap();
 
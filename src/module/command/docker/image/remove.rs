@@ -43,6 +43,13 @@ impl CommandModule for Remove {
         }
     }
 
+    fn get_affected_monitors(&self) -> Vec<String> {
+        vec![
+            String::from("docker-images"),
+            String::from("docker-image-updates"),
+        ]
+    }
+
     fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
         let target_id = parameters.first().unwrap();
 
@@ -12,6 +12,7 @@ use lightkeeper_module::command_module;
     name="docker-inspect",
     version="0.0.1",
     description="Inspects a Docker container.",
+    cache_sensitivity="Sensitive",
 )]
 pub struct Inspect;
 
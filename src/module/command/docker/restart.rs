@@ -38,6 +38,10 @@ impl CommandModule for Restart {
         }
     }
 
+    fn get_affected_monitors(&self) -> Vec<String> {
+        vec![String::from("docker-containers")]
+    }
+
     fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
         let mut command = ShellCommand::new();
         command.use_sudo = host.settings.contains(&crate::host::HostSetting::UseSudo);
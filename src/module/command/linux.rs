@@ -1,3 +1,6 @@
+pub mod agent_deploy;
+pub use agent_deploy::AgentDeploy;
+
 pub mod logs;
 pub use logs::Logs;
 
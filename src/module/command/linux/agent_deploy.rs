@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+use crate::frontend;
+use crate::host::*;
+use crate::module::connection::ResponseMessage;
+use crate::module::monitoring::agent;
+use crate::module::*;
+use crate::module::command::*;
+use lightkeeper_module::command_module;
+
+/// Uploaded to the home directory first, since the install directory may not exist yet.
+const UPLOAD_PATH: &str = ".lightkeeper-agent.upload";
+/// Machine type field of the ELF header.
+const ELF_MACHINE_X86_64: u16 = 0x3E;
+
+#[command_module(
+    name="linux-agent-deploy",
+    version="0.0.1",
+    description="Installs lightkeeper-agent to ~/.local/bin of the SSH user.",
+    settings={
+      local_path => "Path of the agent binary built for the host's architecture. \
+                     Default: lightkeeper-agent in the same directory as the lightkeeper executable."
+    }
+)]
+pub struct AgentDeploy {
+    local_path: String,
+}
+
+impl Module for AgentDeploy {
+    fn new(settings: &HashMap<String, String>) -> Self {
+        let default_path = std::env::current_exe().ok()
+                                                  .and_then(|path| path.parent().map(|directory| directory.join(lightkeeper_agent::AGENT_BINARY_NAME)))
+                                                  .unwrap_or_else(|| PathBuf::from(lightkeeper_agent::AGENT_BINARY_NAME));
+
+        AgentDeploy {
+            local_path: settings.get("local_path").cloned().unwrap_or_else(|| default_path.to_string_lossy().to_string()),
+        }
+    }
+}
+
+impl CommandModule for AgentDeploy {
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new("ssh", "0.0.1"))
+    }
+
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            category: String::from("host"),
+            display_style: frontend::DisplayStyle::Icon,
+            display_icon: String::from("update"),
+            display_text: String::from("Install agent"),
+            confirmation_text: format!("Install {} to ~/{}?", self.local_path, agent::INSTALL_PATH),
+            ..Default::default()
+        }
+    }
+
+    fn get_file_upload(&self, host: Host, _parameters: Vec<String>) -> Result<Option<FileUpload>, String> {
+        let binary_architecture = read_binary_architecture(&self.local_path)?;
+        if binary_architecture == platform_info::Architecture::Unknown {
+            return Err(format!("Unsupported architecture in {}", self.local_path));
+        }
+        else if binary_architecture != host.platform.architecture {
+            return Err(format!("Agent is built for {} but the host architecture is {}", binary_architecture, host.platform.architecture));
+        }
+
+        Ok(Some(FileUpload {
+            local_path: self.local_path.clone(),
+            remote_path: String::from(UPLOAD_PATH),
+            permissions: 0o755,
+        }))
+    }
+
+    fn get_connector_message(&self, host: Host, _parameters: Vec<String>) -> Result<String, String> {
+        if host.platform.os != platform_info::OperatingSystem::Linux {
+            return Err(String::from("Unsupported platform"));
+        }
+
+        // Uploaded agent is run first to make sure it works on the host and is compatible, so a working agent isn't replaced.
+        // Replacing instead of overwriting works even if the old version is running.
+        Ok(format!(
+            "version=$(\"$HOME/{upload_path}\" {flag}) && echo \"$version\" && [ \"$version\" = \"{report_version}\" ] && \
+             mkdir -p \"$HOME/{install_dir}\" && mv -f \"$HOME/{upload_path}\" \"$HOME/{install_path}\"",
+            upload_path = UPLOAD_PATH,
+            flag = lightkeeper_agent::REPORT_VERSION_FLAG,
+            report_version = lightkeeper_agent::REPORT_VERSION,
+            install_dir = agent::INSTALL_PATH.rsplit_once('/').map(|(directory, _)| directory).unwrap_or("."),
+            install_path = agent::INSTALL_PATH,
+        ))
+    }
+
+    fn process_response(&self, _host: Host, response: &ResponseMessage) -> Result<CommandResult, String> {
+        match response.message.trim().parse::<u32>() {
+            Ok(lightkeeper_agent::REPORT_VERSION) if !response.is_error() => {
+                Ok(CommandResult::new_info("Agent installed. Refresh the host to start using it."))
+            },
+            Ok(report_version) => {
+                Err(format!("Agent was not installed, since its report version {} is not supported", report_version))
+            },
+            Err(_) => Err(response.message.clone()),
+        }
+    }
+}
+
+/// Reads the architecture from the ELF header of the binary.
+fn read_binary_architecture(path: &str) -> Result<platform_info::Architecture, String> {
+    let mut header = [0u8; 20];
+    File::open(path).and_then(|mut file| file.read_exact(&mut header))
+                    .map_err(|error| format!("Couldn't read {}: {}", path, error))?;
+
+    if &header[0..4] != b"\x7fELF" {
+        return Err(format!("{} is not a Linux executable", path));
+    }
+
+    // Byte 5 is the data encoding: 1 for little-endian, 2 for big-endian.
+    let machine = match header[5] {
+        2 => u16::from_be_bytes([header[18], header[19]]),
+        _ => u16::from_le_bytes([header[18], header[19]]),
+    };
+
+    match machine {
+        ELF_MACHINE_X86_64 => Ok(platform_info::Architecture::X86_64),
+        _ => Ok(platform_info::Architecture::Unknown),
+    }
+}
@@ -13,6 +13,7 @@ use lightkeeper_module::command_module;
     name="logs",
     version="0.0.1",
     description="Shows logs from journalctl.",
+    cache_sensitivity="Sensitive",
 )]
 pub struct Logs;
 
@@ -40,6 +40,10 @@ impl CommandModule for Uninstall {
         }
     }
 
+    fn get_affected_monitors(&self) -> Vec<String> {
+        vec![String::from("package")]
+    }
+
     fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
         let package = parameters.first().unwrap();
 
@@ -36,6 +36,10 @@ impl CommandModule for Update {
         }
     }
 
+    fn get_affected_monitors(&self) -> Vec<String> {
+        vec![String::from("package")]
+    }
+
     fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
         let package = parameters.first().unwrap();
 
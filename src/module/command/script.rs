@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::cache::CacheSensitivity;
+use crate::enums::Criticality;
+use crate::frontend;
+use crate::host::*;
+use crate::module::connection::ResponseMessage;
+use crate::module::*;
+use crate::module::command::*;
+use crate::utils::ShellCommand;
+
+const MODULE_NAME: &str = "script";
+const MODULE_VERSION: &str = "0.0.1";
+
+
+/// Generic command for user-defined commands. Configured with `module: script` so every configured command is a separate instance.
+/// MetadataSupport is implemented manually instead of with the command_module-macro,
+/// since the module specification has to contain the instance ID instead of the module name.
+#[derive(Clone)]
+pub struct Script {
+    instance_id: String,
+    command: String,
+    connector: String,
+    use_sudo: bool,
+    cache_sensitivity: CacheSensitivity,
+    affected_monitors: Vec<String>,
+    display_options: frontend::DisplayOptions,
+}
+
+impl Module for Script {
+    fn new(settings: &HashMap<String, String>) -> Self {
+        let get_setting = |key: &str, default: &str| settings.get(key).cloned().unwrap_or_else(|| default.to_string());
+        let get_list = |key: &str| settings.get(key).map(|value| parse_list(value)).unwrap_or_default();
+        let instance_id = get_setting(INSTANCE_ID_SETTING, "");
+        let display_icon = get_setting("display_icon", "");
+
+        let display_options = frontend::DisplayOptions {
+            display_style: if display_icon.is_empty() { frontend::DisplayStyle::Text } else { frontend::DisplayStyle::Icon },
+            display_text: get_setting("display_text", if instance_id.is_empty() { "Script" } else { &instance_id }),
+            display_icon: display_icon,
+            tab_title: get_setting("tab_title", ""),
+            category: get_setting("category", "custom"),
+            confirmation_text: get_setting("confirmation_text", ""),
+            user_parameters: parse_user_parameters(settings),
+            parent_id: get_setting("parent_id", ""),
+            depends_on_criticality: get_list("depends_on_criticality").iter()
+                                                                      .filter_map(|criticality| Criticality::from_str(criticality).ok())
+                                                                      .collect(),
+            depends_on_value: get_list("depends_on_value"),
+            depends_on_tags: get_list("depends_on_tags"),
+            depends_on_no_tags: get_list("depends_on_no_tags"),
+            multivalue_level: get_setting("multivalue_level", "0").parse().unwrap_or(0),
+            action: match get_setting("action", "none").as_str() {
+                "details_dialog" => UIAction::DetailsDialog,
+                "text_view" => UIAction::TextView,
+                "text_dialog" => UIAction::TextDialog,
+                "log_view" => UIAction::LogView,
+                "terminal" => UIAction::Terminal,
+                _ => UIAction::None,
+            },
+            ..Default::default()
+        };
+
+        Script {
+            command: get_setting("command", ""),
+            connector: get_setting("connector", "ssh"),
+            use_sudo: get_setting("use_sudo", "false") == "true",
+            cache_sensitivity: CacheSensitivity::from_settings(CacheSensitivity::Normal, settings),
+            affected_monitors: get_list("affected_monitors"),
+            display_options: display_options,
+            instance_id: instance_id,
+        }
+    }
+}
+
+impl MetadataSupport for Script {
+    fn get_metadata() -> Metadata {
+        Metadata {
+            module_spec: ModuleSpecification::new_with_type(MODULE_NAME, MODULE_VERSION, "command"),
+            description: String::from("Runs a user-defined command. Set `module: script` in command configuration to use."),
+            settings: HashMap::from([
+                (String::from("command"), String::from("Command to run. Executed with a shell. Parameters are inserted with {1}, {2}, etc. and are quoted automatically. \
+                                                        For commands attached to a monitor, the first parameters come from the monitor value, followed by user inputs. \
+                                                        Not interpolated, so ${VAR} is expanded by the shell on the host.")),
+                (String::from("connector"), String::from("Connector to use: ssh or local-command. Default: ssh")),
+                (String::from("use_sudo"), String::from("Run command with sudo if host has use_sudo enabled. Default: false")),
+                (String::from(CacheSensitivity::SETTING), format!("{} Default: false", CacheSensitivity::SETTING_DESCRIPTION)),
+                (String::from("display_text"), String::from("Button label or tooltip. Default: command ID")),
+                (String::from("display_icon"), String::from("Icon name. If set, command is displayed as an icon button.")),
+                (String::from("tab_title"), String::from("Title for the tab if action opens one.")),
+                (String::from("category"), String::from("Category to display the command in. Default: custom")),
+                (String::from("parent_id"), String::from("Monitor ID to attach the command to. If empty, command is displayed on category-level.")),
+                (String::from("depends_on_criticality"), String::from("Comma-separated list of monitor criticality levels for which the command is shown, e.g. \"Warning, Critical\"")),
+                (String::from("depends_on_value"), String::from("Comma-separated list of monitor values for which the command is shown.")),
+                (String::from("depends_on_tags"), String::from("Comma-separated list of tags the monitor value needs to have.")),
+                (String::from("depends_on_no_tags"), String::from("Comma-separated list of tags the monitor value can't have.")),
+                (String::from("multivalue_level"), String::from("Attach to values of this multivalue level. Default: 0 (any level)")),
+                (String::from("affected_monitors"), String::from("Comma-separated list of monitor IDs to refresh without cache after the command succeeds.")),
+                (String::from("confirmation_text"), String::from("Ask for confirmation with this text before running.")),
+                (String::from("action"), String::from("What to do with the output: none, details_dialog, text_view, text_dialog, log_view or terminal. Default: none")),
+                (String::from("input1_label"), String::from("Asks for user input before running. Use input2_label etc. for more inputs.")),
+                (String::from("input1_type"), String::from("text, integer, decimal or option. Default: text")),
+                (String::from("input1_default"), String::from("Default value for the input.")),
+                (String::from("input1_options"), String::from("Comma-separated list of options for option type.")),
+                (String::from("input1_validator"), String::from("Regular expression the input has to match.")),
+            ]),
+            parent_module: None,
+            is_stateless: true,
+            cache_scope: crate::cache::CacheScope::Host,
+            cache_sensitivity: crate::cache::CacheSensitivity::Normal,
+        }
+    }
+
+    fn get_metadata_self(&self) -> Metadata {
+        Metadata {
+            module_spec: self.get_module_spec(),
+            cache_sensitivity: self.cache_sensitivity,
+            ..Self::get_metadata()
+        }
+    }
+
+    fn get_module_spec(&self) -> ModuleSpecification {
+        let id = if self.instance_id.is_empty() { MODULE_NAME } else { &self.instance_id };
+        ModuleSpecification::new_with_type(id, MODULE_VERSION, "command")
+    }
+}
+
+impl BoxCloneableCommand for Script {
+    fn box_clone(&self) -> Command {
+        Box::new(self.clone())
+    }
+}
+
+impl CommandModule for Script {
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new(self.connector.as_str(), "0.0.1"))
+    }
+
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        self.display_options.clone()
+    }
+
+    fn get_affected_monitors(&self) -> Vec<String> {
+        self.affected_monitors.clone()
+    }
+
+    fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
+        if self.command.is_empty() {
+            return Err(String::from("Command is not set"));
+        }
+
+        let command = render_template(&self.command, &parameters)?;
+
+        if self.use_sudo && host.settings.contains(&HostSetting::UseSudo) {
+            let mut sudo_command = ShellCommand::new_from(vec!["sh", "-c", command.as_str()]);
+            sudo_command.use_sudo = true;
+            Ok(sudo_command.to_string())
+        }
+        else {
+            Ok(command)
+        }
+    }
+
+    fn process_response(&self, _host: Host, response: &ResponseMessage) -> Result<CommandResult, String> {
+        if response.is_error() {
+            if response.message.is_empty() {
+                Ok(CommandResult::new_error(format!("Exit code {}", response.return_code)))
+            }
+            else {
+                Ok(CommandResult::new_error(response.message.clone()))
+            }
+        }
+        else {
+            Ok(CommandResult::new_info(response.message.clone()))
+        }
+    }
+}
+
+
+/// Replaces placeholders {1}, {2}, etc. with quoted parameters. Other braces are left as is.
+fn render_template(template: &str, parameters: &[String]) -> Result<String, String> {
+    let mut result = String::new();
+    let mut remaining = template;
+
+    while let Some(start) = remaining.find('{') {
+        result.push_str(&remaining[..start]);
+        remaining = &remaining[start..];
+
+        let placeholder_index = remaining.find('}')
+                                         .and_then(|end| remaining[1..end].parse::<usize>().ok().map(|index| (index, end)));
+
+        match placeholder_index {
+            Some((index, end)) => {
+                let parameter = index.checked_sub(1).and_then(|index| parameters.get(index))
+                                     .ok_or(format!("Parameter {{{}}} is not available", index))?;
+                result.push_str(&ShellCommand::quote(parameter));
+                remaining = &remaining[end + 1..];
+            },
+            None => {
+                result.push('{');
+                remaining = &remaining[1..];
+            }
+        }
+    }
+
+    result.push_str(remaining);
+    Ok(result)
+}
+
+fn parse_list(value: &str) -> Vec<String> {
+    value.split(',').map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect()
+}
+
+/// Reads input fields from settings input1_label, input2_label, etc. until a label is missing.
+fn parse_user_parameters(settings: &HashMap<String, String>) -> Vec<frontend::UserInputField> {
+    let mut fields = Vec::new();
+
+    for number in 1.. {
+        let get_setting = |key: &str| settings.get(&format!("input{}_{}", number, key)).cloned().unwrap_or_default();
+        let label = match settings.get(&format!("input{}_label", number)) {
+            Some(label) => label.clone(),
+            None => break,
+        };
+
+        let options = parse_list(&get_setting("options"));
+
+        fields.push(frontend::UserInputField {
+            field_type: match get_setting("type").as_str() {
+                "integer" => frontend::UserInputFieldType::Integer,
+                "decimal" => frontend::UserInputFieldType::DecimalNumber,
+                "option" => frontend::UserInputFieldType::Option,
+                _ => frontend::UserInputFieldType::Text,
+            },
+            label: label,
+            default_value: get_setting("default"),
+            validator_regexp: get_setting("validator"),
+            option_descriptions: vec![String::new(); options.len()],
+            options: options,
+            ..Default::default()
+        });
+    }
+
+    fields
+}
@@ -1,4 +1,8 @@
 pub mod lvm;
+pub mod smart;
+pub mod mdraid;
+pub mod zfs;
+pub mod btrfs;
 
 pub mod file_space_usage;
 pub use file_space_usage::FileSpaceUsage;
\ No newline at end of file
@@ -0,0 +1,8 @@
+pub mod scrub_start;
+pub use scrub_start::ScrubStart;
+
+pub mod scrub_cancel;
+pub use scrub_cancel::ScrubCancel;
+
+pub mod balance;
+pub use balance::Balance;
\ No newline at end of file
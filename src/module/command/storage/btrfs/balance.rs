@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use crate::frontend;
+use crate::host::*;
+use crate::module::connection::ResponseMessage;
+use crate::module::*;
+use crate::module::command::*;
+use crate::utils::ShellCommand;
+use lightkeeper_module::command_module;
+
+
+#[command_module(
+    name="storage-btrfs-balance",
+    version="0.0.1",
+    description="Starts a filtered balance on a btrfs filesystem in the background. Only chunks that are at most the given percentage full are rewritten, which frees unallocated space.",
+)]
+pub struct Balance {
+}
+
+impl Module for Balance {
+    fn new(_settings: &HashMap<String, String>) -> Self {
+        Balance {
+        }
+    }
+}
+
+impl CommandModule for Balance {
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new("ssh", "0.0.1"))
+    }
+
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            category: String::from("storage"),
+            parent_id: String::from("storage-btrfs"),
+            display_style: frontend::DisplayStyle::Icon,
+            display_icon: String::from("resize-column-2"),
+            display_text: String::from("Balance"),
+            confirmation_text: String::from("Balancing rewrites data and can slow down the filesystem for a long time. Start balance?"),
+            depends_on_no_tags: vec![String::from("Balancing")],
+            user_parameters: vec![
+                frontend::UserInputField::number("Data usage filter (%)", "50"),
+                frontend::UserInputField::number("Metadata usage filter (%)", "50"),
+            ],
+            multivalue_level: 1,
+            ..Default::default()
+        }
+    }
+
+    fn get_affected_monitors(&self) -> Vec<String> {
+        vec![
+            String::from("storage-btrfs"),
+        ]
+    }
+
+    fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
+        let mountpoint = parameters.get(0).unwrap();
+        let data_usage = parameters.get(1).unwrap();
+        let metadata_usage = parameters.get(2).unwrap();
+
+        for usage in [data_usage, metadata_usage] {
+            if usage.parse::<u8>().map(|usage| usage > 100).unwrap_or(true) {
+                return Err(format!("Invalid usage filter: {}", usage));
+            }
+        }
+
+        let mut command = ShellCommand::new();
+        command.use_sudo = host.settings.contains(&HostSetting::UseSudo);
+
+        if host.platform.os == platform_info::OperatingSystem::Linux {
+            command.arguments(vec![
+                "btrfs", "balance", "start", "--bg",
+                &format!("-dusage={}", data_usage),
+                &format!("-musage={}", metadata_usage),
+                mountpoint
+            ]);
+            Ok(command.to_string())
+        }
+        else {
+            Err(String::from("Unsupported platform"))
+        }
+    }
+
+    fn process_response(&self, _host: Host, response: &ResponseMessage) -> Result<CommandResult, String> {
+        if response.return_code == 0 {
+            Ok(CommandResult::new_info(String::new()))
+        }
+        else {
+            Ok(CommandResult::new_error(response.message.clone()))
+        }
+    }
+}
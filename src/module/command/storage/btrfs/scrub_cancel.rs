@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use crate::frontend;
+use crate::host::*;
+use crate::module::connection::ResponseMessage;
+use crate::module::*;
+use crate::module::command::*;
+use crate::utils::ShellCommand;
+use lightkeeper_module::command_module;
+
+
+#[command_module(
+    name="storage-btrfs-scrub-cancel",
+    version="0.0.1",
+    description="Cancels a running scrub on a btrfs filesystem.",
+)]
+pub struct ScrubCancel {
+}
+
+impl Module for ScrubCancel {
+    fn new(_settings: &HashMap<String, String>) -> Self {
+        ScrubCancel {
+        }
+    }
+}
+
+impl CommandModule for ScrubCancel {
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new("ssh", "0.0.1"))
+    }
+
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            category: String::from("storage"),
+            parent_id: String::from("storage-btrfs"),
+            display_style: frontend::DisplayStyle::Icon,
+            display_icon: String::from("stop"),
+            display_text: String::from("Cancel scrub"),
+            depends_on_tags: vec![String::from("Scrubbing")],
+            multivalue_level: 1,
+            ..Default::default()
+        }
+    }
+
+    fn get_affected_monitors(&self) -> Vec<String> {
+        vec![
+            String::from("storage-btrfs"),
+        ]
+    }
+
+    fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
+        let mountpoint = parameters.get(0).unwrap();
+
+        let mut command = ShellCommand::new();
+        command.use_sudo = host.settings.contains(&HostSetting::UseSudo);
+
+        if host.platform.os == platform_info::OperatingSystem::Linux {
+            command.arguments(vec!["btrfs", "scrub", "cancel", mountpoint]);
+            Ok(command.to_string())
+        }
+        else {
+            Err(String::from("Unsupported platform"))
+        }
+    }
+
+    fn process_response(&self, _host: Host, response: &ResponseMessage) -> Result<CommandResult, String> {
+        if response.return_code == 0 {
+            Ok(CommandResult::new_info(String::new()))
+        }
+        else {
+            Ok(CommandResult::new_error(response.message.clone()))
+        }
+    }
+}
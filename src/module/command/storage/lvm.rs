@@ -1,3 +1,6 @@
+use crate::Host;
+use crate::module::platform_info;
+
 pub mod snapshot;
 pub use snapshot::Snapshot;
 
@@ -5,4 +8,25 @@ pub mod lvremove;
 pub use lvremove::LVRemove;
 
 pub mod lvresize;
-pub use lvresize::LVResize;
\ No newline at end of file
+pub use lvresize::LVResize;
+
+pub mod lvextend;
+pub use lvextend::LVExtend;
+
+pub mod snapshot_merge;
+pub use snapshot_merge::SnapshotMerge;
+
+pub mod vgextend;
+pub use vgextend::VGExtend;
+
+pub mod pvcreate;
+pub use pvcreate::PVCreate;
+
+
+/// LVM commands are used the same way on all supported platforms.
+pub fn is_supported(host: &Host) -> bool {
+    host.platform.version_is_same_or_greater_than(platform_info::Flavor::Debian, "9") ||
+    host.platform.version_is_same_or_greater_than(platform_info::Flavor::Ubuntu, "20") ||
+    host.platform.version_is_same_or_greater_than(platform_info::Flavor::CentOS, "7") ||
+    host.platform.version_is_same_or_greater_than(platform_info::Flavor::RedHat, "7")
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use crate::frontend;
+use crate::host::*;
+use crate::module::connection::ResponseMessage;
+use crate::module::*;
+use crate::module::command::*;
+use crate::module::command::storage::lvm;
+use crate::utils::ShellCommand;
+use crate::utils::string_validation;
+use lightkeeper_module::command_module;
+
+
+#[command_module(
+    name="storage-lvm-lvextend",
+    version="0.0.1",
+    description="Extends an LVM logical volume and grows the filesystem on it. Growing is supported for ext2/3/4 and XFS.",
+)]
+pub struct LVExtend {
+}
+
+impl Module for LVExtend {
+    fn new(_settings: &HashMap<String, String>) -> Self {
+        LVExtend {
+        }
+    }
+}
+
+impl CommandModule for LVExtend {
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new("ssh", "0.0.1"))
+    }
+
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            category: String::from("storage"),
+            parent_id: String::from("storage-lvm-logical-volume"),
+            display_style: frontend::DisplayStyle::Icon,
+            display_icon: String::from("resize-column"),
+            display_text: String::from("Extend with filesystem"),
+            // Snapshots and thin pools don't contain a filesystem.
+            depends_on_no_tags: vec![String::from("Snapshot"), String::from("Thin pool")],
+            user_parameters: vec![
+                frontend::UserInputField::decimal_number_with_units("Size to add", "5G", vec![
+                    String::from("b"), String::from("B"),
+                    String::from("s"), String::from("S"),
+                    String::from("k"), String::from("K"),
+                    String::from("m"), String::from("M"),
+                    String::from("g"), String::from("G"),
+                    String::from("t"), String::from("T"),
+                    String::from("p"), String::from("P"),
+                    String::from("e"), String::from("E")
+                ]),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn get_affected_monitors(&self) -> Vec<String> {
+        vec![
+            String::from("storage-lvm-logical-volume"),
+            String::from("storage-lvm-volume-group"),
+            String::from("filesystem"),
+        ]
+    }
+
+    fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
+        let lv_path = parameters.get(0).unwrap();
+        let _vg_name = parameters.get(1).unwrap();
+        let _lv_name = parameters.get(2).unwrap();
+        let _lv_size = parameters.get(3).unwrap();
+        let added_size = crate::utils::remove_whitespace(parameters.get(4).unwrap());
+
+        let mut command = ShellCommand::new();
+        command.use_sudo = host.settings.contains(&crate::host::HostSetting::UseSudo);
+
+        if !string_validation::is_numeric_with_unit(&added_size, &self.get_display_options().user_parameters[0].units) {
+            Err(format!("Invalid size: {}", added_size))
+        }
+        else if lvm::is_supported(&host) {
+            // Filesystem is resized with fsadm, which uses resize2fs or xfs_growfs.
+            let size_argument = format!("+{}", added_size);
+            command.arguments(vec!["lvextend", "--resizefs", "--size", &size_argument, lv_path]);
+            Ok(command.to_string())
+        }
+        else {
+            Err(String::from("Unsupported platform"))
+        }
+    }
+
+    fn process_response(&self, _host: Host, response: &ResponseMessage) -> Result<CommandResult, String> {
+        if response.return_code == 0 && response.message.contains("successfully resized") {
+            Ok(CommandResult::new_info(String::new()))
+        }
+        else {
+            Ok(CommandResult::new_error(response.message.clone()))
+        }
+    }
+}
@@ -4,6 +4,7 @@ use crate::host::*;
 use crate::module::connection::ResponseMessage;
 use crate::module::*;
 use crate::module::command::*;
+use crate::module::command::storage::lvm;
 use crate::utils::ShellCommand;
 use lightkeeper_module::command_module;
 
@@ -40,6 +41,13 @@ impl CommandModule for LVRemove {
         }
     }
 
+    fn get_affected_monitors(&self) -> Vec<String> {
+        vec![
+            String::from("storage-lvm-logical-volume"),
+            String::from("storage-lvm-volume-group"),
+        ]
+    }
+
     fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
         let lv_path = parameters.get(0).unwrap();
         let _vg_name = parameters.get(1).unwrap();
@@ -49,7 +57,7 @@ impl CommandModule for LVRemove {
         let mut command = ShellCommand::new();
         command.use_sudo = host.settings.contains(&crate::host::HostSetting::UseSudo);
 
-        if host.platform.version_is_same_or_greater_than(platform_info::Flavor::Debian, "9") {
+        if lvm::is_supported(&host) {
             command.arguments(vec!["lvremove", "-y", lv_path]);
             Ok(command.to_string())
         }
@@ -4,6 +4,7 @@ use crate::host::*;
 use crate::module::connection::ResponseMessage;
 use crate::module::*;
 use crate::module::command::*;
+use crate::module::command::storage::lvm;
 use crate::utils::ShellCommand;
 use crate::utils::string_validation;
 use lightkeeper_module::command_module;
@@ -53,6 +54,14 @@ impl CommandModule for LVResize {
         }
     }
 
+    fn get_affected_monitors(&self) -> Vec<String> {
+        vec![
+            String::from("storage-lvm-logical-volume"),
+            String::from("storage-lvm-volume-group"),
+            String::from("filesystem"),
+        ]
+    }
+
     fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
         let lv_path = parameters.get(0).unwrap();
         let _vg_name = parameters.get(1).unwrap();
@@ -66,7 +75,7 @@ impl CommandModule for LVResize {
         if !string_validation::is_numeric_with_unit(&new_size, &self.get_display_options().user_parameters[0].units) {
             Err(format!("Invalid size: {}", new_size))
         }
-        else if host.platform.version_is_same_or_greater_than(platform_info::Flavor::Debian, "9") {
+        else if lvm::is_supported(&host) {
             command.arguments(vec!["lvresize", "--size", &new_size, lv_path]);
             Ok(command.to_string())
         }
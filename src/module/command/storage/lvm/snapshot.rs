@@ -6,6 +6,7 @@ use crate::host::*;
 use crate::module::connection::ResponseMessage;
 use crate::module::*;
 use crate::module::command::*;
+use crate::module::command::storage::lvm;
 use crate::utils::ShellCommand;
 use crate::utils::string_validation;
 use lightkeeper_module::command_module;
@@ -43,7 +44,7 @@ impl CommandModule for Snapshot {
             display_style: frontend::DisplayStyle::Icon,
             display_icon: String::from("copy"),
             display_text: String::from("Create a snapshot"),
-            depends_on_no_tags: vec![String::from("Snapshot")],
+            depends_on_no_tags: vec![String::from("Snapshot"), String::from("Thin pool")],
             user_parameters: vec![
                 frontend::UserInputField::decimal_number_with_units("Snapshot size", "3G", vec![
                     String::from("r"), String::from("R"),
@@ -61,6 +62,13 @@ impl CommandModule for Snapshot {
         }
     }
 
+    fn get_affected_monitors(&self) -> Vec<String> {
+        vec![
+            String::from("storage-lvm-logical-volume"),
+            String::from("storage-lvm-volume-group"),
+        ]
+    }
+
     fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
         let lv_path = parameters.get(0).unwrap();
         let _vg_name = parameters.get(1).unwrap();
@@ -78,7 +86,7 @@ impl CommandModule for Snapshot {
         if !string_validation::is_numeric_with_unit(&new_size, &self.get_display_options().user_parameters[0].units) {
             Err(format!("Invalid size: {}", new_size))
         }
-        else if host.platform.version_is_same_or_greater_than(platform_info::Flavor::Debian, "9") {
+        else if lvm::is_supported(&host) {
             command.arguments(vec!["lvcreate", "--snapshot", "--name", &snapshot_name, "--size", &new_size, lv_path]);
             Ok(command.to_string())
         }
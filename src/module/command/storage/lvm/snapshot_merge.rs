@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use crate::frontend;
+use crate::host::*;
+use crate::module::connection::ResponseMessage;
+use crate::module::*;
+use crate::module::command::*;
+use crate::module::command::storage::lvm;
+use crate::utils::ShellCommand;
+use lightkeeper_module::command_module;
+
+
+#[command_module(
+    name="storage-lvm-snapshot-merge",
+    version="0.0.1",
+    description="Merges an LVM snapshot back into its origin, rolling the origin back to the state of the snapshot. The snapshot is removed afterwards.",
+)]
+pub struct SnapshotMerge {
+}
+
+impl Module for SnapshotMerge {
+    fn new(_settings: &HashMap<String, String>) -> Self {
+        SnapshotMerge {
+        }
+    }
+}
+
+impl CommandModule for SnapshotMerge {
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new("ssh", "0.0.1"))
+    }
+
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            category: String::from("storage"),
+            parent_id: String::from("storage-lvm-logical-volume"),
+            display_style: frontend::DisplayStyle::Icon,
+            display_icon: String::from("update-low"),
+            display_text: String::from("Merge into origin (rollback)"),
+            confirmation_text: String::from("Are you sure you want to roll back the origin volume to this snapshot? Changes made after the snapshot will be lost."),
+            depends_on_tags: vec![String::from("Snapshot")],
+            depends_on_no_tags: vec![String::from("Merging")],
+            ..Default::default()
+        }
+    }
+
+    fn get_affected_monitors(&self) -> Vec<String> {
+        vec![
+            String::from("storage-lvm-logical-volume"),
+            String::from("storage-lvm-volume-group"),
+        ]
+    }
+
+    fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
+        let lv_path = parameters.get(0).unwrap();
+
+        let mut command = ShellCommand::new();
+        command.use_sudo = host.settings.contains(&crate::host::HostSetting::UseSudo);
+
+        if lvm::is_supported(&host) {
+            // Merging can take a long time, so progress is not polled.
+            command.arguments(vec!["lvconvert", "--merge", "--background", lv_path]);
+            Ok(command.to_string())
+        }
+        else {
+            Err(String::from("Unsupported platform"))
+        }
+    }
+
+    fn process_response(&self, _host: Host, response: &ResponseMessage) -> Result<CommandResult, String> {
+        if response.return_code != 0 {
+            Ok(CommandResult::new_error(response.message.clone()))
+        }
+        // If origin is in use (e.g. mounted), merge starts on its next activation.
+        else if response.message.contains("next activation") {
+            Ok(CommandResult::new_warning(response.message.clone()))
+        }
+        else {
+            Ok(CommandResult::new_info(String::new()))
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use crate::frontend;
+use crate::host::*;
+use crate::module::connection::ResponseMessage;
+use crate::module::*;
+use crate::module::command::*;
+use crate::module::command::storage::lvm;
+use crate::utils::ShellCommand;
+use lightkeeper_module::command_module;
+
+
+#[command_module(
+    name="storage-lvm-vgextend",
+    version="0.0.1",
+    description="Adds a physical volume to an LVM volume group. Device is initialized as a physical volume if it isn't one already.",
+)]
+pub struct VGExtend {
+}
+
+impl Module for VGExtend {
+    fn new(_settings: &HashMap<String, String>) -> Self {
+        VGExtend {
+        }
+    }
+}
+
+impl CommandModule for VGExtend {
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new("ssh", "0.0.1"))
+    }
+
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            category: String::from("storage"),
+            parent_id: String::from("storage-lvm-volume-group"),
+            display_style: frontend::DisplayStyle::Icon,
+            display_icon: String::from("add"),
+            display_text: String::from("Add a physical volume"),
+            user_parameters: vec![
+                frontend::UserInputField {
+                    label: String::from("Device"),
+                    default_value: String::from("/dev/"),
+                    validator_regexp: String::from("^/dev/\\S*$"),
+                    additional_validator_regexp: String::from("^/dev/\\S+$"),
+                    ..Default::default()
+                },
+            ],
+            confirmation_text: String::from("Are you sure you want to add {2} to volume group {1}? Existing data on {2} may be overwritten."),
+            ..Default::default()
+        }
+    }
+
+    fn get_affected_monitors(&self) -> Vec<String> {
+        vec![
+            String::from("storage-lvm-volume-group"),
+            String::from("storage-lvm-physical-volume"),
+        ]
+    }
+
+    fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
+        let vg_name = parameters.get(0).unwrap();
+        let device = parameters.get(1).unwrap().trim();
+
+        if !device.starts_with("/dev/") || device.len() <= "/dev/".len() {
+            return Err(format!("Invalid device: {}", device));
+        }
+
+        let mut command = ShellCommand::new();
+        command.use_sudo = host.settings.contains(&crate::host::HostSetting::UseSudo);
+
+        if lvm::is_supported(&host) {
+            // Prompts, e.g. about wiping existing signatures, get EOF and are declined.
+            command.arguments(vec!["vgextend", vg_name, device]);
+            Ok(format!("{} < /dev/null", command.to_string()))
+        }
+        else {
+            Err(String::from("Unsupported platform"))
+        }
+    }
+
+    fn process_response(&self, _host: Host, response: &ResponseMessage) -> Result<CommandResult, String> {
+        if response.return_code == 0 && response.message.contains("successfully extended") {
+            Ok(CommandResult::new_info(String::new()))
+        }
+        else {
+            Ok(CommandResult::new_error(response.message.clone()))
+        }
+    }
+}
@@ -0,0 +1,8 @@
+pub mod fail;
+pub use fail::Fail;
+
+pub mod remove;
+pub use remove::Remove;
+
+pub mod add;
+pub use add::Add;
\ No newline at end of file
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use crate::frontend;
+use crate::host::*;
+use crate::module::connection::ResponseMessage;
+use crate::module::*;
+use crate::module::command::*;
+use crate::utils::ShellCommand;
+use lightkeeper_module::command_module;
+
+
+#[command_module(
+    name="storage-mdraid-add",
+    version="0.0.1",
+    description="Adds a device to a software RAID array. If the array is degraded, the device is used as a replacement and rebuilding starts.",
+)]
+pub struct Add {
+}
+
+impl Module for Add {
+    fn new(_settings: &HashMap<String, String>) -> Self {
+        Add {
+        }
+    }
+}
+
+impl CommandModule for Add {
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new("ssh", "0.0.1"))
+    }
+
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            category: String::from("storage"),
+            parent_id: String::from("storage-mdraid"),
+            display_style: frontend::DisplayStyle::Icon,
+            display_icon: String::from("download"),
+            display_text: String::from("Add a device"),
+            user_parameters: vec![
+                frontend::UserInputField {
+                    label: String::from("Device"),
+                    default_value: String::from("/dev/"),
+                    validator_regexp: String::from("^/dev/\\S*$"),
+                    additional_validator_regexp: String::from("^/dev/\\S+$"),
+                    ..Default::default()
+                },
+            ],
+            confirmation_text: String::from("Are you sure you want to add {2} to {1}? Existing data on {2} may be overwritten."),
+            multivalue_level: 1,
+            ..Default::default()
+        }
+    }
+
+    fn get_affected_monitors(&self) -> Vec<String> {
+        vec![
+            String::from("storage-mdraid"),
+        ]
+    }
+
+    fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
+        let array_path = parameters.get(0).unwrap();
+        let device = parameters.get(1).unwrap().trim();
+
+        if !device.starts_with("/dev/") || device.len() <= "/dev/".len() {
+            return Err(format!("Invalid device: {}", device));
+        }
+
+        let mut command = ShellCommand::new();
+        command.use_sudo = host.settings.contains(&HostSetting::UseSudo);
+
+        if host.platform.os == platform_info::OperatingSystem::Linux {
+            command.arguments(vec!["mdadm", array_path, "--add", device]);
+            // mdadm can't be told to skip confirmations, so any prompt gets EOF and is declined.
+            Ok(format!("{} < /dev/null", command.to_string()))
+        }
+        else {
+            Err(String::from("Unsupported platform"))
+        }
+    }
+
+    fn process_response(&self, _host: Host, response: &ResponseMessage) -> Result<CommandResult, String> {
+        if response.return_code == 0 {
+            Ok(CommandResult::new_info(response.message.clone()))
+        }
+        else {
+            Ok(CommandResult::new_error(response.message.clone()))
+        }
+    }
+}
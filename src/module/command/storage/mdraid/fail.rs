@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+use crate::frontend;
+use crate::host::*;
+use crate::module::connection::ResponseMessage;
+use crate::module::*;
+use crate::module::command::*;
+use crate::utils::ShellCommand;
+use lightkeeper_module::command_module;
+
+
+#[command_module(
+    name="storage-mdraid-fail",
+    version="0.0.1",
+    description="Marks a member device of a software RAID array as failed.",
+)]
+pub struct Fail {
+}
+
+impl Module for Fail {
+    fn new(_settings: &HashMap<String, String>) -> Self {
+        Fail {
+        }
+    }
+}
+
+impl CommandModule for Fail {
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new("ssh", "0.0.1"))
+    }
+
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            category: String::from("storage"),
+            parent_id: String::from("storage-mdraid"),
+            display_style: frontend::DisplayStyle::Icon,
+            display_icon: String::from("cancel"),
+            display_text: String::from("Mark as failed"),
+            confirmation_text: String::from("Are you sure you want to mark this device as failed? The array will be degraded."),
+            depends_on_no_tags: vec![String::from("Faulty"), String::from("Removed")],
+            multivalue_level: 2,
+            ..Default::default()
+        }
+    }
+
+    fn get_affected_monitors(&self) -> Vec<String> {
+        vec![
+            String::from("storage-mdraid"),
+        ]
+    }
+
+    fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
+        let array_path = parameters.get(0).unwrap();
+        let device = parameters.get(1).unwrap();
+
+        let mut command = ShellCommand::new();
+        command.use_sudo = host.settings.contains(&HostSetting::UseSudo);
+
+        if host.platform.os == platform_info::OperatingSystem::Linux {
+            command.arguments(vec!["mdadm", array_path, "--fail", device]);
+            Ok(command.to_string())
+        }
+        else {
+            Err(String::from("Unsupported platform"))
+        }
+    }
+
+    fn process_response(&self, _host: Host, response: &ResponseMessage) -> Result<CommandResult, String> {
+        if response.return_code == 0 {
+            Ok(CommandResult::new_info(response.message.clone()))
+        }
+        else {
+            Ok(CommandResult::new_error(response.message.clone()))
+        }
+    }
+}
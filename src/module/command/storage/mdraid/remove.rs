@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+use crate::frontend;
+use crate::host::*;
+use crate::module::connection::ResponseMessage;
+use crate::module::*;
+use crate::module::command::*;
+use crate::utils::ShellCommand;
+use lightkeeper_module::command_module;
+
+
+#[command_module(
+    name="storage-mdraid-remove",
+    version="0.0.1",
+    description="Removes a failed or spare member device from a software RAID array.",
+)]
+pub struct Remove {
+}
+
+impl Module for Remove {
+    fn new(_settings: &HashMap<String, String>) -> Self {
+        Remove {
+        }
+    }
+}
+
+impl CommandModule for Remove {
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new("ssh", "0.0.1"))
+    }
+
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            category: String::from("storage"),
+            parent_id: String::from("storage-mdraid"),
+            display_style: frontend::DisplayStyle::Icon,
+            display_icon: String::from("delete"),
+            display_text: String::from("Remove from array"),
+            confirmation_text: String::from("Are you sure you want to remove this device from the array?"),
+            depends_on_tags: vec![String::from("Removable")],
+            multivalue_level: 2,
+            ..Default::default()
+        }
+    }
+
+    fn get_affected_monitors(&self) -> Vec<String> {
+        vec![
+            String::from("storage-mdraid"),
+        ]
+    }
+
+    fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
+        let array_path = parameters.get(0).unwrap();
+        let device = parameters.get(1).unwrap();
+
+        let mut command = ShellCommand::new();
+        command.use_sudo = host.settings.contains(&HostSetting::UseSudo);
+
+        if host.platform.os == platform_info::OperatingSystem::Linux {
+            command.arguments(vec!["mdadm", array_path, "--remove", device]);
+            Ok(command.to_string())
+        }
+        else {
+            Err(String::from("Unsupported platform"))
+        }
+    }
+
+    fn process_response(&self, _host: Host, response: &ResponseMessage) -> Result<CommandResult, String> {
+        if response.return_code == 0 {
+            Ok(CommandResult::new_info(response.message.clone()))
+        }
+        else {
+            Ok(CommandResult::new_error(response.message.clone()))
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use crate::frontend;
+use crate::host::*;
+use crate::module::connection::ResponseMessage;
+use crate::module::*;
+use crate::module::command::*;
+use crate::utils::ShellCommand;
+use lightkeeper_module::command_module;
+
+/// Bits 0-2 of smartctl exit status mean that the command itself failed. Others describe disk health.
+const EXIT_STATUS_COMMAND_FAILED_MASK: i32 = 0b111;
+
+#[command_module(
+    name="storage-smart-selftest",
+    version="0.0.1",
+    description="Starts a SMART self-test on a disk. Results are shown by storage-smart after the test completes.",
+)]
+pub struct SelfTest {
+}
+
+impl Module for SelfTest {
+    fn new(_settings: &HashMap<String, String>) -> Self {
+        SelfTest {
+        }
+    }
+}
+
+impl CommandModule for SelfTest {
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new("ssh", "0.0.1"))
+    }
+
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            category: String::from("storage"),
+            parent_id: String::from("storage-smart"),
+            display_style: frontend::DisplayStyle::Icon,
+            display_icon: String::from("start"),
+            display_text: String::from("Start a self-test"),
+            depends_on_value: vec![String::from("Passed"), String::from("Failing")],
+            depends_on_no_tags: vec![String::from("Self-test running")],
+            user_parameters: vec![
+                frontend::UserInputField::option("Test type", "short", vec![
+                    ("short", "Takes usually a few minutes."),
+                    ("long", "Reads the whole disk surface. Can take hours."),
+                ]),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn get_affected_monitors(&self) -> Vec<String> {
+        vec![
+            String::from("storage-smart"),
+        ]
+    }
+
+    fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
+        let device = parameters.get(0).unwrap();
+        let device_type = parameters.get(1).unwrap();
+        let test_type = parameters.get(2).unwrap();
+
+        if test_type != "short" && test_type != "long" {
+            return Err(format!("Invalid test type: {}", test_type));
+        }
+
+        let mut command = ShellCommand::new();
+        command.use_sudo = host.settings.contains(&HostSetting::UseSudo);
+
+        if host.platform.os == platform_info::OperatingSystem::Linux {
+            command.arguments(vec!["smartctl", "-d", device_type, "-t", test_type, device]);
+            Ok(command.to_string())
+        }
+        else {
+            Err(String::from("Unsupported platform"))
+        }
+    }
+
+    fn process_response(&self, _host: Host, response: &ResponseMessage) -> Result<CommandResult, String> {
+        if response.return_code & EXIT_STATUS_COMMAND_FAILED_MASK == 0 && response.message.contains("Testing has begun") {
+            // E.g. "Please wait 2 minutes for test to complete."
+            let duration = response.message.lines().find(|line| line.starts_with("Please wait")).unwrap_or_default();
+            Ok(CommandResult::new_info(duration))
+        }
+        else {
+            Ok(CommandResult::new_error(response.message.clone()))
+        }
+    }
+}
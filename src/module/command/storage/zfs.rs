@@ -0,0 +1,8 @@
+pub mod scrub;
+pub use scrub::Scrub;
+
+pub mod snapshot;
+pub use snapshot::Snapshot;
+
+pub mod list_snapshots;
+pub use list_snapshots::ListSnapshots;
\ No newline at end of file
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use chrono::DateTime;
+use crate::frontend;
+use crate::host::*;
+use crate::module::connection::ResponseMessage;
+use crate::module::*;
+use crate::module::command::*;
+use crate::utils::ShellCommand;
+use crate::utils::string_manipulation;
+use lightkeeper_module::command_module;
+
+
+#[command_module(
+    name="storage-zfs-list-snapshots",
+    version="0.0.1",
+    description="Lists snapshots of a ZFS dataset.",
+)]
+pub struct ListSnapshots {
+}
+
+impl Module for ListSnapshots {
+    fn new(_settings: &HashMap<String, String>) -> Self {
+        ListSnapshots {
+        }
+    }
+}
+
+impl CommandModule for ListSnapshots {
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new("ssh", "0.0.1"))
+    }
+
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            category: String::from("storage"),
+            parent_id: String::from("storage-zfs-dataset"),
+            display_style: frontend::DisplayStyle::Icon,
+            display_icon: String::from("view-document"),
+            display_text: String::from("Show snapshots"),
+            action: UIAction::TextDialog,
+            ..Default::default()
+        }
+    }
+
+    fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
+        let dataset = parameters.get(0).unwrap();
+
+        let mut command = ShellCommand::new();
+        command.use_sudo = host.settings.contains(&HostSetting::UseSudo);
+
+        if host.platform.os == platform_info::OperatingSystem::Linux {
+            command.arguments(vec!["zfs", "list", "-Hp", "-t", "snapshot", "-o", "name,used,refer,creation", "-s", "creation", "-d", "1", dataset]);
+            Ok(command.to_string())
+        }
+        else {
+            Err(String::from("Unsupported platform"))
+        }
+    }
+
+    fn process_response(&self, _host: Host, response: &ResponseMessage) -> Result<CommandResult, String> {
+        if response.is_error() {
+            return Ok(CommandResult::new_error(response.message.clone()));
+        }
+
+        let mut result_rows = Vec::new();
+        for line in response.message.lines().filter(|line| !line.trim().is_empty()) {
+            let parts = line.split('\t').collect::<Vec<_>>();
+            if parts.len() < 4 {
+                return Err(format!("Invalid zfs list output: {}", line));
+            }
+
+            let used = parts[1].parse::<u64>().unwrap_or_default();
+            let referenced = parts[2].parse::<u64>().unwrap_or_default();
+            // TODO: format according to locale.
+            let created = parts[3].parse::<i64>().ok().and_then(|timestamp| DateTime::from_timestamp(timestamp, 0))
+                                  .map(|datetime| datetime.format("%d.%m.%Y %H:%M:%S UTC").to_string())
+                                  .unwrap_or(String::from("(Unknown)"));
+
+            result_rows.push(format!("- **{}**, created {} (used {}, referenced {})",
+                                     parts[0], created,
+                                     string_manipulation::format_bytes_human(used),
+                                     string_manipulation::format_bytes_human(referenced)));
+        }
+
+        if result_rows.is_empty() {
+            result_rows.push(String::from("No snapshots"));
+        }
+
+        Ok(CommandResult::new_hidden(result_rows.join("\n")))
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use crate::frontend;
+use crate::host::*;
+use crate::module::connection::ResponseMessage;
+use crate::module::*;
+use crate::module::command::*;
+use crate::utils::ShellCommand;
+use lightkeeper_module::command_module;
+
+
+#[command_module(
+    name="storage-zfs-scrub",
+    version="0.0.1",
+    description="Starts a scrub on a ZFS pool.",
+)]
+pub struct Scrub {
+}
+
+impl Module for Scrub {
+    fn new(_settings: &HashMap<String, String>) -> Self {
+        Scrub {
+        }
+    }
+}
+
+impl CommandModule for Scrub {
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new("ssh", "0.0.1"))
+    }
+
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            category: String::from("storage"),
+            parent_id: String::from("storage-zfs-pool"),
+            display_style: frontend::DisplayStyle::Icon,
+            display_icon: String::from("refresh"),
+            display_text: String::from("Start a scrub"),
+            confirmation_text: String::from("Scrub reads all data in the pool and can slow down other disk I/O. Start scrub?"),
+            depends_on_no_tags: vec![String::from("Scrubbing"), String::from("Resilvering")],
+            multivalue_level: 1,
+            ..Default::default()
+        }
+    }
+
+    fn get_affected_monitors(&self) -> Vec<String> {
+        vec![
+            String::from("storage-zfs-pool"),
+        ]
+    }
+
+    fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
+        let pool_name = parameters.get(0).unwrap();
+
+        let mut command = ShellCommand::new();
+        command.use_sudo = host.settings.contains(&HostSetting::UseSudo);
+
+        if host.platform.os == platform_info::OperatingSystem::Linux {
+            command.arguments(vec!["zpool", "scrub", pool_name]);
+            Ok(command.to_string())
+        }
+        else {
+            Err(String::from("Unsupported platform"))
+        }
+    }
+
+    fn process_response(&self, _host: Host, response: &ResponseMessage) -> Result<CommandResult, String> {
+        if response.return_code == 0 {
+            Ok(CommandResult::new_info(String::new()))
+        }
+        else {
+            Ok(CommandResult::new_error(response.message.clone()))
+        }
+    }
+}
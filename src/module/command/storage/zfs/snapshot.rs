@@ -0,0 +1,81 @@
+use chrono;
+
+use std::collections::HashMap;
+use crate::frontend;
+use crate::host::*;
+use crate::module::connection::ResponseMessage;
+use crate::module::*;
+use crate::module::command::*;
+use crate::utils::ShellCommand;
+use lightkeeper_module::command_module;
+
+
+#[command_module(
+    name="storage-zfs-snapshot",
+    version="0.0.1",
+    description="Creates a snapshot of a ZFS dataset.",
+    settings={
+      snapshot_name => "Name of the snapshot. $TIME is replaced with a timestamp. Defaults to 'snapshot_$TIME'."
+    }
+)]
+pub struct Snapshot {
+    pub snapshot_name: String,
+}
+
+impl Module for Snapshot {
+    fn new(settings: &HashMap<String, String>) -> Self {
+        Snapshot {
+            snapshot_name: settings.get("snapshot_name").unwrap_or(&String::from("snapshot_$TIME")).clone(),
+        }
+    }
+}
+
+impl CommandModule for Snapshot {
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new("ssh", "0.0.1"))
+    }
+
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            category: String::from("storage"),
+            parent_id: String::from("storage-zfs-dataset"),
+            display_style: frontend::DisplayStyle::Icon,
+            display_icon: String::from("copy"),
+            display_text: String::from("Create a snapshot"),
+            ..Default::default()
+        }
+    }
+
+    fn get_affected_monitors(&self) -> Vec<String> {
+        vec![
+            String::from("storage-zfs-dataset"),
+        ]
+    }
+
+    fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
+        let dataset = parameters.get(0).unwrap();
+
+        let timestamp = chrono::Utc::now().format("%Y%m%d%H%M%S").to_string();
+        let snapshot_name = self.snapshot_name.replace("$TIME", &timestamp);
+
+        let mut command = ShellCommand::new();
+        command.use_sudo = host.settings.contains(&HostSetting::UseSudo);
+
+        if host.platform.os == platform_info::OperatingSystem::Linux {
+            command.arguments(vec!["zfs", "snapshot", &format!("{}@{}", dataset, snapshot_name)]);
+            Ok(command.to_string())
+        }
+        else {
+            Err(String::from("Unsupported platform"))
+        }
+    }
+
+    fn process_response(&self, _host: Host, response: &ResponseMessage) -> Result<CommandResult, String> {
+        if response.return_code == 0 {
+            Ok(CommandResult::new_info(String::new()))
+        }
+        else {
+            Ok(CommandResult::new_error(response.message.clone()))
+        }
+    }
+}
@@ -40,6 +40,10 @@ impl CommandModule for Start {
         }
     }
 
+    fn get_affected_monitors(&self) -> Vec<String> {
+        vec![String::from("systemd-service")]
+    }
+
     fn get_connector_message(&self, host: Host, parameters: Vec<String>) -> Result<String, String> {
         let service = parameters.first().unwrap();
 
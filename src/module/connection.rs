@@ -1,6 +1,8 @@
 pub mod connection_module;
 pub use connection_module::ConnectionModule;
 pub use connection_module::Connector;
+pub use connection_module::ResponseFuture;
+pub use connection_module::AbortHandle;
 
 pub mod response_message;
 pub use response_message::ResponseMessage;
@@ -1,6 +1,8 @@
 
 use std::collections::HashMap;
+use std::future::Future;
 use std::net::IpAddr;
+use std::pin::Pin;
 use std::io;
 use crate::module::MetadataSupport;
 use crate::module::module::Module;
@@ -8,10 +10,18 @@ use crate::module::connection::ResponseMessage;
 use crate::file_handler::FileMetadata;
 
 pub type Connector = Box<dyn ConnectionModule + Send>;
+pub type ResponseFuture = Pin<Box<dyn Future<Output = Result<ResponseMessage, String>> + Send>>;
+pub type AbortHandle = Box<dyn Fn() + Send + Sync>;
 
 pub trait ConnectionModule : MetadataSupport + Module {
     fn send_message(&mut self, message: &str) -> Result<ResponseMessage, String>;
 
+    /// Returns a future that doesn't block a thread while waiting for the response.
+    /// Returns None if not supported, in which case `send_message` is used instead.
+    fn send_message_async(&mut self, _message: &str) -> Option<ResponseFuture> {
+        None
+    }
+
     fn download_file(&self, _source: &String) -> io::Result<(FileMetadata, Vec<u8>)> {
         Err(io::Error::new(io::ErrorKind::Other, "Not implemented"))
     }
@@ -20,6 +30,11 @@ pub trait ConnectionModule : MetadataSupport + Module {
         Err(io::Error::new(io::ErrorKind::Other, "Not implemented"))
     }
 
+    /// Whether multiple shell commands can be combined into one message with `utils::CommandBatch`.
+    fn supports_batching(&self) -> bool {
+        false
+    }
+
     /// Check the connection status. Only relevant to modules that use a persistent connection.
     fn is_connected(&self) -> bool {
         false
@@ -40,6 +55,12 @@ pub trait ConnectionModule : MetadataSupport + Module {
         // Do nothing by default.
     }
 
+    /// Returns a function that interrupts work in progress, e.g. when a request times out.
+    /// Can be called without access to the module, since the work in progress keeps it locked.
+    fn get_abort_handle(&self) -> Option<AbortHandle> {
+        None
+    }
+
     fn reconnect(&mut self) -> Result<(), String> {
         Ok(())
     }
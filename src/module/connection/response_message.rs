@@ -3,10 +3,13 @@ use serde_derive::{Serialize, Deserialize};
 
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ResponseMessage {
     pub message: String,
     pub return_code: i32,
     pub is_from_cache: bool,
+    /// Seconds since the response was cached. Set only if `is_from_cache` is true.
+    pub cache_age: Option<u64>,
     /// Not found in cache when OnlyCache policy was used.
     pub not_found: bool,
 }
@@ -2,22 +2,35 @@ use std::{
     net::TcpStream,
     net::IpAddr,
     net::Ipv4Addr,
-    net::ToSocketAddrs,
+    net::SocketAddr,
+    net::Shutdown,
+    os::fd::OwnedFd,
+    os::unix::net::UnixStream,
+    process,
+    sync::Arc,
+    sync::Mutex,
+    sync::atomic::AtomicBool,
+    sync::atomic::Ordering,
     collections::HashMap,
     path::Path,
     io,
     io::Read,
     io::Write,
+    time::Duration,
 };
 
 use chrono::Utc;
-use ssh2::Session;
+use ssh2::{BlockDirections, Session};
+use tokio::io::{unix::AsyncFd, Interest};
 use crate::file_handler::FileMetadata;
 use crate::utils::strip_newline;
 use lightkeeper_module::connection_module;
 use crate::module::*;
 use crate::module::connection::*;
 
+/// Operations waiting for the socket are retried at least this often, in case readiness of another operation was missed.
+const SOCKET_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 #[connection_module(
     name="ssh",
     version="0.0.1",
@@ -30,11 +43,19 @@ use crate::module::connection::*;
       private_key_passphrase => "Passphrase for the private key file. Default: empty.",
       connection_timeout => "Timeout (in seconds) for the SSH connection. Default: 15.",
       agent_key_identifier => "Identifier for selecting key from ssh-agent. This is the comment part of the \
-                               key (e.g. user@desktop). Default: empty (all keys are tried)."
+                               key (e.g. user@desktop). Default: empty (all keys are tried).",
+      proxy_jump => "Jump host to connect through (e.g. user@jumphost:22). Requires OpenSSH client. Default: empty (not used).",
+      batch_commands => "Combine monitor commands into one execution to reduce round trips. Commands are then run with `sh` instead of the login shell. Default: false."
     }
 )]
 pub struct Ssh2 {
     session: Session,
+    /// Copy of the session socket for waiting until it's ready. Session is in non-blocking mode after connecting.
+    socket: Option<Arc<OwnedFd>>,
+    /// Set by non-blocking requests if a channel can't be opened anymore, so the session is reconnected.
+    session_lost: Arc<AtomicBool>,
+    /// libssh2 keeps the state of opening a channel or SFTP in the session, so only one can be opened at a time.
+    open_lock: Arc<tokio::sync::Mutex<()>>,
     is_initialized: bool,
     address: IpAddr,
     port: u16,
@@ -43,7 +64,11 @@ pub struct Ssh2 {
     private_key_path: Option<String>,
     private_key_passphrase: Option<String>,
     agent_key_identifier: Option<String>,
+    proxy_jump: Option<String>,
+    /// OpenSSH client process that provides the tunnel when using a jump host.
+    proxy_process: Option<Arc<Mutex<process::Child>>>,
     connection_timeout: u16,
+    batch_commands: bool,
 }
 
 impl Module for Ssh2 {
@@ -53,6 +78,9 @@ impl Module for Ssh2 {
 
         Ssh2 {
             session: session,
+            socket: None,
+            session_lost: Arc::new(AtomicBool::new(false)),
+            open_lock: Arc::new(tokio::sync::Mutex::new(())),
             is_initialized: false,
             address: IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
             port: settings.get("port").unwrap_or(&String::from("22")).parse::<u16>().unwrap(),
@@ -62,30 +90,50 @@ impl Module for Ssh2 {
             // TODO: Hide passphrase in UI (currently behaves like a normal text field).
             private_key_passphrase: settings.get("private_key_passphrase").cloned(),
             agent_key_identifier: settings.get("agent_key_identifier").cloned(),
+            proxy_jump: settings.get("proxy_jump").cloned(),
+            proxy_process: None,
             connection_timeout: settings.get("connection_timeout").unwrap_or(&String::from("15")).parse::<u16>().unwrap(),
+            batch_commands: settings.get("batch_commands").map(|value| value == "true").unwrap_or(false),
         }
     }
 }
 
 impl ConnectionModule for Ssh2 {
     fn connect(&mut self, address: &IpAddr) -> Result<(), String> {
-        if self.is_initialized {
+        if self.is_connected() {
             return Ok(())
         }
+        else if self.is_initialized {
+            self.disconnect();
+        }
 
         self.address = address.clone();
 
-        let socket_address = format!("{}:{}", address, self.port).to_socket_addrs().unwrap().next().unwrap();
-        let connection_timeout = std::time::Duration::from_secs(self.connection_timeout as u64);
-        let stream = match TcpStream::connect_timeout(&socket_address, connection_timeout) {
-            Ok(stream) => stream,
-            Err(error) => return Err(error.to_string())
-        };
+        self.session = Session::new().unwrap();
+        // Only affects connecting and authenticating, which are done in blocking mode.
+        self.session.set_timeout(self.connection_timeout as u32 * 1000);
+
+        let socket = if let Some(proxy_jump) = self.proxy_jump.clone() {
+            let stream = self.open_proxy_tunnel(&proxy_jump)?;
+            log::info!("Connected to {}:{} through {}", address, self.port, proxy_jump);
+            let socket = stream.try_clone().map_err(|error| error.to_string())?;
+            self.session.set_tcp_stream(stream);
+            OwnedFd::from(socket)
+        }
+        else {
+            let socket_address = SocketAddr::new(*address, self.port);
+            let connection_timeout = std::time::Duration::from_secs(self.connection_timeout as u64);
+            let stream = match TcpStream::connect_timeout(&socket_address, connection_timeout) {
+                Ok(stream) => stream,
+                Err(error) => return Err(error.to_string())
+            };
 
-        log::info!("Connected to {}:{}", address, self.port);
+            log::info!("Connected to {}:{}", address, self.port);
+            let socket = stream.try_clone().map_err(|error| error.to_string())?;
+            self.session.set_tcp_stream(stream);
+            OwnedFd::from(socket)
+        };
 
-        self.session = Session::new().unwrap();
-        self.session.set_tcp_stream(stream);
         if let Err(error) = self.session.handshake() {
             return Err(format!("Handshake error: {}", error));
         };
@@ -130,6 +178,11 @@ impl ConnectionModule for Ssh2 {
             }
         }
 
+        // Requests to the same host can then be processed at the same time without a thread for each.
+        self.session.set_blocking(false);
+        self.socket = Some(Arc::new(socket));
+        // Requests that are still using the old session can't affect the new one.
+        self.session_lost = Arc::new(AtomicBool::new(false));
         self.is_initialized = true;
         Ok(())
     }
@@ -139,7 +192,7 @@ impl ConnectionModule for Ssh2 {
             return Ok(ResponseMessage::empty());
         }
 
-        let mut channel = match self.session.channel_session() {
+        let mut channel = match self.open_channel() {
             Ok(channel) => channel,
             Err(error) => {
                 // Error is likely duo to disconnected or timeouted session. Try to reconnect once.
@@ -148,83 +201,144 @@ impl ConnectionModule for Ssh2 {
                     return Err(format!("Error reconnecting: {}", error));
                 }
 
-                match self.session.channel_session() {
+                match self.open_channel() {
                     Ok(channel) => channel,
                     Err(error) => return Err(format!("Error opening channel: {}", error))
                 }
             }
         };
 
-        if let Err(error) = channel.exec(message) {
+        if let Err(error) = self.wait_for(|| channel.exec(message).map_err(io::Error::from)) {
             return Err(format!("Error executing command '{}': {}", message, error));
         };
 
-        let mut output = String::new();
-        if let Err(error) = channel.read_to_string(&mut output) {
-            return Err(format!("Invalid output string received: {}", error));
+        let mut output = Vec::new();
+        let mut buffer = [0u8; 8192];
+        loop {
+            match self.wait_for(|| channel.read(&mut buffer)) {
+                Ok(0) => break,
+                Ok(count) => output.extend_from_slice(&buffer[..count]),
+                Err(error) => return Err(format!("Error reading output: {}", error)),
+            }
+        }
+
+        if let Err(error) = self.wait_for(|| channel.wait_close().map_err(io::Error::from)) {
+            log::error!("Error while closing channel: {}", error);
         };
 
         let exit_status = channel.exit_status().unwrap_or(-1);
+        Self::to_response(output, exit_status)
+    }
 
-        if let Err(error) = channel.wait_close() {
-            log::error!("Error while closing channel: {}", error);
-        };
+    fn send_message_async(&mut self, message: &str) -> Option<ResponseFuture> {
+        if message.is_empty() {
+            return Some(Box::pin(async { Ok(ResponseMessage::empty()) }));
+        }
+
+        // If not connected, the blocking version will handle reconnecting.
+        let socket = self.socket.as_ref()?.try_clone().ok()?;
+        let session = self.session.clone();
+        let session_lost = self.session_lost.clone();
+        let open_lock = self.open_lock.clone();
+        let message = message.to_string();
+
+        Some(Box::pin(async move {
+            let socket = AsyncFd::new(socket).map_err(|error| error.to_string())?;
+
+            let open_guard = open_lock.lock().await;
+            let mut channel = Self::wait_for_async(&session, &socket, || session.channel_session().map_err(io::Error::from)).await
+                                  .map_err(|error| {
+                                      // Error is likely due to disconnected or timeouted session.
+                                      session_lost.store(true, Ordering::Relaxed);
+                                      format!("Error opening channel: {}", error)
+                                  })?;
+            drop(open_guard);
+
+            Self::wait_for_async(&session, &socket, || channel.exec(&message).map_err(io::Error::from)).await
+                 .map_err(|error| format!("Error executing command '{}': {}", message, error))?;
+
+            let mut output = Vec::new();
+            let mut buffer = [0u8; 8192];
+            loop {
+                match Self::wait_for_async(&session, &socket, || channel.read(&mut buffer)).await {
+                    Ok(0) => break,
+                    Ok(count) => output.extend_from_slice(&buffer[..count]),
+                    Err(error) => return Err(format!("Error reading output: {}", error)),
+                }
+            }
+
+            if let Err(error) = Self::wait_for_async(&session, &socket, || channel.wait_close().map_err(io::Error::from)).await {
+                log::error!("Error while closing channel: {}", error);
+            };
 
-        Ok(ResponseMessage::new(strip_newline(&output), exit_status))
+            let exit_status = channel.exit_status().unwrap_or(-1);
+            Self::to_response(output, exit_status)
+        }))
     }
 
     fn download_file(&self, source: &String) -> io::Result<(FileMetadata, Vec<u8>)> {
-        let sftp = self.session.sftp().unwrap();
-        match sftp.open(Path::new(&source)) {
+        let sftp = self.open_sftp()?;
+        match self.wait_for(|| sftp.open(Path::new(&source)).map_err(io::Error::from)) {
             Ok(mut file) => {
                 let mut contents = Vec::new();
-                if let Err(error) = file.read_to_end(&mut contents) {
-                    Err(error)
-                }
-                else {
-                    let stat = file.stat().unwrap();
-                    let metadata = FileMetadata {
-                        download_time: Utc::now(),
-                        local_path: None,
-                        remote_path: source.clone(),
-                        remote_file_hash: sha256::digest(contents.as_slice()),
-                        owner_uid: stat.uid.unwrap(),
-                        owner_gid: stat.gid.unwrap(),
-                        permissions: stat.perm.unwrap(),
-                        temporary: true,
-                    };
-                    Ok((metadata, contents))
+                let mut buffer = [0u8; 32768];
+                loop {
+                    match self.wait_for(|| file.read(&mut buffer))? {
+                        0 => break,
+                        count => contents.extend_from_slice(&buffer[..count]),
+                    }
                 }
+
+                let stat = self.wait_for(|| file.stat().map_err(io::Error::from))?;
+                let metadata = FileMetadata {
+                    download_time: Utc::now(),
+                    local_path: None,
+                    remote_path: source.clone(),
+                    remote_file_hash: sha256::digest(contents.as_slice()),
+                    owner_uid: stat.uid.unwrap(),
+                    owner_gid: stat.gid.unwrap(),
+                    permissions: stat.perm.unwrap(),
+                    temporary: true,
+                };
+                Ok((metadata, contents))
             }
             Err(error) => {
-                Err(io::Error::new(io::ErrorKind::Other, error.message()))
+                Err(io::Error::new(io::ErrorKind::Other, error.to_string()))
             }
         }
     }
 
     fn upload_file(&self, metadata: &FileMetadata, contents: Vec<u8>) -> io::Result<()> {
-        let sftp = self.session.sftp().unwrap();
+        let sftp = self.open_sftp()?;
 
-        let file = sftp.open_mode(
+        let file = self.wait_for(|| sftp.open_mode(
             Path::new(&metadata.remote_path),
             ssh2::OpenFlags::WRITE | ssh2::OpenFlags::TRUNCATE,
             metadata.permissions.try_into().unwrap(),
             ssh2::OpenType::File,
-        );
+        ).map_err(io::Error::from));
 
         match file {
             Ok(mut file) => {
-                file.write(&contents)?;
+                let mut remaining = contents.as_slice();
+                while !remaining.is_empty() {
+                    let count = self.wait_for(|| file.write(remaining))?;
+                    remaining = &remaining[count..];
+                }
                 Ok(())
             }
             Err(error) => {
-                Err(io::Error::new(io::ErrorKind::Other, error.message()))
+                Err(io::Error::new(io::ErrorKind::Other, error.to_string()))
             }
         }
     }
 
     fn is_connected(&self) -> bool {
-        self.is_initialized
+        self.is_initialized && !self.session_lost.load(Ordering::Relaxed)
+    }
+
+    fn supports_batching(&self) -> bool {
+        self.batch_commands
     }
 
     fn reconnect(&mut self) -> Result<(), String> {
@@ -233,9 +347,121 @@ impl ConnectionModule for Ssh2 {
         self.connect(&self.address.clone())
     }
 
+    /// Shuts down the socket, so blocking operations fail instead of keeping the connector locked.
+    /// The session is then reconnected by the next request.
+    fn get_abort_handle(&self) -> Option<AbortHandle> {
+        let socket = self.socket.as_ref()?.try_clone().ok()?;
+        let session_lost = self.session_lost.clone();
+
+        Some(Box::new(move || {
+            session_lost.store(true, Ordering::Relaxed);
+            // Shutdown works the same for the TCP socket and the Unix socket of a proxy tunnel.
+            if let Ok(socket) = socket.try_clone() {
+                let _ = TcpStream::from(socket).shutdown(Shutdown::Both);
+            }
+        }))
+    }
+
     fn disconnect(&mut self) {
         let _ = self.session.disconnect(None, "", None);
+        self.socket = None;
+        if let Some(proxy_process) = self.proxy_process.take() {
+            let mut proxy_process = proxy_process.lock().unwrap();
+            let _ = proxy_process.kill();
+            let _ = proxy_process.wait();
+        }
         self.is_initialized = false;
     }
 }
 
+
+impl Ssh2 {
+    fn to_response(output: Vec<u8>, exit_status: i32) -> Result<ResponseMessage, String> {
+        match String::from_utf8(output) {
+            Ok(output) => Ok(ResponseMessage::new(strip_newline(&output), exit_status)),
+            Err(error) => Err(format!("Invalid output string received: {}", error)),
+        }
+    }
+
+    fn open_channel(&self) -> io::Result<ssh2::Channel> {
+        let _open_guard = self.open_lock.blocking_lock();
+        self.wait_for(|| self.session.channel_session().map_err(io::Error::from))
+    }
+
+    fn open_sftp(&self) -> io::Result<ssh2::Sftp> {
+        let _open_guard = self.open_lock.blocking_lock();
+        self.wait_for(|| self.session.sftp().map_err(io::Error::from))
+    }
+
+    /// Retries the operation until the session isn't blocked anymore. Blocks the thread while waiting,
+    /// so this can't be used from the async worker threads.
+    fn wait_for<T>(&self, mut operation: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+        match operation() {
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                let socket = match self.socket.as_ref() {
+                    Some(socket) => socket.try_clone()?,
+                    None => return Err(io::Error::new(io::ErrorKind::NotConnected, "Not connected")),
+                };
+
+                let runtime = tokio::runtime::Handle::try_current().map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+                runtime.block_on(async {
+                    let socket = AsyncFd::new(socket)?;
+                    Self::wait_for_async(&self.session, &socket, operation).await
+                })
+            },
+            result => result,
+        }
+    }
+
+    /// Retries the operation until the session isn't blocked anymore.
+    async fn wait_for_async<T>(session: &Session, socket: &AsyncFd<OwnedFd>, mut operation: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+        loop {
+            match operation() {
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                    let interest = match session.block_directions() {
+                        BlockDirections::Outbound => Interest::WRITABLE,
+                        BlockDirections::Both => Interest::READABLE | Interest::WRITABLE,
+                        BlockDirections::Inbound | BlockDirections::None => Interest::READABLE,
+                    };
+
+                    // Other requests share the session and can consume data meant for this one, so don't wait forever.
+                    // Readiness is cleared before retrying, so data arriving in between isn't missed.
+                    if let Ok(ready) = tokio::time::timeout(SOCKET_POLL_INTERVAL, socket.ready(interest)).await {
+                        ready?.clear_ready();
+                    }
+                },
+                result => return result,
+            }
+        }
+    }
+
+    /// Starts OpenSSH client in stdio forwarding mode (ssh -W) to reach the host through a jump host.
+    /// Returns the local end of the tunnel.
+    fn open_proxy_tunnel(&mut self, proxy_jump: &str) -> Result<UnixStream, String> {
+        let (local_stream, remote_stream) = UnixStream::pair().map_err(|error| error.to_string())?;
+        let remote_stream_copy = remote_stream.try_clone().map_err(|error| error.to_string())?;
+
+        // Multiple jump hosts can be given as a comma-separated list. The last one is connected through the others.
+        let mut jump_hosts = proxy_jump.split(',').map(|jump_host| jump_host.trim()).collect::<Vec<&str>>();
+        let last_jump_host = jump_hosts.pop().unwrap_or_default();
+
+        let mut command = process::Command::new("ssh");
+        command.arg("-o").arg("BatchMode=yes")
+               .arg("-o").arg(format!("ConnectTimeout={}", self.connection_timeout))
+               .arg("-W").arg(SocketAddr::new(self.address, self.port).to_string());
+
+        if !jump_hosts.is_empty() {
+            command.arg("-J").arg(jump_hosts.join(","));
+        }
+
+        let child = command.arg(format!("ssh://{}", last_jump_host))
+                           .stdin(process::Stdio::from(std::os::fd::OwnedFd::from(remote_stream)))
+                           .stdout(process::Stdio::from(std::os::fd::OwnedFd::from(remote_stream_copy)))
+                           .stderr(process::Stdio::null())
+                           .spawn()
+                           .map_err(|error| format!("Failed to start ssh for jump host: {}", error))?;
+
+        self.proxy_process = Some(Arc::new(Mutex::new(child)));
+        Ok(local_stream)
+    }
+}
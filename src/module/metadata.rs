@@ -2,6 +2,7 @@ use std::collections::HashMap;
 
 use super::ModuleSpecification;
 use crate::cache::CacheScope as CacheScope;
+use crate::cache::CacheSensitivity as CacheSensitivity;
 
 #[derive(Clone, Debug)]
 pub struct Metadata {
@@ -14,4 +15,6 @@ pub struct Metadata {
     /// Stateless modules can be run in parallel. Stateful modules can currently run only 1 connection per host.
     pub is_stateless: bool,
     pub cache_scope: CacheScope,
+    /// Sensitive responses are kept only in memory and never written to the cache file.
+    pub cache_sensitivity: CacheSensitivity,
 }
\ No newline at end of file
@@ -7,10 +7,13 @@ pub use metadata::Metadata;
 pub mod module;
 pub use module::Module;
 pub use module::MetadataSupport;
+pub use module::INSTANCE_ID_SETTING;
 
 pub mod connection;
 pub mod command;
 pub mod monitoring;
+pub mod scripted;
+pub mod plugin;
 
 pub mod module_specification;
 pub use module_specification::ModuleSpecification;
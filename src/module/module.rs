@@ -2,6 +2,9 @@ use std::collections::HashMap;
 use crate::module::metadata::Metadata;
 use crate::module::ModuleSpecification;
 
+/// Setting that contains the configured ID for instances of generic modules.
+pub const INSTANCE_ID_SETTING: &str = "instance_id";
+
 pub trait Module {
     fn new(settings: &HashMap<String, String>) -> Self where Self: Sized;
 }
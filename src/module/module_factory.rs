@@ -1,5 +1,7 @@
 
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use super::{
     module::MetadataSupport,
@@ -11,14 +13,27 @@ use super::{
     monitoring::MonitoringModule,
     command,
     command::CommandModule,
+    scripted,
+    plugin,
 };
+use crate::file_handler;
+
+/// Constructors are shared closures so that scripted and plugin modules can capture their script or library.
+type ConnectorConstructor = Arc<dyn Fn(&HashMap<String, String>) -> connection::Connector + Send + Sync>;
+type MonitorConstructor = Arc<dyn Fn(&HashMap<String, String>) -> monitoring::Monitor + Send + Sync>;
+type CommandConstructor = Arc<dyn Fn(&HashMap<String, String>) -> command::Command + Send + Sync>;
+
+/// Module scripts are loaded from this subdirectory of the configuration directory.
+const SCRIPTED_MODULES_DIR: &str = "modules";
+/// Native plugin libraries are loaded from this subdirectory of the configuration directory.
+const PLUGINS_DIR: &str = "plugins";
 
 
 #[derive(Default)]
 pub struct ModuleFactory {
-    connector_modules: Vec<(Metadata, fn(&HashMap<String, String>) -> connection::Connector)>,
-    monitor_modules: Vec<(Metadata, fn(&HashMap<String, String>) -> monitoring::Monitor)>,
-    command_modules: Vec<(Metadata, fn(&HashMap<String, String>) -> command::Command)>,
+    connector_modules: Vec<(Metadata, ConnectorConstructor)>,
+    monitor_modules: Vec<(Metadata, MonitorConstructor)>,
+    command_modules: Vec<(Metadata, CommandConstructor)>,
 }
 
 impl ModuleFactory {
@@ -40,30 +55,50 @@ impl ModuleFactory {
             normalized_spec.version = self.get_latest_version_for_connector(&normalized_spec.id);
         }
 
-        let constructor = self.connector_modules.iter().find(|(metadata, _ctor)| metadata.module_spec == normalized_spec).unwrap().1;
+        let constructor = &self.connector_modules.iter().find(|(metadata, _ctor)| metadata.module_spec == normalized_spec).unwrap().1;
         constructor(settings)
     }
 
-    pub fn new_monitor(&self, module_spec: &ModuleSpecification, settings: &HashMap<String, String>) -> monitoring::Monitor {
+    /// Returns an error if the connector the monitor uses doesn't exist, e.g. because of a typo in the connector setting.
+    pub fn new_monitor(&self, module_spec: &ModuleSpecification, settings: &HashMap<String, String>) -> Result<monitoring::Monitor, String> {
         let mut normalized_spec = module_spec.clone();
         normalized_spec.module_type = String::from("monitor");
         if normalized_spec.latest_version() {
             normalized_spec.version = self.get_latest_version_for_monitor(&normalized_spec.id);
         }
 
-        let constructor = self.monitor_modules.iter().find(|(metadata, _ctor)| metadata.module_spec == normalized_spec).unwrap().1;
-        constructor(settings)
+        let constructor = &self.monitor_modules.iter().find(|(metadata, _ctor)| metadata.module_spec == normalized_spec).unwrap().1;
+        let monitor = constructor(settings);
+        self.check_connector(monitor.get_connector_spec())?;
+        Ok(monitor)
     }
 
-    pub fn new_command(&self, module_spec: &ModuleSpecification, settings: &HashMap<String, String>) -> command::Command {
+    /// Returns an error if the connector the command uses doesn't exist, e.g. because of a typo in the connector setting.
+    pub fn new_command(&self, module_spec: &ModuleSpecification, settings: &HashMap<String, String>) -> Result<command::Command, String> {
         let mut normalized_spec = module_spec.clone();
         normalized_spec.module_type = String::from("command");
         if normalized_spec.latest_version() {
             normalized_spec.version = self.get_latest_version_for_command(&normalized_spec.id);
         }
 
-        let constructor = self.command_modules.iter().find(|(metadata, _ctor)| metadata.module_spec == normalized_spec).unwrap().1;
-        constructor(settings)
+        let constructor = &self.command_modules.iter().find(|(metadata, _ctor)| metadata.module_spec == normalized_spec).unwrap().1;
+        let command = constructor(settings);
+        self.check_connector(command.get_connector_spec())?;
+        Ok(command)
+    }
+
+    /// Generic modules get the connector from settings, so it's not guaranteed to exist like with built-in modules.
+    fn check_connector(&self, connector_spec: Option<ModuleSpecification>) -> Result<(), String> {
+        if let Some(connector_spec) = connector_spec {
+            let exists = self.connector_modules.iter().any(|(metadata, _ctor)| {
+                metadata.module_spec.id == connector_spec.id && (connector_spec.latest_version() || metadata.module_spec.version == connector_spec.version)
+            });
+
+            if !exists {
+                return Err(format!("Connector module '{}' was not found", connector_spec.id));
+            }
+        }
+        Ok(())
     }
 
     pub fn get_latest_version_for_command(&self, module_id: &String) -> String {
@@ -245,16 +280,179 @@ impl ModuleFactory {
         documentation
     }
 
+    /// Loads user-written module scripts from the configuration directory. Scripts that fail to load are skipped.
+    pub fn load_scripted_modules(&mut self, config_dir: &String) {
+        let config_dir = match Self::resolve_config_dir(config_dir) {
+            Some(config_dir) => config_dir,
+            None => return,
+        };
+
+        let modules_dir = config_dir.join(SCRIPTED_MODULES_DIR);
+        let mut monitor_count = 0;
+        let mut command_count = 0;
+
+        for runtime in scripted::runtime::load_scripts(&modules_dir) {
+            match self.validate_scripted_module(&runtime) {
+                Ok(script_metadata) if script_metadata.module_type == "monitor" => {
+                    let constructor_runtime = runtime.clone();
+                    let constructor_metadata = script_metadata.clone();
+                    let constructor: MonitorConstructor = Arc::new(move |settings| {
+                        Box::new(scripted::ScriptedMonitor::new_scripted(&constructor_runtime, &constructor_metadata, settings))
+                    });
+
+                    self.monitor_modules.push((script_metadata.metadata, constructor));
+                    monitor_count += 1;
+                },
+                Ok(script_metadata) => {
+                    let constructor_runtime = runtime.clone();
+                    let constructor_metadata = script_metadata.clone();
+                    let constructor: CommandConstructor = Arc::new(move |settings| {
+                        Box::new(scripted::ScriptedCommand::new_scripted(&constructor_runtime, &constructor_metadata, settings))
+                    });
+
+                    self.command_modules.push((script_metadata.metadata, constructor));
+                    command_count += 1;
+                },
+                Err(error) => log::error!("Skipping module script {}: {}", runtime.module_id, error),
+            }
+        }
+
+        if monitor_count > 0 || command_count > 0 {
+            log::info!("Loaded {} scripted monitoring modules and {} scripted command modules from {}",
+                       monitor_count, command_count, modules_dir.display());
+        }
+    }
+
+    /// Loads native plugin libraries from the configuration directory. Plugins with incompatible ABI or conflicting modules are skipped.
+    /// Connectors are registered first so that monitors and commands of the same plugin can use them.
+    pub fn load_plugin_modules(&mut self, config_dir: &String) {
+        let config_dir = match Self::resolve_config_dir(config_dir) {
+            Some(config_dir) => config_dir,
+            None => return,
+        };
+
+        for library in plugin::load_plugins(&config_dir.join(PLUGINS_DIR)) {
+            let mut manifests = library.manifest.modules.clone();
+            manifests.sort_by_key(|manifest| manifest.module_type != "connector");
+
+            for manifest in manifests {
+                if let Err(error) = self.validate_plugin_module(&manifest) {
+                    log::error!("Skipping module {} of plugin {}: {}", manifest.id, library.manifest.name, error);
+                    continue;
+                }
+
+                let metadata = plugin::get_metadata(&manifest);
+                let constructor_library = library.clone();
+
+                match manifest.module_type.as_str() {
+                    "connector" => {
+                        let constructor: ConnectorConstructor = Arc::new(move |settings| {
+                            let connector = plugin::PluginConnector::new_plugin(&constructor_library, &manifest, settings)
+                                                                    .unwrap_or_else(|error| plugin::PluginConnector::new_failed(&manifest, error));
+                            Box::new(connector)
+                        });
+                        self.connector_modules.push((metadata, constructor));
+                    },
+                    "monitor" => {
+                        let constructor: MonitorConstructor = Arc::new(move |settings| {
+                            let monitor = plugin::PluginMonitor::new_plugin(&constructor_library, &manifest, settings)
+                                                                .unwrap_or_else(|error| plugin::PluginMonitor::new_failed(&manifest, error));
+                            Box::new(monitor)
+                        });
+                        self.monitor_modules.push((metadata, constructor));
+                    },
+                    _ => {
+                        let constructor: CommandConstructor = Arc::new(move |settings| {
+                            let command = plugin::PluginCommand::new_plugin(&constructor_library, &manifest, settings)
+                                                                .unwrap_or_else(|error| plugin::PluginCommand::new_failed(&manifest, error));
+                            Box::new(command)
+                        });
+                        self.command_modules.push((metadata, constructor));
+                    },
+                }
+            }
+        }
+    }
+
+    fn validate_plugin_module(&self, manifest: &lightkeeper_plugin::ModuleManifest) -> Result<(), String> {
+        if !["connector", "monitor", "command"].contains(&manifest.module_type.as_str()) {
+            return Err(format!("Unknown module type \"{}\"", manifest.module_type));
+        }
+
+        if manifest.id.is_empty() || manifest.id.chars().any(char::is_whitespace) {
+            return Err(String::from("Module ID can't be empty or contain whitespace"));
+        }
+
+        let module_spec = ModuleSpecification::new_with_type(&manifest.id, &manifest.version, &manifest.module_type);
+        if self.get_module_metadatas().iter().any(|metadata| metadata.module_spec == module_spec) {
+            return Err(format!("Module {} already exists", module_spec.id));
+        }
+
+        if let Some(connector_spec) = plugin::get_connector_spec(manifest) {
+            let connector_spec = ModuleSpecification::new_with_type(connector_spec.id.as_str(), connector_spec.version.as_str(), "connector");
+            if !self.connector_modules.iter().any(|(metadata, _)| metadata.module_spec == connector_spec) {
+                return Err(format!("Connector module '{}' was not found", connector_spec.id));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn resolve_config_dir(config_dir: &String) -> Option<PathBuf> {
+        if config_dir.is_empty() {
+            file_handler::get_config_dir().ok()
+        }
+        else {
+            Some(Path::new(config_dir).to_path_buf())
+        }
+    }
+
+    /// Checks the same things as validate_modules(), but returns an error instead of panicking since scripts are user-written.
+    fn validate_scripted_module(&self, runtime: &scripted::ScriptRuntime) -> Result<scripted::runtime::ScriptMetadata, String> {
+        let script_metadata = runtime.get_metadata()?;
+        let module_spec = &script_metadata.metadata.module_spec;
+
+        if self.get_module_metadatas().iter().any(|metadata| &metadata.module_spec == module_spec) {
+            return Err(format!("Module {} already exists", module_spec.id));
+        }
+
+        if let Some(connector_spec) = &script_metadata.connector_spec {
+            let connector_spec = ModuleSpecification::new_with_type(connector_spec.id.as_str(), connector_spec.version.as_str(), "connector");
+            if !self.connector_modules.iter().any(|(metadata, _)| metadata.module_spec == connector_spec) {
+                return Err(format!("Connector module '{}' was not found", connector_spec.id));
+            }
+        }
+
+        if script_metadata.module_type == "monitor" {
+            runtime.get_display_options(scripted::ScriptedMonitor::default_display_options(runtime), &HashMap::new())?;
+            if !runtime.has_function("process_responses", 4) {
+                return Err(String::from("Function process_responses(settings, host, responses, parent) is missing"));
+            }
+        }
+        else {
+            runtime.get_display_options(scripted::ScriptedCommand::default_display_options(runtime), &HashMap::new())?;
+            if !runtime.has_function("get_connector_messages", 3) {
+                return Err(String::from("Function get_connector_messages(settings, host, parameters) is missing"));
+            }
+        }
+
+        Ok(script_metadata)
+    }
+
     fn load_modules(&mut self) {
         // Connection modules.
-        self.connector_modules = vec![
+        let connector_modules: Vec<(Metadata, fn(&HashMap<String, String>) -> connection::Connector)> = vec![
             (connection::Ssh2::get_metadata(), connection::Ssh2::new_connection_module),
             (connection::Http::get_metadata(), connection::Http::new_connection_module),
             (connection::LocalCommand::get_metadata(), connection::LocalCommand::new_connection_module),
         ];
 
+        self.connector_modules = connector_modules.into_iter()
+                                                  .map(|(metadata, constructor)| (metadata, Arc::new(constructor) as ConnectorConstructor))
+                                                  .collect();
+
         // Monitoring modules.
-        self.monitor_modules = vec![
+        let monitor_modules: Vec<(Metadata, fn(&HashMap<String, String>) -> monitoring::Monitor)> = vec![
             (monitoring::os::Os::get_metadata(), monitoring::os::Os::new_monitoring_module),
             (monitoring::linux::Package::get_metadata(), monitoring::linux::Package::new_monitoring_module),
             (monitoring::linux::Kernel::get_metadata(), monitoring::linux::Kernel::new_monitoring_module),
@@ -262,11 +460,19 @@ impl ModuleFactory {
             (monitoring::linux::Uptime::get_metadata(), monitoring::linux::Uptime::new_monitoring_module),
             (monitoring::linux::Load::get_metadata(), monitoring::linux::Load::new_monitoring_module),
             (monitoring::linux::Ram::get_metadata(), monitoring::linux::Ram::new_monitoring_module),
+            (monitoring::linux::Cpu::get_metadata(), monitoring::linux::Cpu::new_monitoring_module),
             (monitoring::linux::Who::get_metadata(), monitoring::linux::Who::new_monitoring_module),
             (monitoring::storage::Filesystem::get_metadata(), monitoring::storage::Filesystem::new_monitoring_module),
+            (monitoring::storage::BlockDevices::get_metadata(), monitoring::storage::BlockDevices::new_monitoring_module),
+            (monitoring::storage::DiskIo::get_metadata(), monitoring::storage::DiskIo::new_monitoring_module),
+            (monitoring::storage::Smart::get_metadata(), monitoring::storage::Smart::new_monitoring_module),
+            (monitoring::storage::MdRaid::get_metadata(), monitoring::storage::MdRaid::new_monitoring_module),
             (monitoring::storage::lvm::LogicalVolume::get_metadata(), monitoring::storage::lvm::LogicalVolume::new_monitoring_module),
             (monitoring::storage::lvm::VolumeGroup::get_metadata(), monitoring::storage::lvm::VolumeGroup::new_monitoring_module),
             (monitoring::storage::lvm::PhysicalVolume::get_metadata(), monitoring::storage::lvm::PhysicalVolume::new_monitoring_module),
+            (monitoring::storage::zfs::Pool::get_metadata(), monitoring::storage::zfs::Pool::new_monitoring_module),
+            (monitoring::storage::zfs::Dataset::get_metadata(), monitoring::storage::zfs::Dataset::new_monitoring_module),
+            (monitoring::storage::Btrfs::get_metadata(), monitoring::storage::Btrfs::new_monitoring_module),
             (monitoring::systemd::Service::get_metadata(), monitoring::systemd::Service::new_monitoring_module),
             (monitoring::network::Oping::get_metadata(), monitoring::network::Oping::new_monitoring_module),
             (monitoring::network::Ping::get_metadata(), monitoring::network::Ping::new_monitoring_module),
@@ -277,14 +483,21 @@ impl ModuleFactory {
             (monitoring::docker::Compose::get_metadata(), monitoring::docker::Compose::new_monitoring_module),
             (monitoring::docker::Containers::get_metadata(), monitoring::docker::Containers::new_monitoring_module),
             (monitoring::docker::Images::get_metadata(), monitoring::docker::Images::new_monitoring_module),
+            (monitoring::script::Script::get_metadata(), monitoring::script::Script::new_monitoring_module),
+            (monitoring::nagios_plugin::NagiosPlugin::get_metadata(), monitoring::nagios_plugin::NagiosPlugin::new_monitoring_module),
 
             // Monitoring extension modules.
             (monitoring::docker::ImageUpdates::get_metadata(), monitoring::docker::ImageUpdates::new_monitoring_module),
         ];
 
+        self.monitor_modules = monitor_modules.into_iter()
+                                              .map(|(metadata, constructor)| (metadata, Arc::new(constructor) as MonitorConstructor))
+                                              .collect();
+
         // Command modules.
-        self.command_modules = vec![
+        let command_modules: Vec<(Metadata, fn(&HashMap<String, String>) -> command::Command)> = vec![
             (command::linux::Logs::get_metadata(), command::linux::Logs::new_command_module),
+            (command::linux::AgentDeploy::get_metadata(), command::linux::AgentDeploy::new_command_module),
             (command::os::Reboot::get_metadata(), command::os::Reboot::new_command_module),
             (command::os::Shutdown::get_metadata(), command::os::Shutdown::new_command_module),
             (command::linux::Shell::get_metadata(), command::linux::Shell::new_command_module),
@@ -296,6 +509,20 @@ impl ModuleFactory {
             (command::storage::lvm::Snapshot::get_metadata(), command::storage::lvm::Snapshot::new_command_module),
             (command::storage::lvm::LVResize::get_metadata(), command::storage::lvm::LVResize::new_command_module),
             (command::storage::lvm::LVRemove::get_metadata(), command::storage::lvm::LVRemove::new_command_module),
+            (command::storage::lvm::LVExtend::get_metadata(), command::storage::lvm::LVExtend::new_command_module),
+            (command::storage::lvm::SnapshotMerge::get_metadata(), command::storage::lvm::SnapshotMerge::new_command_module),
+            (command::storage::lvm::VGExtend::get_metadata(), command::storage::lvm::VGExtend::new_command_module),
+            (command::storage::lvm::PVCreate::get_metadata(), command::storage::lvm::PVCreate::new_command_module),
+            (command::storage::smart::SelfTest::get_metadata(), command::storage::smart::SelfTest::new_command_module),
+            (command::storage::mdraid::Fail::get_metadata(), command::storage::mdraid::Fail::new_command_module),
+            (command::storage::mdraid::Remove::get_metadata(), command::storage::mdraid::Remove::new_command_module),
+            (command::storage::mdraid::Add::get_metadata(), command::storage::mdraid::Add::new_command_module),
+            (command::storage::zfs::Scrub::get_metadata(), command::storage::zfs::Scrub::new_command_module),
+            (command::storage::zfs::Snapshot::get_metadata(), command::storage::zfs::Snapshot::new_command_module),
+            (command::storage::zfs::ListSnapshots::get_metadata(), command::storage::zfs::ListSnapshots::new_command_module),
+            (command::storage::btrfs::ScrubStart::get_metadata(), command::storage::btrfs::ScrubStart::new_command_module),
+            (command::storage::btrfs::ScrubCancel::get_metadata(), command::storage::btrfs::ScrubCancel::new_command_module),
+            (command::storage::btrfs::Balance::get_metadata(), command::storage::btrfs::Balance::new_command_module),
             (command::docker::Restart::get_metadata(), command::docker::Restart::new_command_module),
             (command::docker::Inspect::get_metadata(), command::docker::Inspect::new_command_module),
             (command::docker::Shell::get_metadata(), command::docker::Shell::new_command_module),
@@ -315,8 +542,13 @@ impl ModuleFactory {
             (command::systemd::service::Mask::get_metadata(), command::systemd::service::Mask::new_command_module),
             (command::systemd::service::Unmask::get_metadata(), command::systemd::service::Unmask::new_command_module),
             (command::systemd::service::Logs::get_metadata(), command::systemd::service::Logs::new_command_module),
+            (command::script::Script::get_metadata(), command::script::Script::new_command_module),
         ];
 
+        self.command_modules = command_modules.into_iter()
+                                              .map(|(metadata, constructor)| (metadata, Arc::new(constructor) as CommandConstructor))
+                                              .collect();
+
         self.validate_modules();
         self.connector_modules.iter().map(|(metadata, _)| metadata).for_each(|metadata| log::debug!("Loaded connector module: {}", metadata.module_spec.id));
         self.monitor_modules.iter().map(|(metadata, _)| metadata).for_each(|metadata| log::debug!("Loaded monitoring module: {}", metadata.module_spec.id));
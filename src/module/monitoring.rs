@@ -7,6 +7,10 @@ pub use monitoring_module::BoxCloneableMonitor;
 pub mod data_point;
 pub use data_point::DataPoint;
 
+pub mod agent;
+
+pub mod sampling;
+
 pub mod linux;
 
 pub mod network;
@@ -19,4 +23,8 @@ pub mod systemd;
 
 pub mod docker;
 
-pub mod storage;
\ No newline at end of file
+pub mod storage;
+
+pub mod script;
+
+pub mod nagios_plugin;
\ No newline at end of file
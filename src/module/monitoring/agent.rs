@@ -0,0 +1,42 @@
+//! Helpers for monitors that can use lightkeeper-agent output instead of shell commands.
+//! Agent is installed with the `linux-agent-deploy` command and its availability is detected along with platform info.
+//! All monitors send the same command, so the agent is executed only once per refresh thanks to batching and the response cache.
+
+use crate::Host;
+use crate::module::connection::ResponseMessage;
+pub use lightkeeper_agent::Report;
+
+
+pub fn is_available(host: &Host) -> bool {
+    host.platform.has_agent()
+}
+
+/// Where `linux-agent-deploy` installs the agent, relative to the home directory of the SSH user.
+pub const INSTALL_PATH: &str = ".local/bin/lightkeeper-agent";
+
+/// Agent is run by its install path since `~/.local/bin` usually isn't in `PATH` of non-interactive sessions.
+pub fn get_command() -> String {
+    format!("\"$HOME/{}\"", INSTALL_PATH)
+}
+
+pub fn parse_report(response: &ResponseMessage) -> Result<Report, String> {
+    if response.is_error() {
+        return Err(format!("Agent failed with exit code {}", response.return_code));
+    }
+
+    let report: Report = serde_json::from_str(&response.message).map_err(|error| format!("Invalid agent report: {}", error))?;
+    if report.report_version != lightkeeper_agent::REPORT_VERSION {
+        return Err(format!("Unsupported agent report version {}", report.report_version));
+    }
+    Ok(report)
+}
+
+/// Error for metrics missing from the report. Includes the errors reported by the agent.
+pub fn missing_metric_error(report: &Report, metric: &str) -> String {
+    if report.errors.is_empty() {
+        format!("Agent didn't report {}", metric)
+    }
+    else {
+        format!("Agent didn't report {}: {}", metric, report.errors.join(", "))
+    }
+}
@@ -3,8 +3,10 @@ use chrono::{DateTime, Utc};
 
 use serde_derive::{Serialize, Deserialize};
 use crate::enums::Criticality;
+use crate::module::connection::ResponseMessage;
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(default)]
 pub struct DataPoint {
     /// With multivalue, value can be a composite result/value of all of the values.
     /// For example, with service statuses, this can show the worst state in the multivalue group.
@@ -26,6 +28,8 @@ pub struct DataPoint {
     /// Unique invocation ID. Used by UI as an identifier for asynchronously executed requests and received results.
     pub invocation_id: u64,
     pub is_from_cache: bool,
+    /// Seconds since the oldest response this is based on was cached.
+    pub cache_age: Option<u64>,
 }
 
 impl DataPoint {
@@ -94,6 +98,15 @@ impl DataPoint {
         }
     }
 
+    /// Data point is considered cached only if all responses are from cache.
+    pub fn set_cache_state(&mut self, responses: &[ResponseMessage]) {
+        self.is_from_cache = !responses.is_empty() && responses.iter().all(|response| response.is_from_cache);
+        self.cache_age = match self.is_from_cache {
+            true => responses.iter().filter_map(|response| response.cache_age).max(),
+            false => None,
+        };
+    }
+
     pub fn is_internal(&self) -> bool {
         self.value.starts_with("_")
     }
@@ -112,6 +125,7 @@ impl Default for DataPoint {
             time: Utc::now(),
             invocation_id: 0,
             is_from_cache: false,
+            cache_age: None,
         }
     }
 }
@@ -100,6 +100,10 @@ impl MonitoringModule for ImageUpdates {
             let response = responses.get(index).unwrap();
 
             new_point.is_from_cache = response.is_from_cache && old_point.is_from_cache;
+            new_point.cache_age = match new_point.is_from_cache {
+                true => response.cache_age.max(old_point.cache_age),
+                false => None,
+            };
 
             if response.is_error() {
                 new_point = DataPoint::empty_and_critical();
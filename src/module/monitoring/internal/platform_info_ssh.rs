@@ -7,6 +7,7 @@ use crate::utils::{VersionNumber, string_manipulation};
 use lightkeeper_module::monitoring_module;
 use crate::module::*;
 use crate::module::monitoring::*;
+use crate::module::monitoring::agent;
 
 #[monitoring_module(
     name="_internal-platform-info-ssh",
@@ -32,6 +33,7 @@ impl MonitoringModule for PlatformInfoSsh {
         Ok(vec![
             String::from("cat /etc/os-release"),
             String::from("uname -m"),
+            format!("{} {}", agent::get_command(), lightkeeper_agent::REPORT_VERSION_FLAG),
         ])
     }
 
@@ -48,6 +50,13 @@ impl MonitoringModule for PlatformInfoSsh {
         datapoint.multivalue.push(DataPoint::labeled_value(String::from("os_version"), platform.os_version.to_string()));
         datapoint.multivalue.push(DataPoint::labeled_value(String::from("os_flavor"), platform.os_flavor.to_string()));
         datapoint.multivalue.push(DataPoint::labeled_value(String::from("architecture"), platform.architecture.to_string()));
+
+        // Agent is optional, so the command fails on most hosts.
+        if let Some(agent_response) = response.get(2).filter(|response| response.is_success()) {
+            if let Ok(report_version) = agent_response.message.trim().parse::<u32>() {
+                datapoint.multivalue.push(DataPoint::labeled_value(String::from("agent_report_version"), report_version.to_string()));
+            }
+        }
         Ok(datapoint)
     }
 }
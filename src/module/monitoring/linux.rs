@@ -17,4 +17,7 @@ pub mod load;
 pub use load::Load;
 
 pub mod ram;
-pub use ram::Ram;
\ No newline at end of file
+pub use ram::Ram;
+
+pub mod cpu;
+pub use cpu::Cpu;
\ No newline at end of file
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use crate::enums::Criticality;
+use crate::module::connection::ResponseMessage;
+use crate::module::platform_info;
+use crate::{
+    Host,
+    frontend,
+};
+use lightkeeper_module::monitoring_module;
+use crate::module::*;
+use crate::module::monitoring::*;
+use crate::module::monitoring::sampling::{Sample, SampleHistory};
+
+const TOTAL_LABEL: &str = "Total";
+
+#[monitoring_module(
+    name="cpu",
+    version="0.0.1",
+    description="Shows CPU utilisation per core using /proc/stat. Usage is calculated from the difference to the previous refresh.",
+    settings={
+        warning_threshold => "Warning threshold for total usage in percent. Default: 80",
+        critical_threshold => "Critical threshold for total usage in percent. Default: 95",
+        iowait_warning_threshold => "Warning threshold for iowait in percent. Default: 20",
+        iowait_critical_threshold => "Critical threshold for iowait in percent. Default: 50",
+        steal_warning_threshold => "Warning threshold for steal in percent. Default: 10",
+        steal_critical_threshold => "Critical threshold for steal in percent. Default: 30",
+    }
+)]
+pub struct Cpu {
+    threshold_warning: f64,
+    threshold_critical: f64,
+    iowait_threshold_warning: f64,
+    iowait_threshold_critical: f64,
+    steal_threshold_warning: f64,
+    steal_threshold_critical: f64,
+    samples: SampleHistory<CpuSample>,
+}
+
+impl Module for Cpu {
+    fn new(settings: &HashMap<String, String>) -> Self {
+        Cpu {
+            threshold_warning: settings.get("warning_threshold").unwrap_or(&String::from("80")).parse().unwrap(),
+            threshold_critical: settings.get("critical_threshold").unwrap_or(&String::from("95")).parse().unwrap(),
+            iowait_threshold_warning: settings.get("iowait_warning_threshold").unwrap_or(&String::from("20")).parse().unwrap(),
+            iowait_threshold_critical: settings.get("iowait_critical_threshold").unwrap_or(&String::from("50")).parse().unwrap(),
+            steal_threshold_warning: settings.get("steal_warning_threshold").unwrap_or(&String::from("10")).parse().unwrap(),
+            steal_threshold_critical: settings.get("steal_critical_threshold").unwrap_or(&String::from("30")).parse().unwrap(),
+            samples: SampleHistory::default(),
+        }
+    }
+}
+
+impl MonitoringModule for Cpu {
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            display_style: frontend::DisplayStyle::ProgressBar,
+            display_text: String::from("CPU usage"),
+            category: String::from("host"),
+            unit: String::from("%"),
+            use_multivalue: true,
+            ..Default::default()
+        }
+    }
+
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new("ssh", "0.0.1"))
+    }
+
+    fn get_connector_message(&self, host: Host, _parent_result: DataPoint) -> Result<String, String> {
+        if host.platform.os != platform_info::OperatingSystem::Linux {
+            return Err(String::from("Unsupported platform"));
+        }
+
+        Ok(self.samples.get_command(&host.name, "grep '^cpu' /proc/stat"))
+    }
+
+    fn process_response(&self, host: Host, response: ResponseMessage, _parent_result: DataPoint) -> Result<DataPoint, String> {
+        if response.is_error() {
+            return Err(response.message);
+        }
+
+        let samples = parse_samples(&response.message)?;
+        let (current, previous) = self.samples.update(&host.name, samples, response.is_from_cache)?;
+
+        let mut result = DataPoint::empty();
+        for (label, times) in current.cpus.iter() {
+            let delta = match previous.as_ref().and_then(|previous| previous.get(label)) {
+                Some(previous_times) => times.difference(previous_times),
+                // Averages since boot.
+                None => times.clone(),
+            };
+
+            let usage = CpuUsage::from(&delta);
+            let mut data_point = DataPoint::labeled_value(label.clone(), format!("{:.0}", usage.total));
+            // A single busy core is normal, so thresholds only apply to the total.
+            if label == TOTAL_LABEL {
+                data_point.criticality = self.get_criticality(&usage);
+            }
+            data_point.description = format!("user {:.0} % | system {:.0} % | iowait {:.1} % | steal {:.1} %",
+                                             usage.user, usage.system, usage.iowait, usage.steal);
+            if previous.is_none() {
+                data_point.description.push_str(" | average since boot");
+            }
+            result.multivalue.push(data_point);
+        }
+
+        result.criticality = result.multivalue.iter().map(|data_point| data_point.criticality).max().unwrap_or(Criticality::Normal);
+        Ok(result)
+    }
+}
+
+impl Cpu {
+    fn get_criticality(&self, usage: &CpuUsage) -> Criticality {
+        if usage.total >= self.threshold_critical ||
+           usage.iowait >= self.iowait_threshold_critical ||
+           usage.steal >= self.steal_threshold_critical {
+            Criticality::Critical
+        }
+        else if usage.total >= self.threshold_warning ||
+                usage.iowait >= self.iowait_threshold_warning ||
+                usage.steal >= self.steal_threshold_warning {
+            Criticality::Warning
+        }
+        else {
+            Criticality::Normal
+        }
+    }
+}
+
+/// Returns one or more samples in the order they were taken.
+fn parse_samples(output: &str) -> Result<Vec<CpuSample>, String> {
+    let mut samples = Vec::<CpuSample>::new();
+
+    for line in output.lines() {
+        let mut parts = line.split_whitespace();
+        let name = match parts.next() {
+            Some(name) if name.starts_with("cpu") => name,
+            _ => continue,
+        };
+
+        // Aggregate line starts a new sample.
+        let label = if name == "cpu" {
+            samples.push(CpuSample::default());
+            TOTAL_LABEL.to_string()
+        }
+        else {
+            name.to_string()
+        };
+
+        let values = parts.map(|value| value.parse::<u64>().map_err(|error| format!("Invalid /proc/stat line: {}", error)))
+                          .collect::<Result<Vec<_>, _>>()?;
+        let times = CpuTimes::from_values(&values)?;
+
+        match samples.last_mut() {
+            Some(sample) => sample.cpus.push((label, times)),
+            None => return Err(String::from("Invalid /proc/stat output")),
+        }
+    }
+
+    if samples.is_empty() {
+        return Err(String::from("No CPU statistics found"));
+    }
+    Ok(samples)
+}
+
+#[derive(Clone, Default)]
+struct CpuSample {
+    /// Aggregate first, then cores.
+    cpus: Vec<(String, CpuTimes)>,
+}
+
+impl CpuSample {
+    fn get(&self, label: &str) -> Option<&CpuTimes> {
+        self.cpus.iter().find(|(cpu_label, _)| cpu_label == label).map(|(_, times)| times)
+    }
+
+}
+
+impl Sample for CpuSample {
+    fn timestamp(&self) -> f64 {
+        self.get(TOTAL_LABEL).map(CpuTimes::total).unwrap_or_default() as f64
+    }
+}
+
+/// Time spent in each state in clock ticks.
+#[derive(Clone, Default)]
+struct CpuTimes {
+    user: u64,
+    nice: u64,
+    system: u64,
+    idle: u64,
+    iowait: u64,
+    irq: u64,
+    softirq: u64,
+    steal: u64,
+}
+
+impl CpuTimes {
+    fn from_values(values: &[u64]) -> Result<Self, String> {
+        if values.len() < 4 {
+            return Err(String::from("Invalid /proc/stat line"));
+        }
+
+        // Older kernels have less columns. Guest time is already included in user time.
+        let value = |index: usize| values.get(index).copied().unwrap_or_default();
+        Ok(CpuTimes {
+            user: value(0),
+            nice: value(1),
+            system: value(2),
+            idle: value(3),
+            iowait: value(4),
+            irq: value(5),
+            softirq: value(6),
+            steal: value(7),
+        })
+    }
+
+    fn total(&self) -> u64 {
+        self.user + self.nice + self.system + self.idle + self.iowait + self.irq + self.softirq + self.steal
+    }
+
+    /// Counters can go backwards, e.g. iowait, so saturating subtraction is used.
+    fn difference(&self, previous: &CpuTimes) -> CpuTimes {
+        CpuTimes {
+            user: self.user.saturating_sub(previous.user),
+            nice: self.nice.saturating_sub(previous.nice),
+            system: self.system.saturating_sub(previous.system),
+            idle: self.idle.saturating_sub(previous.idle),
+            iowait: self.iowait.saturating_sub(previous.iowait),
+            irq: self.irq.saturating_sub(previous.irq),
+            softirq: self.softirq.saturating_sub(previous.softirq),
+            steal: self.steal.saturating_sub(previous.steal),
+        }
+    }
+}
+
+/// Percentages of total time.
+struct CpuUsage {
+    /// Everything except idle and iowait.
+    total: f64,
+    user: f64,
+    system: f64,
+    iowait: f64,
+    steal: f64,
+}
+
+impl From<&CpuTimes> for CpuUsage {
+    fn from(times: &CpuTimes) -> Self {
+        let total_time = times.total() as f64;
+        // No time has passed, e.g. for an offline core.
+        let percent = |value: u64| if total_time > 0.0 { value as f64 / total_time * 100.0 } else { 0.0 };
+
+        CpuUsage {
+            total: if total_time > 0.0 { 100.0 - percent(times.idle) - percent(times.iowait) } else { 0.0 },
+            user: percent(times.user + times.nice),
+            system: percent(times.system + times.irq + times.softirq),
+            iowait: percent(times.iowait),
+            steal: percent(times.steal),
+        }
+    }
+}
@@ -39,7 +39,10 @@ impl MonitoringModule for Kernel {
     }
 
     fn get_connector_message(&self, host: Host, _result: DataPoint) -> Result<String, String> {
-        if host.platform.os == platform_info::OperatingSystem::Linux {
+        if agent::is_available(&host) {
+            Ok(agent::get_command())
+        }
+        else if host.platform.os == platform_info::OperatingSystem::Linux {
             Ok(String::from("uname -r -m"))
         }
         else {
@@ -47,7 +50,12 @@ impl MonitoringModule for Kernel {
         }
     }
 
-    fn process_response(&self, _host: Host, response: ResponseMessage, _result: DataPoint) -> Result<DataPoint, String> {
+    fn process_response(&self, host: Host, response: ResponseMessage, _result: DataPoint) -> Result<DataPoint, String> {
+        if agent::is_available(&host) {
+            let report = agent::parse_report(&response)?;
+            return Ok(DataPoint::new(format!("{} ({})", report.facts.kernel, report.facts.architecture)));
+        }
+
         Ok(DataPoint::new(response.message.replace(" ", " (") + ")"))
     }
 }
\ No newline at end of file
@@ -38,7 +38,10 @@ impl MonitoringModule for Load {
     }
 
     fn get_connector_message(&self, host: Host, _parent_result: DataPoint) -> Result<String, String> {
-        if host.platform.os == platform_info::OperatingSystem::Linux {
+        if agent::is_available(&host) {
+            Ok(agent::get_command())
+        }
+        else if host.platform.os == platform_info::OperatingSystem::Linux {
             Ok(String::from("uptime"))
         }
         else {
@@ -46,7 +49,13 @@ impl MonitoringModule for Load {
         }
     }
 
-    fn process_response(&self, _host: Host, response: ResponseMessage, _parent_result: DataPoint) -> Result<DataPoint, String> {
+    fn process_response(&self, host: Host, response: ResponseMessage, _parent_result: DataPoint) -> Result<DataPoint, String> {
+        if agent::is_available(&host) {
+            let report = agent::parse_report(&response)?;
+            let load = report.load_average.as_ref().ok_or_else(|| agent::missing_metric_error(&report, "load average"))?;
+            return Ok(DataPoint::new(format!("{:.2}, {:.2}, {:.2}", load.one, load.five, load.fifteen)));
+        }
+
         let parts = response.message.split("load average: ").collect::<Vec<&str>>();
         Ok(DataPoint::new(parts[1].to_string()))
     }
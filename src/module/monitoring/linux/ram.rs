@@ -38,7 +38,10 @@ impl MonitoringModule for Ram {
     }
 
     fn get_connector_message(&self, host: Host, _parent_result: DataPoint) -> Result<String, String> {
-        if host.platform.version_is_same_or_greater_than(platform_info::Flavor::Debian, "10") ||
+        if agent::is_available(&host) {
+            Ok(agent::get_command())
+        }
+        else if host.platform.version_is_same_or_greater_than(platform_info::Flavor::Debian, "10") ||
            host.platform.version_is_same_or_greater_than(platform_info::Flavor::Ubuntu, "20") ||
            host.platform.version_is_same_or_greater_than(platform_info::Flavor::CentOS, "7") {
             Ok(String::from("free -m"))
@@ -49,6 +52,15 @@ impl MonitoringModule for Ram {
     }
 
     fn process_response(&self, host: Host, response: ResponseMessage, _parent_result: DataPoint) -> Result<DataPoint, String> {
+        if agent::is_available(&host) {
+            let report = agent::parse_report(&response)?;
+            let memory = report.memory.as_ref().ok_or_else(|| agent::missing_metric_error(&report, "memory"))?;
+            // Same units as with `free -m`.
+            let total = memory.total_kb / 1024;
+            let available = memory.available_kb / 1024;
+            return Ok(format_usage(total, available));
+        }
+
         if host.platform.version_is_same_or_greater_than(platform_info::Flavor::Debian, "10") ||
            host.platform.version_is_same_or_greater_than(platform_info::Flavor::Ubuntu, "20") ||
            host.platform.version_is_same_or_greater_than(platform_info::Flavor::CentOS, "7") {
@@ -63,12 +75,15 @@ impl MonitoringModule for Ram {
             // cache
             let available = parts[6].parse::<u64>().unwrap();
 
-            let usage_percent = 1.0 - (available as f64 / total as f64);
-            let value = format!("{} / {} M  ({:.0} %)", total - available, total, usage_percent * 100.0);
-            Ok(DataPoint::new(value))
+            Ok(format_usage(total, available))
         }
         else {
             Err(String::from("Unsupported platform"))
         }
     }
-}
\ No newline at end of file
+}
+
+fn format_usage(total: u64, available: u64) -> DataPoint {
+    let usage_percent = 1.0 - (available as f64 / total as f64);
+    DataPoint::new(format!("{} / {} M  ({:.0} %)", total - available, total, usage_percent * 100.0))
+}
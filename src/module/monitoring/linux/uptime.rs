@@ -40,7 +40,10 @@ impl MonitoringModule for Uptime {
     }
 
     fn get_connector_message(&self, host: Host, _parent_result: DataPoint) -> Result<String, String> {
-        if host.platform.os == platform_info::OperatingSystem::Linux {
+        if agent::is_available(&host) {
+            Ok(agent::get_command())
+        }
+        else if host.platform.os == platform_info::OperatingSystem::Linux {
             Ok(String::from("uptime -s"))
         }
         else {
@@ -48,7 +51,13 @@ impl MonitoringModule for Uptime {
         }
     }
 
-    fn process_response(&self, _host: Host, response: ResponseMessage, _parent_result: DataPoint) -> Result<DataPoint, String> {
+    fn process_response(&self, host: Host, response: ResponseMessage, _parent_result: DataPoint) -> Result<DataPoint, String> {
+        if agent::is_available(&host) {
+            let report = agent::parse_report(&response)?;
+            let uptime_seconds = report.uptime_seconds.ok_or_else(|| agent::missing_metric_error(&report, "uptime"))?;
+            return Ok(DataPoint::new((uptime_seconds / 86400).to_string()));
+        }
+
         let boot_datetime = NaiveDateTime::parse_from_str(&response.message, "%Y-%m-%d %H:%M:%S")
                                         .map_err(|e| e.to_string())?;
 
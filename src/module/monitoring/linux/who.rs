@@ -40,7 +40,10 @@ impl MonitoringModule for Who {
     }
 
     fn get_connector_message(&self, host: Host, _parent_result: DataPoint) -> Result<String, String> {
-        if host.platform.os == platform_info::OperatingSystem::Linux {
+        if agent::is_available(&host) {
+            Ok(agent::get_command())
+        }
+        else if host.platform.os == platform_info::OperatingSystem::Linux {
             Ok(String::from("who -s"))
         }
         else {
@@ -48,23 +51,36 @@ impl MonitoringModule for Who {
         }
     }
 
-    fn process_response(&self, _host: Host, response: ResponseMessage, _parent_result: DataPoint) -> Result<DataPoint, String> {
+    fn process_response(&self, host: Host, response: ResponseMessage, _parent_result: DataPoint) -> Result<DataPoint, String> {
         let mut result = DataPoint::empty();
 
-        let lines = response.message.lines().filter(|line| !line.is_empty());
-        for line in lines {
-            let mut parts = line.split_whitespace();
-            let user = parts.next().unwrap().to_string();
-            let _pts = parts.next().unwrap().to_string();
-            // TODO: format according to locales.
-            let login_date = parts.next().unwrap().to_string();
-            let login_time = parts.next().unwrap().to_string();
-            // Removes parentheses.
-            let ip_address = string_manipulation::get_string_between(&parts.next().unwrap(), "(", ")");
-            
-            let value_text = format!("{} {} (from {})", login_date, login_time, ip_address);
-            let data_point = DataPoint::labeled_value(user, value_text);
-            result.multivalue.push(data_point);
+        if agent::is_available(&host) {
+            let report = agent::parse_report(&response)?;
+            for session in report.users.iter() {
+                let login_time = chrono::DateTime::from_timestamp(session.login_time, 0)
+                                                  .map(|datetime| datetime.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+                                                  .unwrap_or_default();
+                let source = if session.host.is_empty() { &session.terminal } else { &session.host };
+                let value_text = format!("{} (from {})", login_time, source);
+                result.multivalue.push(DataPoint::labeled_value(session.user.clone(), value_text));
+            }
+        }
+        else {
+            let lines = response.message.lines().filter(|line| !line.is_empty());
+            for line in lines {
+                let mut parts = line.split_whitespace();
+                let user = parts.next().unwrap().to_string();
+                let _pts = parts.next().unwrap().to_string();
+                // TODO: format according to locales.
+                let login_date = parts.next().unwrap().to_string();
+                let login_time = parts.next().unwrap().to_string();
+                // Removes parentheses.
+                let ip_address = string_manipulation::get_string_between(&parts.next().unwrap(), "(", ")");
+
+                let value_text = format!("{} {} (from {})", login_date, login_time, ip_address);
+                let data_point = DataPoint::labeled_value(user, value_text);
+                result.multivalue.push(data_point);
+            }
         }
 
         if result.multivalue.is_empty() {
@@ -43,7 +43,7 @@ pub trait MonitoringModule : BoxCloneableMonitor + MetadataSupport + Module {
         Err(String::new())
     }
 
-    /// Note that if implementing this method, you will need to set is_from_cache yourself.
+    /// Note that if implementing this method, you will need to set the cache state yourself with `DataPoint::set_cache_state`.
     fn process_responses(&self, _host: Host, _responses: Vec<ResponseMessage>, _parent_result: DataPoint) -> Result<DataPoint, String> {
         Err(String::new())
     }
@@ -54,6 +54,9 @@ pub trait BoxCloneableMonitor {
     fn box_clone(&self) -> Monitor;
 }
 
+/// How many of the latest data points are kept for each monitor.
+pub const DATA_POINT_BUFFER_SIZE: usize = 4;
+
 #[derive(Default, Clone, Serialize, Deserialize)]
 pub struct MonitoringData {
     pub monitor_id: String,
@@ -71,4 +74,13 @@ impl MonitoringData {
             is_critical: false,
         }
     }
+
+    /// Adds a new data point and drops the oldest ones if the buffer is full.
+    pub fn push_value(&mut self, data_point: DataPoint) {
+        self.values.push_back(data_point);
+
+        while self.values.len() > DATA_POINT_BUFFER_SIZE {
+            self.values.pop_front();
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,330 @@
+use std::collections::HashMap;
+
+use crate::cache::CacheSensitivity;
+use crate::enums::Criticality;
+use crate::module::connection::ResponseMessage;
+use crate::host::HostSetting;
+use crate::utils::ShellCommand;
+use crate::{
+    Host,
+    frontend,
+};
+use crate::module::*;
+use crate::module::monitoring::*;
+
+const MODULE_NAME: &str = "nagios-plugin";
+const MODULE_VERSION: &str = "0.0.1";
+
+
+/// Runs Nagios / Monitoring Plugins compatible checks. Configured with `module: nagios-plugin` so every configured monitor
+/// is a separate instance. See https://www.monitoring-plugins.org/doc/guidelines.html for the output format.
+/// MetadataSupport is implemented manually for the same reason as with the script-module.
+#[derive(Clone)]
+pub struct NagiosPlugin {
+    instance_id: String,
+    command: String,
+    connector: String,
+    use_sudo: bool,
+    cache_sensitivity: CacheSensitivity,
+    display_text: String,
+    category: String,
+    ignore_from_summary: bool,
+}
+
+impl Module for NagiosPlugin {
+    fn new(settings: &HashMap<String, String>) -> Self {
+        let get_setting = |key: &str, default: &str| settings.get(key).cloned().unwrap_or_else(|| default.to_string());
+        let instance_id = get_setting(INSTANCE_ID_SETTING, "");
+
+        NagiosPlugin {
+            command: get_setting("command", ""),
+            connector: get_setting("connector", "ssh"),
+            use_sudo: get_setting("use_sudo", "false") == "true",
+            cache_sensitivity: CacheSensitivity::from_settings(CacheSensitivity::Normal, settings),
+            display_text: get_setting("display_text", if instance_id.is_empty() { "Nagios plugin" } else { &instance_id }),
+            category: get_setting("category", "nagios"),
+            ignore_from_summary: get_setting("ignore_from_summary", "false") == "true",
+            instance_id: instance_id,
+        }
+    }
+}
+
+impl MetadataSupport for NagiosPlugin {
+    fn get_metadata() -> Metadata {
+        Metadata {
+            module_spec: ModuleSpecification::new_with_type(MODULE_NAME, MODULE_VERSION, "monitor"),
+            description: String::from("Runs a Nagios compatible check plugin. Set `module: nagios-plugin` in monitor configuration to use."),
+            settings: HashMap::from([
+                (String::from("command"), String::from("Plugin with arguments, e.g. \"/usr/lib/nagios/plugins/check_load -w 5,4,3 -c 10,8,6\". Not interpolated, so ${VAR} is expanded by the shell on the host.")),
+                (String::from("connector"), String::from("Connector to use: ssh or local-command. Default: ssh")),
+                (String::from("use_sudo"), String::from("Run plugin with sudo if host has use_sudo enabled. Default: false")),
+                (String::from(CacheSensitivity::SETTING), format!("{} Default: false", CacheSensitivity::SETTING_DESCRIPTION)),
+                (String::from("display_text"), String::from("Label for the status line. Default: monitor ID")),
+                (String::from("category"), String::from("Category to display the monitor in. Default: nagios")),
+                (String::from("ignore_from_summary"), String::from("Don't show in the host summary. Default: false")),
+            ]),
+            parent_module: None,
+            is_stateless: true,
+            cache_scope: crate::cache::CacheScope::Host,
+            cache_sensitivity: crate::cache::CacheSensitivity::Normal,
+        }
+    }
+
+    fn get_metadata_self(&self) -> Metadata {
+        Metadata {
+            module_spec: self.get_module_spec(),
+            cache_sensitivity: self.cache_sensitivity,
+            ..Self::get_metadata()
+        }
+    }
+
+    fn get_module_spec(&self) -> ModuleSpecification {
+        let id = if self.instance_id.is_empty() { MODULE_NAME } else { &self.instance_id };
+        ModuleSpecification::new_with_type(id, MODULE_VERSION, "monitor")
+    }
+}
+
+impl BoxCloneableMonitor for NagiosPlugin {
+    fn box_clone(&self) -> Monitor {
+        Box::new(self.clone())
+    }
+}
+
+impl MonitoringModule for NagiosPlugin {
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            display_style: frontend::DisplayStyle::CriticalityLevel,
+            display_text: self.display_text.clone(),
+            category: self.category.clone(),
+            use_multivalue: true,
+            ignore_from_summary: self.ignore_from_summary,
+            ..Default::default()
+        }
+    }
+
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new(self.connector.as_str(), "0.0.1"))
+    }
+
+    fn get_connector_message(&self, host: Host, _parent_result: DataPoint) -> Result<String, String> {
+        if self.command.is_empty() {
+            return Err(String::from("Command is not set"));
+        }
+
+        if self.use_sudo && host.settings.contains(&HostSetting::UseSudo) {
+            let mut command = ShellCommand::new_from(vec!["sh", "-c", self.command.as_str()]);
+            command.use_sudo = true;
+            Ok(command.to_string())
+        }
+        else {
+            Ok(self.command.clone())
+        }
+    }
+
+    fn process_response(&self, _host: Host, response: ResponseMessage, _parent_result: DataPoint) -> Result<DataPoint, String> {
+        let criticality = match response.return_code {
+            0 => Criticality::Normal,
+            1 => Criticality::Warning,
+            2 => Criticality::Critical,
+            3 => Criticality::NoData,
+            // E.g. plugin not found or not executable.
+            _ => Criticality::Error,
+        };
+
+        let (status, long_text, perfdata) = split_output(&response.message);
+
+        let mut status_point = DataPoint::labeled_value_with_level(self.display_text.clone(), status.clone(), criticality);
+        status_point.description = long_text;
+
+        let mut result = DataPoint::value_with_level(status, criticality);
+        result.multivalue.push(status_point);
+
+        for metric in parse_perfdata(&perfdata) {
+            result.multivalue.push(metric.to_data_point());
+        }
+
+        Ok(result)
+    }
+}
+
+
+/// Returns the status line, the long text and all performance data.
+/// Performance data is found after "|" on the first line and after the first "|" in the long text, which can continue on following lines.
+fn split_output(output: &str) -> (String, String, String) {
+    let mut lines = output.trim().lines();
+    let first_line = lines.next().unwrap_or_default();
+    let (status, mut perfdata) = match first_line.split_once('|') {
+        Some((status, perfdata)) => (status.trim().to_string(), perfdata.trim().to_string()),
+        None => (first_line.trim().to_string(), String::new()),
+    };
+
+    let mut long_text = Vec::new();
+    let mut is_perfdata = false;
+
+    for line in lines {
+        if is_perfdata {
+            perfdata.push(' ');
+            perfdata.push_str(line.trim());
+        }
+        else if let Some((text, line_perfdata)) = line.split_once('|') {
+            is_perfdata = true;
+            long_text.push(text.trim_end());
+            perfdata.push(' ');
+            perfdata.push_str(line_perfdata.trim());
+        }
+        else {
+            long_text.push(line.trim_end());
+        }
+    }
+
+    (status, long_text.join("\n").trim().to_string(), perfdata.trim().to_string())
+}
+
+/// Single performance data metric in the format 'label'=value[UOM];[warn];[crit];[min];[max]
+struct PerfdataMetric {
+    label: String,
+    /// None if value is "U" (unknown).
+    value: Option<f64>,
+    unit: String,
+    warning: Option<ThresholdRange>,
+    critical: Option<ThresholdRange>,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+impl PerfdataMetric {
+    fn parse(token: &str) -> Option<Self> {
+        let (label, data) = token.rsplit_once('=')?;
+        let label = label.trim_matches('\'').replace("''", "'");
+        let mut fields = data.split(';');
+
+        let value_with_unit = fields.next().unwrap_or_default();
+        let unit_start = value_with_unit.find(|char: char| !(char.is_ascii_digit() || "-+.".contains(char)))
+                                        .unwrap_or(value_with_unit.len());
+        // "U" alone means the value could not be determined.
+        let (value, unit) = match value_with_unit {
+            "U" => (None, String::new()),
+            _ => (Some(value_with_unit[..unit_start].parse::<f64>().ok()?), value_with_unit[unit_start..].to_string()),
+        };
+
+        let mut next_field = || fields.next().filter(|field| !field.is_empty());
+
+        Some(PerfdataMetric {
+            label: label,
+            value: value,
+            unit: unit,
+            warning: next_field().and_then(ThresholdRange::parse),
+            critical: next_field().and_then(ThresholdRange::parse),
+            min: next_field().and_then(|min| min.parse().ok()),
+            max: next_field().and_then(|max| max.parse().ok()),
+        })
+    }
+
+    fn to_data_point(&self) -> DataPoint {
+        let criticality = match self.value {
+            None => Criticality::NoData,
+            Some(value) if self.critical.as_ref().map(|range| range.is_alert(value)).unwrap_or(false) => Criticality::Critical,
+            Some(value) if self.warning.as_ref().map(|range| range.is_alert(value)).unwrap_or(false) => Criticality::Warning,
+            Some(_) => Criticality::Normal,
+        };
+
+        let value = match (self.value, self.unit.as_str()) {
+            (None, _) => String::from("unknown"),
+            // Continuous counter, no unit to display.
+            (Some(value), "c") | (Some(value), "") => value.to_string(),
+            (Some(value), "%") => format!("{}%", value),
+            (Some(value), unit) => format!("{} {}", value, unit),
+        };
+
+        let mut data_point = DataPoint::labeled_value_with_level(self.label.clone(), value, criticality);
+        data_point.description = [
+            ("warning", self.warning.as_ref().map(|range| range.source.clone())),
+            ("critical", self.critical.as_ref().map(|range| range.source.clone())),
+            ("min", self.min.map(|min| min.to_string())),
+            ("max", self.max.map(|max| max.to_string())),
+        ].into_iter()
+         .filter_map(|(name, value)| value.map(|value| format!("{}: {}", name, value)))
+         .collect::<Vec<String>>()
+         .join(", ");
+        data_point.command_params = vec![self.label.clone()];
+        data_point
+    }
+}
+
+/// Threshold in Nagios range format: "10", "10:", "~:10", "10:20" or "@10:20".
+/// Alert is raised if value is outside the range, or inside it if the range starts with "@".
+struct ThresholdRange {
+    source: String,
+    start: f64,
+    end: f64,
+    inside: bool,
+}
+
+impl ThresholdRange {
+    fn parse(range: &str) -> Option<Self> {
+        let inside = range.starts_with('@');
+        let bounds = range.trim_start_matches('@');
+
+        let (start, end) = match bounds.split_once(':') {
+            Some((start, end)) => (start, end),
+            None => ("0", bounds),
+        };
+
+        let start = match start {
+            "~" => f64::NEG_INFINITY,
+            "" => 0.0,
+            _ => start.parse().ok()?,
+        };
+
+        let end = match end {
+            "" => f64::INFINITY,
+            _ => end.parse().ok()?,
+        };
+
+        Some(ThresholdRange {
+            source: range.to_string(),
+            start: start,
+            end: end,
+            inside: inside,
+        })
+    }
+
+    fn is_alert(&self, value: f64) -> bool {
+        let is_inside = value >= self.start && value <= self.end;
+        is_inside == self.inside
+    }
+}
+
+/// Labels can be quoted with single quotes, in which case they can contain spaces. Quotes inside labels are escaped as ''.
+fn parse_perfdata(perfdata: &str) -> Vec<PerfdataMetric> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for char in perfdata.chars() {
+        match char {
+            '\'' => {
+                in_quotes = !in_quotes;
+                current.push(char);
+            },
+            char if char.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            },
+            _ => current.push(char),
+        }
+    }
+
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    tokens.iter().filter_map(|token| {
+        let metric = PerfdataMetric::parse(token);
+        if metric.is_none() {
+            log::warn!("Invalid performance data: {}", token);
+        }
+        metric
+    }).collect()
+}
@@ -75,7 +75,7 @@ impl MonitoringModule for Dns {
 
                 let mut datapoint = DataPoint::label(dns_server);
                 datapoint.description = String::from("resolv.conf");
-                datapoint.is_from_cache = resolvconf_response.is_from_cache;
+                datapoint.set_cache_state(&[resolvconf_response.clone()]);
                 result.multivalue.push(datapoint);
             }
         }
@@ -91,7 +91,7 @@ impl MonitoringModule for Dns {
                     for dns_server in dns_servers {
                         let mut datapoint = DataPoint::label(dns_server);
                         datapoint.description = String::from("systemd-resolved");
-                        datapoint.is_from_cache = resolvectl_response.is_from_cache;
+                        datapoint.set_cache_state(&[resolvectl_response.clone()]);
                         result.multivalue.push(datapoint);
                     }
                 }
@@ -44,7 +44,10 @@ impl MonitoringModule for Routes {
     }
 
     fn get_connector_message(&self, host: Host, _result: DataPoint) -> Result<String, String> {
-        if host.platform.os == platform_info::OperatingSystem::Linux {
+        if agent::is_available(&host) {
+            Ok(agent::get_command())
+        }
+        else if host.platform.os == platform_info::OperatingSystem::Linux {
             Ok(String::from("ip route ls"))
         }
         else {
@@ -52,9 +55,22 @@ impl MonitoringModule for Routes {
         }
     }
 
-    fn process_response(&self, _host: Host, response: ResponseMessage, _result: DataPoint) -> Result<DataPoint, String> {
+    fn process_response(&self, host: Host, response: ResponseMessage, _result: DataPoint) -> Result<DataPoint, String> {
         let mut result = DataPoint::empty();
 
+        if agent::is_available(&host) {
+            let report = agent::parse_report(&response)?;
+            for route in report.routes.iter() {
+                // Formatted the same way as with `ip route ls`.
+                let subnet = match &route.gateway {
+                    Some(gateway) => format!("{} via {}", route.destination, gateway),
+                    None => route.destination.clone(),
+                };
+                result.multivalue.push(DataPoint::labeled_value(subnet, route.interface.clone()));
+            }
+            return Ok(result);
+        }
+
         let lines = response.message.lines();
         for line in lines {
             // Get substring before word "proto".
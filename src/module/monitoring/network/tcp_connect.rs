@@ -41,7 +41,7 @@ impl MonitoringModule for TcpConnect {
     }
 
     fn process_response(&self, host: Host, _response: ResponseMessage, _result: DataPoint) -> Result<DataPoint, String> {
-        let socket_addr: SocketAddr = format!("{}:{}", host.ip_address, self.port).parse().unwrap();
+        let socket_addr = SocketAddr::new(host.ip_address, self.port);
         let result = TcpStream::connect_timeout(&socket_addr, std::time::Duration::from_secs(self.timeout as u64));
 
         if let Err(error) = result {
@@ -0,0 +1,58 @@
+//! Helpers for monitors that calculate values from the difference between two samples of cumulative counters, e.g. CPU usage.
+//! The latest sample of each host is kept, so later refreshes only need to take one sample.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Used only on the first refresh when there's no previous sample to compare to.
+pub const SAMPLE_INTERVAL_SECONDS: u32 = 1;
+
+pub trait Sample: Clone {
+    /// Increases with each sample, e.g. uptime. Used for telling apart cached responses that are older than the previous sample.
+    fn timestamp(&self) -> f64;
+}
+
+/// Latest sample by host. Clones share the samples, so it can be stored in a module that gets cloned.
+#[derive(Clone)]
+pub struct SampleHistory<T: Sample> {
+    previous_samples: Arc<Mutex<HashMap<String, T>>>,
+}
+
+impl<T: Sample> Default for SampleHistory<T> {
+    fn default() -> Self {
+        SampleHistory {
+            previous_samples: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl<T: Sample> SampleHistory<T> {
+    /// Returns the command for taking samples. If there's no previous sample, two are taken with a short interval in between.
+    pub fn get_command(&self, host_name: &str, sample_command: &str) -> String {
+        if self.previous_samples.lock().unwrap().contains_key(host_name) {
+            sample_command.to_string()
+        }
+        else {
+            format!("{} && sleep {} && {}", sample_command, SAMPLE_INTERVAL_SECONDS, sample_command)
+        }
+    }
+
+    /// Takes the samples of a response in the order they were taken.
+    /// Returns the latest sample and the one it should be compared to. Latest sample is stored unless the response is from cache.
+    pub fn update(&self, host_name: &str, mut samples: Vec<T>, is_from_cache: bool) -> Result<(T, Option<T>), String> {
+        let current = samples.pop().ok_or_else(|| String::from("No samples found"))?;
+        let mut previous_samples = self.previous_samples.lock().unwrap();
+
+        let previous = match samples.pop() {
+            Some(sample) => Some(sample),
+            // Cached response can be older than the previous sample.
+            None => previous_samples.get(host_name).filter(|previous| previous.timestamp() < current.timestamp()).cloned(),
+        };
+
+        if !is_from_cache {
+            previous_samples.insert(host_name.to_string(), current.clone());
+        }
+
+        Ok((current, previous))
+    }
+}
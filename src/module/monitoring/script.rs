@@ -0,0 +1,334 @@
+use std::collections::HashMap;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::cache::CacheSensitivity;
+use crate::enums::Criticality;
+use crate::module::connection::ResponseMessage;
+use crate::host::HostSetting;
+use crate::utils::ShellCommand;
+use crate::{
+    Host,
+    frontend,
+};
+use crate::module::*;
+use crate::module::monitoring::*;
+
+const MODULE_NAME: &str = "script";
+const MODULE_VERSION: &str = "0.0.1";
+
+
+/// Generic monitor for user-defined commands. Configured with `module: script` so every configured monitor is a separate instance.
+/// MetadataSupport is implemented manually instead of with the monitoring_module-macro,
+/// since the module specification has to contain the instance ID instead of the module name.
+#[derive(Clone)]
+pub struct Script {
+    instance_id: String,
+    command: String,
+    connector: String,
+    use_sudo: bool,
+    cache_sensitivity: CacheSensitivity,
+    display_text: String,
+    display_style: frontend::DisplayStyle,
+    display_icon: String,
+    category: String,
+    unit: String,
+    ignore_from_summary: bool,
+    parse_mode: ParseMode,
+    pattern: Option<Regex>,
+    json_path: String,
+    /// Evaluated in order. First matching rule sets the criticality.
+    criticality_rules: Vec<(Criticality, CriticalityRule)>,
+    /// Invalid pattern and rule settings. Reported when the monitor is run.
+    setting_errors: Vec<String>,
+}
+
+#[derive(Clone, PartialEq)]
+enum ParseMode {
+    Raw,
+    Regex,
+    Json,
+    KeyValue,
+}
+
+impl Module for Script {
+    fn new(settings: &HashMap<String, String>) -> Self {
+        let get_setting = |key: &str, default: &str| settings.get(key).cloned().unwrap_or_else(|| default.to_string());
+        let instance_id = get_setting(INSTANCE_ID_SETTING, "");
+        let mut setting_errors = Vec::new();
+
+        let mut criticality_rules = Vec::new();
+        for (criticality, key) in [(Criticality::Critical, "critical_if"), (Criticality::Error, "error_if"), (Criticality::Warning, "warning_if")] {
+            if let Some(rule) = settings.get(key).filter(|rule| !rule.is_empty()) {
+                match CriticalityRule::parse(rule) {
+                    Ok(rule) => criticality_rules.push((criticality, rule)),
+                    Err(error) => setting_errors.push(error),
+                }
+            }
+        }
+
+        let pattern = match Regex::new(&get_setting("pattern", "")) {
+            Ok(regex) => Some(regex),
+            Err(error) => {
+                setting_errors.push(format!("Invalid pattern: {}", error));
+                None
+            }
+        };
+
+        Script {
+            command: get_setting("command", ""),
+            connector: get_setting("connector", "ssh"),
+            use_sudo: get_setting("use_sudo", "false") == "true",
+            cache_sensitivity: CacheSensitivity::from_settings(CacheSensitivity::Normal, settings),
+            display_text: get_setting("display_text", if instance_id.is_empty() { "Script" } else { &instance_id }),
+            display_style: match get_setting("display_style", "text").as_str() {
+                "criticality_level" => frontend::DisplayStyle::CriticalityLevel,
+                "icon" => frontend::DisplayStyle::Icon,
+                "progress_bar" => frontend::DisplayStyle::ProgressBar,
+                _ => frontend::DisplayStyle::Text,
+            },
+            display_icon: get_setting("display_icon", ""),
+            category: get_setting("category", "custom"),
+            unit: get_setting("unit", ""),
+            ignore_from_summary: get_setting("ignore_from_summary", "false") == "true",
+            parse_mode: match get_setting("parse_mode", "raw").as_str() {
+                "regex" => ParseMode::Regex,
+                "json" => ParseMode::Json,
+                "key_value" => ParseMode::KeyValue,
+                _ => ParseMode::Raw,
+            },
+            pattern: pattern,
+            json_path: get_setting("json_path", ""),
+            criticality_rules: criticality_rules,
+            setting_errors: setting_errors,
+            instance_id: instance_id,
+        }
+    }
+}
+
+impl MetadataSupport for Script {
+    fn get_metadata() -> Metadata {
+        Metadata {
+            module_spec: ModuleSpecification::new_with_type(MODULE_NAME, MODULE_VERSION, "monitor"),
+            description: String::from("Runs a user-defined command and parses its output. Set `module: script` in monitor configuration to use."),
+            settings: HashMap::from([
+                (String::from("command"), String::from("Command to run. Executed with a shell. Not interpolated, so ${VAR} is expanded by the shell on the host.")),
+                (String::from("connector"), String::from("Connector to use: ssh or local-command. Default: ssh")),
+                (String::from("use_sudo"), String::from("Run command with sudo if host has use_sudo enabled. Default: false")),
+                (String::from(CacheSensitivity::SETTING), format!("{} Default: false", CacheSensitivity::SETTING_DESCRIPTION)),
+                (String::from("display_text"), String::from("Label for the value. Default: monitor ID")),
+                (String::from("display_style"), String::from("text, criticality_level, icon or progress_bar. Default: text")),
+                (String::from("display_icon"), String::from("Icon name for icon display style.")),
+                (String::from("category"), String::from("Category to display the monitor in. Default: custom")),
+                (String::from("unit"), String::from("Unit to display after the value.")),
+                (String::from("ignore_from_summary"), String::from("Don't show in the host summary. Default: false")),
+                (String::from("parse_mode"), String::from("raw, regex, json or key_value (each key=value line becomes a separate value). Default: raw")),
+                (String::from("pattern"), String::from("Regular expression for regex parse mode. First capture group is used as the value, or the whole match if there are no groups.")),
+                (String::from("json_path"), String::from("Dot-separated path for json parse mode, e.g. status.disks.0.health")),
+                (String::from("critical_if"), String::from("Condition for critical level, e.g. \"> 90\", \"== down\" or \"=~ ^fail\"")),
+                (String::from("error_if"), String::from("Condition for error level.")),
+                (String::from("warning_if"), String::from("Condition for warning level.")),
+            ]),
+            parent_module: None,
+            is_stateless: true,
+            cache_scope: crate::cache::CacheScope::Host,
+            cache_sensitivity: crate::cache::CacheSensitivity::Normal,
+        }
+    }
+
+    fn get_metadata_self(&self) -> Metadata {
+        Metadata {
+            module_spec: self.get_module_spec(),
+            cache_sensitivity: self.cache_sensitivity,
+            ..Self::get_metadata()
+        }
+    }
+
+    fn get_module_spec(&self) -> ModuleSpecification {
+        let id = if self.instance_id.is_empty() { MODULE_NAME } else { &self.instance_id };
+        ModuleSpecification::new_with_type(id, MODULE_VERSION, "monitor")
+    }
+}
+
+impl BoxCloneableMonitor for Script {
+    fn box_clone(&self) -> Monitor {
+        Box::new(self.clone())
+    }
+}
+
+impl MonitoringModule for Script {
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            display_style: self.display_style.clone(),
+            display_text: self.display_text.clone(),
+            display_icon: self.display_icon.clone(),
+            category: self.category.clone(),
+            unit: self.unit.clone(),
+            use_multivalue: self.parse_mode == ParseMode::KeyValue,
+            ignore_from_summary: self.ignore_from_summary,
+            ..Default::default()
+        }
+    }
+
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new(self.connector.as_str(), "0.0.1"))
+    }
+
+    fn get_connector_message(&self, host: Host, _parent_result: DataPoint) -> Result<String, String> {
+        if self.command.is_empty() {
+            return Err(String::from("Command is not set"));
+        }
+
+        if !self.setting_errors.is_empty() {
+            return Err(self.setting_errors.join(", "));
+        }
+
+        if self.use_sudo && host.settings.contains(&HostSetting::UseSudo) {
+            let mut command = ShellCommand::new_from(vec!["sh", "-c", self.command.as_str()]);
+            command.use_sudo = true;
+            Ok(command.to_string())
+        }
+        else {
+            Ok(self.command.clone())
+        }
+    }
+
+    fn process_response(&self, _host: Host, response: ResponseMessage, _parent_result: DataPoint) -> Result<DataPoint, String> {
+        if response.is_error() {
+            let message = response.message.lines().next().unwrap_or_default().to_string();
+            return Ok(DataPoint::value_with_level(format!("Exit code {}: {}", response.return_code, message), Criticality::Error));
+        }
+
+        let output = response.message.trim();
+
+        match self.parse_mode {
+            ParseMode::Raw => self.to_data_point(output),
+            ParseMode::Regex => {
+                let regex = self.pattern.as_ref().ok_or(String::from("Invalid pattern"))?;
+                let captures = regex.captures(output).ok_or(format!("Pattern didn't match output: {}", output))?;
+                let value = captures.get(1).or(captures.get(0)).map(|value| value.as_str()).unwrap_or_default();
+                self.to_data_point(value)
+            },
+            ParseMode::Json => {
+                let root = serde_json::from_str::<Value>(output).map_err(|error| format!("Invalid JSON: {}", error))?;
+                let value = get_json_value(&root, &self.json_path).ok_or(format!("Path not found: {}", self.json_path))?;
+                self.to_data_point(&json_value_to_string(value))
+            },
+            ParseMode::KeyValue => {
+                let mut result = DataPoint::empty();
+
+                for line in output.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+                    if let Some((key, value)) = line.split_once('=') {
+                        let mut point = self.to_data_point(value.trim())?;
+                        point.label = key.trim().to_string();
+                        // Attached commands receive the key as the first parameter.
+                        point.command_params = vec![point.label.clone()];
+                        result.multivalue.push(point);
+                    }
+                }
+
+                if let Some(most_critical) = result.multivalue.iter().max_by_key(|value| value.criticality) {
+                    result.criticality = most_critical.criticality;
+                }
+
+                Ok(result)
+            },
+        }
+    }
+}
+
+impl Script {
+    fn to_data_point(&self, value: &str) -> Result<DataPoint, String> {
+        for (criticality, rule) in self.criticality_rules.iter() {
+            if rule.matches(value) {
+                return Ok(DataPoint::value_with_level(value.to_string(), *criticality));
+            }
+        }
+
+        Ok(DataPoint::new(value.to_string()))
+    }
+}
+
+
+/// Condition in the form of "<operator> <operand>", e.g. "> 90".
+/// Numeric comparisons use the leading number of the value, so units are allowed.
+#[derive(Clone)]
+struct CriticalityRule {
+    operator: String,
+    operand: String,
+    /// Compiled operand for the "=~" operator.
+    regex: Option<Regex>,
+}
+
+impl CriticalityRule {
+    const OPERATORS: [&'static str; 7] = [">=", "<=", "==", "!=", "=~", ">", "<"];
+
+    fn parse(rule: &str) -> Result<Self, String> {
+        let rule = rule.trim();
+        let operator = Self::OPERATORS.iter().find(|operator| rule.starts_with(*operator))
+                                      .ok_or(format!("Invalid rule \"{}\". Rule should start with one of: {}", rule, Self::OPERATORS.join(" ")))?;
+
+        let operand = rule[operator.len()..].trim().to_string();
+        let regex = match *operator {
+            "=~" => Some(Regex::new(&operand).map_err(|error| format!("Invalid pattern in rule \"{}\": {}", rule, error))?),
+            _ => None,
+        };
+
+        Ok(CriticalityRule {
+            operator: operator.to_string(),
+            operand: operand,
+            regex: regex,
+        })
+    }
+
+    fn matches(&self, value: &str) -> bool {
+        match self.operator.as_str() {
+            "==" => value == self.operand,
+            "!=" => value != self.operand,
+            "=~" => self.regex.as_ref().map(|regex| regex.is_match(value)).unwrap_or(false),
+            _ => {
+                let (value, operand) = match (parse_leading_number(value), parse_leading_number(&self.operand)) {
+                    (Some(value), Some(operand)) => (value, operand),
+                    _ => return false,
+                };
+
+                match self.operator.as_str() {
+                    ">=" => value >= operand,
+                    "<=" => value <= operand,
+                    ">" => value > operand,
+                    "<" => value < operand,
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+fn parse_leading_number(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let end = value.char_indices()
+                   .find(|(index, char)| !(char.is_ascii_digit() || *char == '.' || (*index == 0 && *char == '-')))
+                   .map(|(index, _)| index)
+                   .unwrap_or(value.len());
+
+    value[..end].parse::<f64>().ok()
+}
+
+/// Supports dot-separated object keys and array indexes, e.g. "status.disks.0.health". A leading "$." is optional.
+fn get_json_value<'a>(root: &'a Value, path: &str) -> Option<&'a Value> {
+    path.trim_start_matches('$')
+        .split('.')
+        .filter(|key| !key.is_empty())
+        .try_fold(root, |value, key| match value {
+            Value::Array(array) => key.parse::<usize>().ok().and_then(|index| array.get(index)),
+            _ => value.get(key),
+        })
+}
+
+fn json_value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        Value::Null => String::new(),
+        _ => value.to_string(),
+    }
+}
@@ -1,4 +1,15 @@
 pub mod lvm;
+pub mod zfs;
 
 pub mod filesystem;
 pub use filesystem::Filesystem;
+pub mod block_devices;
+pub use block_devices::BlockDevices;
+pub mod disk_io;
+pub use disk_io::DiskIo;
+pub mod smart;
+pub use smart::Smart;
+pub mod mdraid;
+pub use mdraid::MdRaid;
+pub mod btrfs;
+pub use btrfs::Btrfs;
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use serde_derive::Deserialize;
+use serde_json::Value;
+use crate::module::connection::ResponseMessage;
+use crate::{
+    Host,
+    frontend,
+    utils::string_manipulation,
+};
+use lightkeeper_module::monitoring_module;
+use crate::module::*;
+use crate::module::monitoring::*;
+use crate::utils::ShellCommand;
+
+#[monitoring_module(
+    name="storage-block-devices",
+    version="0.0.1",
+    description="Shows block devices as a tree: disks and their partitions, LVM volumes, encrypted devices and mountpoints.",
+    settings={
+        ignored_types => "Comma-separated list of device types to ignore. Default: loop",
+    }
+)]
+pub struct BlockDevices {
+    ignored_types: Vec<String>,
+}
+
+impl Module for BlockDevices {
+    fn new(settings: &HashMap<String, String>) -> Self {
+        BlockDevices {
+            ignored_types: settings.get("ignored_types").unwrap_or(&String::from("loop"))
+                                   .split(',').map(|device_type| device_type.trim().to_string())
+                                   .filter(|device_type| !device_type.is_empty())
+                                   .collect(),
+        }
+    }
+}
+
+impl MonitoringModule for BlockDevices {
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            display_style: frontend::DisplayStyle::Text,
+            display_text: String::from("Block devices"),
+            category: String::from("storage"),
+            use_multivalue: true,
+            ..Default::default()
+        }
+    }
+
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new("ssh", "0.0.1"))
+    }
+
+    fn get_connector_message(&self, host: Host, _result: DataPoint) -> Result<String, String> {
+        // JSON output is supported since util-linux 2.27.
+        if host.platform.version_is_same_or_greater_than(platform_info::Flavor::Debian, "9") ||
+           host.platform.version_is_same_or_greater_than(platform_info::Flavor::Ubuntu, "20") ||
+           host.platform.version_is_same_or_greater_than(platform_info::Flavor::CentOS, "8") {
+            let mut command = ShellCommand::new();
+            command.arguments(vec!["lsblk", "--json", "--bytes", "--output", "NAME,TYPE,SIZE,FSTYPE,MOUNTPOINT,MODEL,RO"]);
+            Ok(command.to_string())
+        }
+        else {
+            Err(String::from("Unsupported platform"))
+        }
+    }
+
+    fn process_response(&self, _host: Host, response: ResponseMessage, _result: DataPoint) -> Result<DataPoint, String> {
+        if response.is_error() {
+            return Err(response.message);
+        }
+
+        let output: LsblkOutput = serde_json::from_str(&response.message).map_err(|error| error.to_string())?;
+        let mut result = DataPoint::empty();
+
+        for device in output.blockdevices.iter().filter(|device| !self.ignored_types.contains(&device.device_type)) {
+            // Unused devices, e.g. zram without a configured size.
+            if device.get_size() == 0 {
+                continue;
+            }
+
+            let mut data_point = device.to_data_point(0);
+            // Only 2 levels are displayed, so deeper levels are flattened and indented.
+            self.add_children(&mut data_point.multivalue, device, 0);
+            result.multivalue.push(data_point);
+        }
+
+        if result.multivalue.is_empty() {
+            result.multivalue.push(DataPoint::labeled_value("No block devices found", " "));
+        }
+
+        Ok(result)
+    }
+}
+
+impl BlockDevices {
+    fn add_children(&self, data_points: &mut Vec<DataPoint>, device: &BlockDevice, depth: usize) {
+        for child in device.children.iter().filter(|child| !self.ignored_types.contains(&child.device_type)) {
+            data_points.push(child.to_data_point(depth));
+            self.add_children(data_points, child, depth + 1);
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct LsblkOutput {
+    blockdevices: Vec<BlockDevice>,
+}
+
+/// Older lsblk versions output numbers and booleans as strings, so those are parsed separately.
+#[derive(Deserialize)]
+struct BlockDevice {
+    name: String,
+    #[serde(rename = "type")]
+    device_type: String,
+    size: Value,
+    fstype: Option<String>,
+    mountpoint: Option<String>,
+    model: Option<String>,
+    ro: Value,
+    #[serde(default)]
+    children: Vec<BlockDevice>,
+}
+
+impl BlockDevice {
+    fn get_size(&self) -> u64 {
+        match &self.size {
+            Value::Number(size) => size.as_u64().unwrap_or_default(),
+            Value::String(size) => size.parse().unwrap_or_default(),
+            _ => 0,
+        }
+    }
+
+    fn is_read_only(&self) -> bool {
+        match &self.ro {
+            Value::Bool(read_only) => *read_only,
+            Value::String(read_only) => read_only == "1",
+            _ => false,
+        }
+    }
+
+    fn get_path(&self) -> String {
+        match self.device_type.as_str() {
+            "lvm" | "crypt" | "dm" => format!("/dev/mapper/{}", self.name),
+            _ => format!("/dev/{}", self.name),
+        }
+    }
+
+    fn to_data_point(&self, depth: usize) -> DataPoint {
+        let label = format!("{}{}", "  ".repeat(depth), self.name);
+        let mut data_point = DataPoint::labeled_value(label, string_manipulation::format_bytes_human(self.get_size()));
+
+        let details = [Some(&self.device_type), self.model.as_ref(), self.fstype.as_ref(), self.mountpoint.as_ref()];
+        data_point.description = details.iter().flatten()
+                                        .map(|detail| detail.trim())
+                                        .filter(|detail| !detail.is_empty())
+                                        .collect::<Vec<_>>()
+                                        .join(" | ");
+
+        if self.is_read_only() {
+            data_point.tags.push(String::from("Read-only"));
+        }
+
+        data_point.command_params = vec![self.get_path()];
+        data_point
+    }
+}
@@ -0,0 +1,368 @@
+use std::collections::HashMap;
+use crate::enums::Criticality;
+use crate::module::connection::ResponseMessage;
+use crate::{
+    Host,
+    frontend,
+    host::HostSetting,
+    utils::string_manipulation,
+};
+use lightkeeper_module::monitoring_module;
+use crate::module::*;
+use crate::module::monitoring::*;
+use crate::utils::ShellCommand;
+
+const SECTION_MOUNTPOINT: &str = "### mountpoint ";
+const SECTION_DEVICE_STATS: &str = "### device stats";
+const SECTION_SCRUB_STATUS: &str = "### scrub status";
+const SECTION_BALANCE_STATUS: &str = "### balance status";
+
+/// Below this, new metadata chunks may not fit and the filesystem can run out of space even if data has free space.
+const LOW_UNALLOCATED_BYTES: u64 = 1024 * 1024 * 1024;
+
+#[monitoring_module(
+    name="storage-btrfs",
+    version="0.0.1",
+    description="Shows btrfs allocation, device error counters and scrub status. Value is the allocated share of the devices, which df doesn't show.",
+    settings={
+        metadata_warning_threshold => "Warning threshold for metadata usage in percent when unallocated space is low. Default: 75",
+        metadata_critical_threshold => "Critical threshold for metadata usage in percent when unallocated space is low. Default: 90",
+    }
+)]
+pub struct Btrfs {
+    metadata_threshold_warning: f64,
+    metadata_threshold_critical: f64,
+}
+
+impl Module for Btrfs {
+    fn new(settings: &HashMap<String, String>) -> Self {
+        Btrfs {
+            metadata_threshold_warning: settings.get("metadata_warning_threshold").unwrap_or(&String::from("75")).parse().unwrap(),
+            metadata_threshold_critical: settings.get("metadata_critical_threshold").unwrap_or(&String::from("90")).parse().unwrap(),
+        }
+    }
+}
+
+impl MonitoringModule for Btrfs {
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            display_style: frontend::DisplayStyle::ProgressBar,
+            display_text: String::from("Btrfs"),
+            category: String::from("storage"),
+            unit: String::from("%"),
+            use_multivalue: true,
+            ..Default::default()
+        }
+    }
+
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new("ssh", "0.0.1"))
+    }
+
+    fn get_connector_message(&self, host: Host, _result: DataPoint) -> Result<String, String> {
+        if host.platform.os != platform_info::OperatingSystem::Linux {
+            return Err(String::from("Unsupported platform"));
+        }
+
+        let use_sudo = host.settings.contains(&HostSetting::UseSudo);
+        let btrfs_command = |arguments: Vec<&str>| {
+            let mut command = ShellCommand::new_from(vec!["btrfs"]);
+            command.arguments(arguments);
+            command.use_sudo = use_sudo;
+            format!("{} \"$target\" < /dev/null", command.to_string())
+        };
+
+        // Subvolumes of the same filesystem can be mounted multiple times, so only the first mountpoint for each UUID is used.
+        // List output is used since raw output escapes spaces in mountpoints.
+        Ok(format!(
+            "findmnt -n -l -t btrfs -o UUID,TARGET | sort -u -k1,1 | while read -r uuid target; do \
+             echo '{}'\"$target\"; {}; echo '{}'; {}; echo '{}'; {}; echo '{}'; {}; done",
+            SECTION_MOUNTPOINT, btrfs_command(vec!["filesystem", "usage", "-b"]),
+            SECTION_DEVICE_STATS, btrfs_command(vec!["device", "stats"]),
+            SECTION_SCRUB_STATUS, btrfs_command(vec!["scrub", "status"]),
+            SECTION_BALANCE_STATUS, btrfs_command(vec!["balance", "status"]),
+        ))
+    }
+
+    fn process_response(&self, _host: Host, response: ResponseMessage, _result: DataPoint) -> Result<DataPoint, String> {
+        let filesystems = parse_output(&response.message);
+        if filesystems.is_empty() && response.is_error() {
+            return Err(response.message);
+        }
+
+        let mut result = DataPoint::empty();
+        for filesystem in filesystems.iter() {
+            result.multivalue.push(self.get_data_point(filesystem));
+        }
+
+        if result.multivalue.is_empty() {
+            result.multivalue.push(DataPoint::labeled_value("No btrfs filesystems found", " "));
+        }
+
+        result.criticality = result.multivalue.iter().map(|data_point| data_point.criticality).max().unwrap_or(Criticality::Normal);
+        Ok(result)
+    }
+}
+
+impl Btrfs {
+    fn get_data_point(&self, filesystem: &BtrfsFilesystem) -> DataPoint {
+        let allocated_percent = filesystem.device_allocated as f64 / filesystem.device_size.max(1) as f64 * 100.0;
+        let mut data_point = DataPoint::labeled_value(filesystem.mountpoint.clone(), format!("{:.0}", allocated_percent));
+        data_point.command_params = vec![filesystem.mountpoint.clone()];
+
+        let mut details = vec![
+            format!("{} / {} allocated", string_manipulation::format_bytes_human(filesystem.device_allocated),
+                                         string_manipulation::format_bytes_human(filesystem.device_size)),
+            format!("{} used", string_manipulation::format_bytes_human(filesystem.used)),
+            format!("{} free (estimated)", string_manipulation::format_bytes_human(filesystem.free_estimated)),
+        ];
+        let mut criticalities = vec![Criticality::Normal];
+
+        if filesystem.device_missing > 0 {
+            details.push(format!("{} missing", string_manipulation::format_bytes_human(filesystem.device_missing)));
+            criticalities.push(Criticality::Critical);
+        }
+
+        let unallocated_low = filesystem.device_size.saturating_sub(filesystem.device_allocated) < LOW_UNALLOCATED_BYTES;
+
+        for allocation in filesystem.allocations.iter() {
+            let used_percent = allocation.used as f64 / allocation.size.max(1) as f64 * 100.0;
+            let mut allocation_point = DataPoint::labeled_value(format!("{} ({})", allocation.block_group, allocation.profile), format!("{:.0}", used_percent));
+            allocation_point.description = format!("{} / {} used", string_manipulation::format_bytes_human(allocation.used),
+                                                                   string_manipulation::format_bytes_human(allocation.size));
+
+            if allocation.block_group == "Metadata" && unallocated_low {
+                if used_percent >= self.metadata_threshold_critical {
+                    allocation_point.criticality = Criticality::Critical;
+                }
+                else if used_percent >= self.metadata_threshold_warning {
+                    allocation_point.criticality = Criticality::Warning;
+                }
+            }
+
+            criticalities.push(allocation_point.criticality);
+            data_point.multivalue.push(allocation_point);
+        }
+
+        for device in filesystem.devices.iter() {
+            let total_errors = device.error_counters.iter().map(|(_, count)| count).sum::<u64>();
+            let mut device_point = DataPoint::labeled_value(device.name.clone(), format!("{} errors", total_errors));
+            device_point.description = device.error_counters.iter()
+                                                            .map(|(counter, count)| format!("{} {}", counter.trim_end_matches("_errs").replace("_io", ""), count))
+                                                            .collect::<Vec<_>>()
+                                                            .join(" | ");
+
+            let has_corruption = device.error_counters.iter().any(|(counter, count)| counter == "corruption_errs" && *count > 0);
+            device_point.criticality = if has_corruption {
+                Criticality::Error
+            }
+            else if total_errors > 0 {
+                Criticality::Warning
+            }
+            else {
+                Criticality::Normal
+            };
+
+            criticalities.push(device_point.criticality);
+            data_point.multivalue.push(device_point);
+        }
+
+        let scrub = &filesystem.scrub;
+        if scrub.status == "running" {
+            details.push(String::from("scrub running"));
+            data_point.tags.push(String::from("Scrubbing"));
+        }
+        else if scrub.status.is_empty() {
+            details.push(String::from("never scrubbed"));
+        }
+        else {
+            details.push(format!("last scrub {} {}, {}", scrub.status, scrub.started, scrub.error_summary));
+        }
+        if scrub.has_errors() {
+            criticalities.push(Criticality::Error);
+        }
+
+        if filesystem.balance_running {
+            details.push(String::from("balance running"));
+            data_point.tags.push(String::from("Balancing"));
+        }
+
+        data_point.description = details.join(" | ");
+        data_point.criticality = criticalities.into_iter().max().unwrap();
+        data_point
+    }
+}
+
+fn parse_output(output: &str) -> Vec<BtrfsFilesystem> {
+    let mut filesystems = Vec::<BtrfsFilesystem>::new();
+    let mut section = "";
+
+    for line in output.lines() {
+        if let Some(mountpoint) = line.strip_prefix(SECTION_MOUNTPOINT) {
+            filesystems.push(BtrfsFilesystem { mountpoint: mountpoint.to_string(), ..Default::default() });
+            section = SECTION_MOUNTPOINT;
+            continue;
+        }
+        else if line.starts_with("### ") {
+            section = match line {
+                SECTION_DEVICE_STATS => SECTION_DEVICE_STATS,
+                SECTION_SCRUB_STATUS => SECTION_SCRUB_STATUS,
+                SECTION_BALANCE_STATUS => SECTION_BALANCE_STATUS,
+                _ => "",
+            };
+            continue;
+        }
+
+        let filesystem = match filesystems.last_mut() {
+            Some(filesystem) => filesystem,
+            None => continue,
+        };
+
+        match section {
+            SECTION_MOUNTPOINT => filesystem.parse_usage_line(line),
+            SECTION_DEVICE_STATS => filesystem.parse_device_stats_line(line),
+            SECTION_SCRUB_STATUS => filesystem.scrub.parse_line(line),
+            SECTION_BALANCE_STATUS if line.contains("is running") => filesystem.balance_running = true,
+            _ => {}
+        }
+    }
+
+    filesystems
+}
+
+#[derive(Default)]
+struct BtrfsFilesystem {
+    mountpoint: String,
+    device_size: u64,
+    device_allocated: u64,
+    device_missing: u64,
+    used: u64,
+    free_estimated: u64,
+    allocations: Vec<BlockGroupAllocation>,
+    devices: Vec<BtrfsDevice>,
+    scrub: ScrubStatus,
+    balance_running: bool,
+}
+
+impl BtrfsFilesystem {
+    /// Parses a line of `btrfs filesystem usage -b`.
+    fn parse_usage_line(&mut self, line: &str) {
+        // E.g. "Data,single: Size:10737418240, Used:9293139968 (86.55%)".
+        if let Some((block_group, rest)) = line.split_once(": Size:") {
+            if let Some((group, profile)) = block_group.split_once(',') {
+                let mut parts = rest.split(", Used:");
+                let size = parts.next().unwrap_or_default().trim().parse().unwrap_or_default();
+                let used = parts.next().unwrap_or_default().split_whitespace().next().unwrap_or_default().parse().unwrap_or_default();
+                self.allocations.push(BlockGroupAllocation {
+                    block_group: group.to_string(),
+                    profile: profile.to_string(),
+                    size: size,
+                    used: used,
+                });
+            }
+            return;
+        }
+
+        // E.g. "    Device size:                 107374182400".
+        if let Some((key, value)) = line.trim().split_once(':') {
+            let value = value.split_whitespace().next().unwrap_or_default().parse::<u64>().unwrap_or_default();
+            match key {
+                "Device size" => self.device_size = value,
+                "Device allocated" => self.device_allocated = value,
+                "Device missing" => self.device_missing = value,
+                "Used" => self.used = value,
+                "Free (estimated)" => self.free_estimated = value,
+                _ => {}
+            }
+        }
+    }
+
+    /// Parses a line of `btrfs device stats`, e.g. "[/dev/sda2].write_io_errs    0".
+    fn parse_device_stats_line(&mut self, line: &str) {
+        let (device_and_counter, count) = match line.split_once(char::is_whitespace) {
+            Some(parts) => parts,
+            None => return,
+        };
+        let (device, counter) = match device_and_counter.strip_prefix('[').and_then(|rest| rest.split_once("].")) {
+            Some(parts) => parts,
+            None => return,
+        };
+        let count = count.trim().parse::<u64>().unwrap_or_default();
+
+        match self.devices.iter_mut().find(|existing| existing.name == device) {
+            Some(existing) => existing.error_counters.push((counter.to_string(), count)),
+            None => self.devices.push(BtrfsDevice {
+                name: device.to_string(),
+                error_counters: vec![(counter.to_string(), count)],
+            }),
+        }
+    }
+}
+
+struct BlockGroupAllocation {
+    /// Data, Metadata or System.
+    block_group: String,
+    /// E.g. single, DUP or RAID1.
+    profile: String,
+    size: u64,
+    used: u64,
+}
+
+struct BtrfsDevice {
+    name: String,
+    error_counters: Vec<(String, u64)>,
+}
+
+#[derive(Default)]
+struct ScrubStatus {
+    /// E.g. "finished", "running", "aborted" or "interrupted". Empty if never scrubbed.
+    status: String,
+    started: String,
+    /// E.g. "no errors found" or "csum=3".
+    error_summary: String,
+}
+
+impl ScrubStatus {
+    /// Parses a line of `btrfs scrub status`.
+    fn parse_line(&mut self, line: &str) {
+        let line = line.trim();
+
+        // Older than btrfs-progs 5.0, e.g. "scrub started at Sun Oct 12 00:00:01 2025 and finished after 00:01:23"
+        // or "scrub started at Sun Oct 12 00:00:01 2025, running for 00:00:10".
+        if let Some(rest) = line.strip_prefix("scrub started at ") {
+            let date_end = rest.find(" and ").or(rest.find(", ")).unwrap_or(rest.len());
+            let (started, status) = rest.split_at(date_end);
+            self.started = started.to_string();
+            self.status = match status {
+                _ if status.contains("finished") => String::from("finished"),
+                _ if status.contains("running") => String::from("running"),
+                _ if status.contains("aborted") => String::from("aborted"),
+                _ => String::from("interrupted"),
+            };
+            return;
+        }
+        // E.g. "total bytes scrubbed: 9.30GiB with 0 errors".
+        else if line.starts_with("total bytes scrubbed:") {
+            let errors = line.split_whitespace().rev().nth(1).unwrap_or_default();
+            self.error_summary = match errors {
+                "0" => String::from("no errors found"),
+                _ => format!("{} errors", errors),
+            };
+            return;
+        }
+
+        if let Some((key, value)) = line.split_once(':') {
+            let value = value.trim().to_string();
+            match key.trim() {
+                "Status" => self.status = value,
+                "Scrub started" => self.started = value,
+                "Error summary" => self.error_summary = value,
+                _ => {}
+            }
+        }
+    }
+
+    fn has_errors(&self) -> bool {
+        !self.error_summary.is_empty() && self.error_summary != "no errors found"
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::{BTreeMap, HashMap};
+use crate::enums::Criticality;
+use crate::module::connection::ResponseMessage;
+use crate::module::platform_info;
+use crate::{
+    Host,
+    frontend,
+    utils::string_manipulation,
+};
+use lightkeeper_module::monitoring_module;
+use crate::module::*;
+use crate::module::monitoring::*;
+use crate::module::monitoring::sampling::{Sample, SampleHistory};
+
+/// /proc/diskstats always uses 512-byte sectors.
+const SECTOR_SIZE: u64 = 512;
+
+#[monitoring_module(
+    name="storage-disk-io",
+    version="0.0.1",
+    description="Shows disk throughput, IOPS and utilisation using /proc/diskstats. Values are calculated from the difference to the previous refresh.",
+    settings={
+        ignored_devices => "Comma-separated list of device name prefixes to ignore. Default: loop,ram,zram,sr,fd",
+        warning_threshold => "Warning threshold for utilisation in percent. Default: 80",
+        critical_threshold => "Critical threshold for utilisation in percent. Default: 95",
+    }
+)]
+pub struct DiskIo {
+    ignored_devices: Vec<String>,
+    threshold_warning: f64,
+    threshold_critical: f64,
+    samples: SampleHistory<DiskSample>,
+}
+
+impl Module for DiskIo {
+    fn new(settings: &HashMap<String, String>) -> Self {
+        DiskIo {
+            ignored_devices: settings.get("ignored_devices").unwrap_or(&String::from("loop,ram,zram,sr,fd"))
+                                     .split(',').map(|device| device.trim().to_string())
+                                     .filter(|device| !device.is_empty())
+                                     .collect(),
+            threshold_warning: settings.get("warning_threshold").unwrap_or(&String::from("80")).parse().unwrap(),
+            threshold_critical: settings.get("critical_threshold").unwrap_or(&String::from("95")).parse().unwrap(),
+            samples: SampleHistory::default(),
+        }
+    }
+}
+
+impl MonitoringModule for DiskIo {
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            display_style: frontend::DisplayStyle::ProgressBar,
+            display_text: String::from("Disk I/O"),
+            category: String::from("storage"),
+            unit: String::from("%"),
+            use_multivalue: true,
+            ..Default::default()
+        }
+    }
+
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new("ssh", "0.0.1"))
+    }
+
+    fn get_connector_message(&self, host: Host, _result: DataPoint) -> Result<String, String> {
+        if host.platform.os != platform_info::OperatingSystem::Linux {
+            return Err(String::from("Unsupported platform"));
+        }
+
+        // Uptime is used as the timestamp of the sample. /sys/block lists whole devices so that partitions can be left out.
+        Ok(format!("{} && ls /sys/block", self.samples.get_command(&host.name, "cat /proc/uptime /proc/diskstats")))
+    }
+
+    fn process_response(&self, host: Host, response: ResponseMessage, _result: DataPoint) -> Result<DataPoint, String> {
+        if response.is_error() {
+            return Err(response.message);
+        }
+
+        let (samples, whole_devices) = parse_output(&response.message)?;
+        let (current, previous) = self.samples.update(&host.name, samples, response.is_from_cache)?;
+
+        let mut result = DataPoint::empty();
+        for (device, stats) in current.devices.iter() {
+            if !whole_devices.contains(device) || self.ignored_devices.iter().any(|prefix| device.starts_with(prefix)) {
+                continue;
+            }
+
+            let data_point = match previous.as_ref() {
+                Some(previous) => {
+                    let elapsed = current.uptime - previous.uptime;
+                    match previous.devices.get(device) {
+                        Some(previous_stats) => self.get_data_point(device, &stats.difference(previous_stats), elapsed),
+                        // New device.
+                        None => continue,
+                    }
+                },
+                None => {
+                    let mut data_point = self.get_data_point(device, stats, current.uptime);
+                    data_point.description.push_str(" | average since boot");
+                    data_point
+                }
+            };
+
+            result.multivalue.push(data_point);
+        }
+
+        if result.multivalue.is_empty() {
+            result.multivalue.push(DataPoint::labeled_value("No disks found", " "));
+        }
+
+        result.criticality = result.multivalue.iter().map(|data_point| data_point.criticality).max().unwrap_or(Criticality::Normal);
+        Ok(result)
+    }
+}
+
+impl DiskIo {
+    fn get_data_point(&self, device: &str, stats: &DiskStats, elapsed_seconds: f64) -> DataPoint {
+        let elapsed_seconds = elapsed_seconds.max(0.001);
+        // Time spent doing I/O can exceed the elapsed time a little because of rounding.
+        let utilisation = (stats.io_time_ms as f64 / (elapsed_seconds * 1000.0) * 100.0).min(100.0);
+        let read_per_second = (stats.sectors_read * SECTOR_SIZE) as f64 / elapsed_seconds;
+        let written_per_second = (stats.sectors_written * SECTOR_SIZE) as f64 / elapsed_seconds;
+        let iops = (stats.reads_completed + stats.writes_completed) as f64 / elapsed_seconds;
+
+        let mut data_point = DataPoint::labeled_value(device.to_string(), format!("{:.0}", utilisation));
+        data_point.description = format!("read {}/s | write {}/s | {:.0} IOPS",
+                                         string_manipulation::format_bytes_human(read_per_second as u64),
+                                         string_manipulation::format_bytes_human(written_per_second as u64),
+                                         iops);
+        data_point.criticality = if utilisation >= self.threshold_critical {
+            Criticality::Critical
+        }
+        else if utilisation >= self.threshold_warning {
+            Criticality::Warning
+        }
+        else {
+            Criticality::Normal
+        };
+        data_point.command_params = vec![format!("/dev/{}", device)];
+        data_point
+    }
+}
+
+/// Returns samples in the order they were taken and the names of whole devices.
+fn parse_output(output: &str) -> Result<(Vec<DiskSample>, Vec<String>), String> {
+    let mut samples = Vec::<DiskSample>::new();
+    let mut whole_devices = Vec::new();
+
+    for line in output.lines() {
+        let parts = line.split_whitespace().collect::<Vec<_>>();
+
+        match parts.len() {
+            0 => continue,
+            // Output of ls.
+            1 => whole_devices.push(parts[0].to_string()),
+            // Uptime starts a new sample.
+            2 => {
+                let uptime = parts[0].parse::<f64>().map_err(|error| format!("Invalid uptime: {}", error))?;
+                samples.push(DiskSample { uptime: uptime, devices: BTreeMap::new() });
+            },
+            _ => {
+                let sample = samples.last_mut().ok_or_else(|| String::from("Invalid output"))?;
+                let stats = DiskStats::from_values(&parts[3..])?;
+                sample.devices.insert(parts[2].to_string(), stats);
+            }
+        }
+    }
+
+    if samples.is_empty() {
+        return Err(String::from("No disk statistics found"));
+    }
+    Ok((samples, whole_devices))
+}
+
+#[derive(Clone)]
+struct DiskSample {
+    /// Seconds since boot.
+    uptime: f64,
+    devices: BTreeMap<String, DiskStats>,
+}
+
+impl Sample for DiskSample {
+    fn timestamp(&self) -> f64 {
+        self.uptime
+    }
+}
+
+/// Cumulative counters since boot.
+#[derive(Clone)]
+struct DiskStats {
+    reads_completed: u64,
+    sectors_read: u64,
+    writes_completed: u64,
+    sectors_written: u64,
+    io_time_ms: u64,
+}
+
+impl DiskStats {
+    /// Takes the fields after the device name.
+    fn from_values(values: &[&str]) -> Result<Self, String> {
+        if values.len() < 10 {
+            return Err(String::from("Invalid /proc/diskstats line"));
+        }
+
+        let value = |index: usize| values[index].parse::<u64>().map_err(|error| format!("Invalid /proc/diskstats line: {}", error));
+        Ok(DiskStats {
+            reads_completed: value(0)?,
+            sectors_read: value(2)?,
+            writes_completed: value(4)?,
+            sectors_written: value(6)?,
+            io_time_ms: value(9)?,
+        })
+    }
+
+    /// Counters wrap around on 32-bit systems, so saturating subtraction is used.
+    fn difference(&self, previous: &DiskStats) -> DiskStats {
+        DiskStats {
+            reads_completed: self.reads_completed.saturating_sub(previous.reads_completed),
+            sectors_read: self.sectors_read.saturating_sub(previous.sectors_read),
+            writes_completed: self.writes_completed.saturating_sub(previous.writes_completed),
+            sectors_written: self.sectors_written.saturating_sub(previous.sectors_written),
+            io_time_ms: self.io_time_ms.saturating_sub(previous.io_time_ms),
+        }
+    }
+}
@@ -4,7 +4,9 @@ use crate::module::connection::ResponseMessage;
 use crate::{
     Host,
     frontend,
+    utils::string_manipulation,
 };
+use lightkeeper_agent::collect;
 use lightkeeper_module::monitoring_module;
 use crate::module::*;
 use crate::module::monitoring::*;
@@ -12,12 +14,15 @@ use crate::module::monitoring::*;
 #[monitoring_module(
     name="filesystem",
     version="0.0.1",
-    description="Shows filesystem usage in a progress bar.",
+    description="Shows filesystem usage in a progress bar. Inode usage is checked too and filesystems that are unexpectedly mounted read-only are reported as critical.",
     settings={
         ignored_filesystems => "Comma-separated list of filesystems to ignore. Default: /run,/dev,/dev/shm,/sys/fs/cgroup",
         warning_threshold => "Warning threshold in percent. Default: 80",
         error_threshold => "Error threshold in percent. Default: 90",
         critical_threshold => "Critical threshold in percent. Default: 95",
+        inode_warning_threshold => "Warning threshold for inode usage in percent. Default: 80",
+        inode_error_threshold => "Error threshold for inode usage in percent. Default: 90",
+        inode_critical_threshold => "Critical threshold for inode usage in percent. Default: 95",
     }
 )]
 pub struct Filesystem {
@@ -25,8 +30,14 @@ pub struct Filesystem {
     threshold_critical: f64,
     threshold_error: f64,
     threshold_warning: f64,
+    inode_threshold_critical: f64,
+    inode_threshold_error: f64,
+    inode_threshold_warning: f64,
 }
 
+/// Filesystem types that can't be mounted read-write, so read-only mounts are expected.
+const READ_ONLY_FS_TYPES: [&str; 3] = ["squashfs", "iso9660", "erofs"];
+
 impl Module for Filesystem {
     fn new(settings: &HashMap<String, String>) -> Self {
         Filesystem {
@@ -39,6 +50,9 @@ impl Module for Filesystem {
             threshold_critical: settings.get("critical_threshold").unwrap_or(&String::from("95")).parse().unwrap(),
             threshold_error: settings.get("error_threshold").unwrap_or(&String::from("90")).parse().unwrap(),
             threshold_warning: settings.get("warning_threshold").unwrap_or(&String::from("80")).parse().unwrap(),
+            inode_threshold_critical: settings.get("inode_critical_threshold").unwrap_or(&String::from("95")).parse().unwrap(),
+            inode_threshold_error: settings.get("inode_error_threshold").unwrap_or(&String::from("90")).parse().unwrap(),
+            inode_threshold_warning: settings.get("inode_warning_threshold").unwrap_or(&String::from("80")).parse().unwrap(),
         }
     }
 }
@@ -59,40 +73,84 @@ impl MonitoringModule for Filesystem {
         Some(ModuleSpecification::new("ssh", "0.0.1"))
     }
 
-    fn get_connector_message(&self, host: Host, _result: DataPoint) -> Result<String, String> {
-        if host.platform.os == platform_info::OperatingSystem::Linux {
-            Ok(String::from("df -hPT"))
+    fn get_connector_messages(&self, host: Host, _result: DataPoint) -> Result<Vec<String>, String> {
+        if agent::is_available(&host) {
+            Ok(vec![agent::get_command()])
+        }
+        else if host.platform.os == platform_info::OperatingSystem::Linux {
+            Ok(vec![
+                String::from("df -hPT"),
+                String::from("df -iPT"),
+                String::from("cat /proc/mounts"),
+                String::from("cat /etc/fstab"),
+            ])
         }
         else {
             Err(String::from("Unsupported platform"))
         }
     }
 
-    fn process_response(&self, _host: Host, response: ResponseMessage, _result: DataPoint) -> Result<DataPoint, String> {
+    fn process_responses(&self, host: Host, responses: Vec<ResponseMessage>, _result: DataPoint) -> Result<DataPoint, String> {
         let mut result = DataPoint::empty();
 
-        // First line contains headers
-        let lines = response.message.lines().skip(1);
-        for line in lines {
-            let mut parts = line.split_whitespace();
-            let _source = parts.next().unwrap().to_string();
-            let fs_type = parts.next().unwrap().to_string();
-            let size_h = parts.next().unwrap().to_string();
-            let used_h = parts.next().unwrap().to_string();
-            let _available_h = parts.next().unwrap().to_string();
+        let filesystems = if agent::is_available(&host) {
+            let report = agent::parse_report(&responses[0])?;
+            report.filesystems.iter().map(|filesystem| FilesystemUsage {
+                fs_type: filesystem.fs_type.clone(),
+                size_h: string_manipulation::format_bytes_human(filesystem.size_bytes),
+                used_h: string_manipulation::format_bytes_human(filesystem.used_bytes),
+                used_percent: filesystem.used_percent().to_string(),
+                inodes_used_percent: filesystem.inodes_used_percent(),
+                read_only: filesystem.read_only && !filesystem.read_only_in_fstab,
+                mountpoint: filesystem.mountpoint.clone(),
+            }).collect::<Vec<_>>()
+        }
+        else {
+            let (space_response, inode_response, mounts_response, fstab_response) = match responses.as_slice() {
+                [space_response, inode_response, mounts_response, fstab_response] => (space_response, inode_response, mounts_response, fstab_response),
+                _ => return Err(String::from("Invalid amount of responses")),
+            };
+
+            let inodes_used_percents = parse_inode_usage(&inode_response.message);
+            let read_only_mountpoints = parse_read_only_mountpoints(&mounts_response.message);
+            // Missing fstab is not an error, all read-only mounts are reported then.
+            let read_only_in_fstab = parse_read_only_mountpoints(&fstab_response.message);
 
-            let mut used_percent = parts.next().unwrap().to_string();
-            // Remove percent symbol from the end.
-            used_percent.pop();
-            let used_percent_float = used_percent.parse::<f64>().unwrap();
+            // First line contains headers
+            space_response.message.lines().skip(1).map(|line| {
+                let mut parts = line.split_whitespace();
+                let _source = parts.next().unwrap().to_string();
+                let fs_type = parts.next().unwrap().to_string();
+                let size_h = parts.next().unwrap().to_string();
+                let used_h = parts.next().unwrap().to_string();
+                let _available_h = parts.next().unwrap().to_string();
 
-            let mountpoint = parts.next().unwrap().to_string();
+                let mut used_percent = parts.next().unwrap().to_string();
+                // Remove percent symbol from the end.
+                used_percent.pop();
 
-            if self.ignored_filesystems.iter().any(|item| mountpoint.starts_with(item)) {
+                // df doesn't escape spaces in mountpoints.
+                let mountpoint = parts.collect::<Vec<_>>().join(" ");
+
+                FilesystemUsage {
+                    fs_type: fs_type,
+                    size_h: size_h,
+                    used_h: used_h,
+                    used_percent: used_percent,
+                    inodes_used_percent: inodes_used_percents.get(&mountpoint).cloned().flatten(),
+                    read_only: read_only_mountpoints.contains(&mountpoint) && !read_only_in_fstab.contains(&mountpoint),
+                    mountpoint: mountpoint,
+                }
+            }).collect::<Vec<_>>()
+        };
+
+        for filesystem in filesystems {
+            if self.ignored_filesystems.iter().any(|item| filesystem.mountpoint.starts_with(item)) {
                 continue;
             }
 
-            let mut data_point = DataPoint::labeled_value(mountpoint.clone(), used_percent);
+            let used_percent_float = filesystem.used_percent.parse::<f64>().unwrap();
+            let mut data_point = DataPoint::labeled_value(filesystem.mountpoint.clone(), filesystem.used_percent);
             data_point.criticality = if used_percent_float >= self.threshold_critical {
                 Criticality::Critical
             }
@@ -105,14 +163,82 @@ impl MonitoringModule for Filesystem {
             else {
                 Criticality::Normal
             };
-            data_point.description = format!("{} | {} / {} used", fs_type, used_h, size_h);
-            data_point.command_params.push(mountpoint);
+            data_point.description = format!("{} | {} / {} used", filesystem.fs_type, filesystem.used_h, filesystem.size_h);
+
+            // Inodes can run out while there's still plenty of space, e.g. on mail spools.
+            if let Some(inodes_used_percent) = filesystem.inodes_used_percent {
+                data_point.description = format!("{} | inodes {}% used", data_point.description, inodes_used_percent);
+
+                let inode_criticality = if inodes_used_percent >= self.inode_threshold_critical {
+                    Criticality::Critical
+                }
+                else if inodes_used_percent >= self.inode_threshold_error {
+                    Criticality::Error
+                }
+                else if inodes_used_percent >= self.inode_threshold_warning {
+                    Criticality::Warning
+                }
+                else {
+                    Criticality::Normal
+                };
+
+                if inode_criticality > Criticality::Normal {
+                    data_point.tags.push(format!("Inodes {}%", inodes_used_percent));
+                    data_point.criticality = data_point.criticality.max(inode_criticality);
+                }
+            }
+
+            // Usually the kernel remounted the filesystem read-only because of I/O or filesystem errors.
+            if filesystem.read_only && !READ_ONLY_FS_TYPES.contains(&filesystem.fs_type.as_str()) {
+                data_point.tags.push(String::from("Read-only"));
+                data_point.criticality = Criticality::Critical;
+            }
+
+            data_point.command_params.push(filesystem.mountpoint);
             result.multivalue.push(data_point);
         }
 
         let most_critical = result.multivalue.iter().max_by_key(|datapoint| datapoint.criticality).unwrap();
         result.criticality = most_critical.criticality;
+        result.set_cache_state(&responses);
 
         Ok(result)
     }
-}
\ No newline at end of file
+}
+
+/// Parses `df -iPT` output. Returns IUse% by mountpoint. Value is None if the filesystem has no fixed inode count, e.g. btrfs.
+fn parse_inode_usage(output: &str) -> HashMap<String, Option<f64>> {
+    output.lines().skip(1).filter_map(|line| {
+        let parts = line.split_whitespace().collect::<Vec<_>>();
+        if parts.len() < 7 {
+            return None;
+        }
+        let used_percent = parts[5].trim_end_matches('%').parse::<f64>().ok();
+        Some((parts[6..].join(" "), used_percent))
+    }).collect()
+}
+
+/// Parses /proc/mounts or /etc/fstab. Returns mountpoints that have the "ro" option. Spaces are octal-escaped in both, e.g. "\040".
+fn parse_read_only_mountpoints(output: &str) -> Vec<String> {
+    output.lines()
+          .filter(|line| !line.trim_start().starts_with('#'))
+          .filter_map(|line| {
+              let parts = line.split_whitespace().collect::<Vec<_>>();
+              match parts.as_slice() {
+                  [_, mountpoint, _, options, ..] if options.split(',').any(|option| option == "ro") => Some(collect::unescape_mount_field(mountpoint)),
+                  _ => None,
+              }
+          })
+          .collect()
+}
+
+struct FilesystemUsage {
+    fs_type: String,
+    size_h: String,
+    used_h: String,
+    used_percent: String,
+    inodes_used_percent: Option<f64>,
+    /// Mounted read-only even though it's not configured so in fstab.
+    read_only: bool,
+    mountpoint: String,
+}
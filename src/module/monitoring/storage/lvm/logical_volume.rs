@@ -16,14 +16,28 @@ use crate::host::HostSetting;
 #[monitoring_module(
     name="storage-lvm-logical-volume",
     version="0.0.1",
-    description="Provides information about LVM logical volumes.",
+    description="Provides information about LVM logical volumes. For thin pools, data and metadata usage are shown.",
+    settings={
+        thin_data_warning_threshold => "Warning threshold for thin pool data usage in percent. Default: 80",
+        thin_data_critical_threshold => "Critical threshold for thin pool data usage in percent. Default: 90",
+        thin_metadata_warning_threshold => "Warning threshold for thin pool metadata usage in percent. Default: 75",
+        thin_metadata_critical_threshold => "Critical threshold for thin pool metadata usage in percent. Default: 90",
+    }
 )]
 pub struct LogicalVolume {
+    thin_data_threshold_warning: f32,
+    thin_data_threshold_critical: f32,
+    thin_metadata_threshold_warning: f32,
+    thin_metadata_threshold_critical: f32,
 }
 
 impl Module for LogicalVolume {
-    fn new(_settings: &HashMap<String, String>) -> Self {
+    fn new(settings: &HashMap<String, String>) -> Self {
         LogicalVolume {
+            thin_data_threshold_warning: settings.get("thin_data_warning_threshold").unwrap_or(&String::from("80")).parse().unwrap(),
+            thin_data_threshold_critical: settings.get("thin_data_critical_threshold").unwrap_or(&String::from("90")).parse().unwrap(),
+            thin_metadata_threshold_warning: settings.get("thin_metadata_warning_threshold").unwrap_or(&String::from("75")).parse().unwrap(),
+            thin_metadata_threshold_critical: settings.get("thin_metadata_critical_threshold").unwrap_or(&String::from("90")).parse().unwrap(),
         }
     }
 }
@@ -49,9 +63,12 @@ impl MonitoringModule for LogicalVolume {
 
         if host.platform.version_is_same_or_greater_than(platform_info::Flavor::Debian, "9") ||
            host.platform.version_is_same_or_greater_than(platform_info::Flavor::Ubuntu, "20") ||
-           host.platform.version_is_same_or_greater_than(platform_info::Flavor::CentOS, "8") {
+           host.platform.version_is_same_or_greater_than(platform_info::Flavor::CentOS, "8") ||
+           host.platform.version_is_same_or_greater_than(platform_info::Flavor::RedHat, "8") {
             command.arguments(vec![
-                "lvs", "--separator", "|", "--options", "lv_path,lv_name,vg_name,lv_size,lv_attr,sync_percent,raid_mismatch_count,snap_percent", "--units", "H"
+                "lvs", "--separator", "|", "--options",
+                "lv_path,lv_name,vg_name,lv_size,lv_attr,sync_percent,raid_mismatch_count,snap_percent,data_percent,metadata_percent,pool_lv,origin",
+                "--units", "H"
             ]);
 
             Ok(command.to_string())
@@ -83,21 +100,42 @@ impl MonitoringModule for LogicalVolume {
             let sync_percent = parts.next().unwrap().to_string();
             let raid_mismatch_count = parts.next().unwrap().to_string();
             let snapshot_full_percent = parts.next().unwrap().to_string();
-
+            let data_percent = parts.next().unwrap_or_default().to_string();
+            let metadata_percent = parts.next().unwrap_or_default().to_string();
+            let pool_lv = parts.next().unwrap_or_default().to_string();
+            let origin = parts.next().unwrap_or_default().to_string();
 
             let mut data_point = DataPoint::labeled_value(lv_name.clone(), String::from("OK"));
             data_point.description = format!("{} | size: {}", vg_name, lv_size);
 
-            match lv_attr.chars().nth(0).unwrap() {
+            let volume_type = lv_attr.chars().nth(0).unwrap();
+            match volume_type {
                 'r' => data_point.tags.push(String::from("RAID")),
                 'R' => data_point.tags.push(String::from("RAID")),
                 'm' => data_point.tags.push(String::from("Mirror")),
                 'M' => data_point.tags.push(String::from("Mirror")),
                 's' => data_point.tags.push(String::from("Snapshot")),
+                'S' => {
+                    data_point.tags.push(String::from("Snapshot"));
+                    data_point.tags.push(String::from("Merging"));
+                },
+                'O' => data_point.tags.push(String::from("Merging")),
                 'p' => data_point.tags.push(String::from("pvmove")),
+                't' => data_point.tags.push(String::from("Thin pool")),
+                'V' => {
+                    data_point.tags.push(String::from("Thin"));
+                    // Thin snapshots have an origin but no separate snapshot type.
+                    if !origin.is_empty() {
+                        data_point.tags.push(String::from("Snapshot"));
+                    }
+                },
                 _ => {}
             }
 
+            if !origin.is_empty() {
+                data_point.description = format!("{} | origin: {}", data_point.description, origin);
+            }
+
             match lv_attr.chars().nth(1).unwrap() {
                 'r' => data_point.tags.push(String::from("Read-only")),
                 _ => {}
@@ -135,7 +173,29 @@ impl MonitoringModule for LogicalVolume {
                     data_point.criticality = Criticality::Warning;
                 }
             }
-            else if !snapshot_full_percent.is_empty() {
+            else if volume_type == 't' && !data_percent.is_empty() {
+                data_point.value = format!("{}% data", data_percent);
+                let data_usage = data_percent.parse::<f32>().unwrap_or_default();
+                let metadata_usage = metadata_percent.parse::<f32>().unwrap_or_default();
+                data_point.description = format!("{} | metadata: {}%", data_point.description, metadata_percent);
+
+                let thin_criticality = if data_usage >= self.thin_data_threshold_critical || metadata_usage >= self.thin_metadata_threshold_critical {
+                    Criticality::Critical
+                }
+                else if data_usage >= self.thin_data_threshold_warning || metadata_usage >= self.thin_metadata_threshold_warning {
+                    Criticality::Warning
+                }
+                else {
+                    Criticality::Normal
+                };
+                data_point.criticality = data_point.criticality.max(thin_criticality);
+            }
+            else if volume_type == 'V' {
+                // Thin volumes can't run out of space themselves, the pool can.
+                data_point.description = format!("{} | pool: {} | {}% allocated", data_point.description, pool_lv, data_percent);
+            }
+            // snap_percent is an alias of data_percent in newer LVM versions, so it is also set for other volume types.
+            else if (volume_type == 's' || volume_type == 'S') && !snapshot_full_percent.is_empty() {
                 data_point.value = format!("{}% full", snapshot_full_percent);
                 let fullness = snapshot_full_percent.parse::<f32>().unwrap();
 
@@ -147,6 +207,8 @@ impl MonitoringModule for LogicalVolume {
                 }
             }
 
+            // Thin pools have no device node, so path is empty. LVM commands accept "vg/lv" too.
+            let lv_path = if lv_path.is_empty() { format!("{}/{}", vg_name, lv_name) } else { lv_path };
             data_point.command_params = vec![lv_path, vg_name, lv_name, lv_size];
             result.multivalue.push(data_point);
         }
@@ -49,7 +49,8 @@ impl MonitoringModule for PhysicalVolume {
 
         if host.platform.version_is_same_or_greater_than(platform_info::Flavor::Debian, "9") ||
            host.platform.version_is_same_or_greater_than(platform_info::Flavor::Ubuntu, "20") ||
-           host.platform.version_is_same_or_greater_than(platform_info::Flavor::CentOS, "8") {
+           host.platform.version_is_same_or_greater_than(platform_info::Flavor::CentOS, "8") ||
+           host.platform.version_is_same_or_greater_than(platform_info::Flavor::RedHat, "8") {
             command.arguments(vec!["pvs", "--separator", "|", "--options", "pv_name,pv_attr,pv_size,pv_free", "--units", "H"]);
             Ok(command.to_string())
         }
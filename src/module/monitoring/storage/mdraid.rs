@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use crate::enums::Criticality;
+use crate::module::connection::ResponseMessage;
+use crate::module::platform_info;
+use crate::{
+    Host,
+    frontend,
+    host::HostSetting,
+};
+use lightkeeper_module::monitoring_module;
+use crate::module::*;
+use crate::module::monitoring::*;
+use crate::utils::ShellCommand;
+
+#[monitoring_module(
+    name="storage-mdraid",
+    version="0.0.1",
+    description="Shows Linux software RAID (mdadm) arrays and their members. Progress is shown while an array is resyncing or rebuilding. Member details require root privileges.",
+)]
+pub struct MdRaid {
+}
+
+impl Module for MdRaid {
+    fn new(_settings: &HashMap<String, String>) -> Self {
+        MdRaid {
+        }
+    }
+}
+
+impl MonitoringModule for MdRaid {
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            display_style: frontend::DisplayStyle::ProgressBar,
+            display_text: String::from("Software RAID"),
+            category: String::from("storage"),
+            unit: String::from("%"),
+            use_multivalue: true,
+            ..Default::default()
+        }
+    }
+
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new("ssh", "0.0.1"))
+    }
+
+    fn get_connector_message(&self, host: Host, _result: DataPoint) -> Result<String, String> {
+        if host.platform.os != platform_info::OperatingSystem::Linux {
+            return Err(String::from("Unsupported platform"));
+        }
+
+        let mut detail = ShellCommand::new_from(vec!["mdadm", "--detail"]);
+        detail.use_sudo = host.settings.contains(&HostSetting::UseSudo);
+
+        // /proc/mdstat is missing if md driver isn't loaded.
+        Ok(format!("if [ -e /proc/mdstat ]; then cat /proc/mdstat; for array in $(awk '/^md/ {{print $1}}' /proc/mdstat); do {} \"/dev/$array\" < /dev/null; done; fi",
+                   detail.to_string()))
+    }
+
+    fn process_response(&self, _host: Host, response: ResponseMessage, _result: DataPoint) -> Result<DataPoint, String> {
+        let (arrays, details) = parse_output(&response.message);
+        if arrays.is_empty() && response.is_error() {
+            return Err(response.message);
+        }
+
+        let mut result = DataPoint::empty();
+        for array in arrays.iter() {
+            result.multivalue.push(array.to_data_point(details.get(&array.path())));
+        }
+
+        if result.multivalue.is_empty() {
+            result.multivalue.push(DataPoint::labeled_value("No arrays found", " "));
+        }
+
+        result.criticality = result.multivalue.iter().map(|data_point| data_point.criticality).max().unwrap_or(Criticality::Normal);
+        Ok(result)
+    }
+}
+
+/// Returns arrays from /proc/mdstat and output of `mdadm --detail` by array path.
+fn parse_output(output: &str) -> (Vec<MdStatArray>, HashMap<String, ArrayDetail>) {
+    let mut arrays = Vec::<MdStatArray>::new();
+    let mut details = HashMap::<String, ArrayDetail>::new();
+    let mut current_detail: Option<ArrayDetail> = None;
+    let mut in_member_table = false;
+
+    for line in output.lines() {
+        let trimmed = line.trim();
+
+        // Start of `mdadm --detail` output, e.g. "/dev/md0:".
+        if line.starts_with("/dev/") && line.ends_with(':') {
+            if let Some(detail) = current_detail.take() {
+                details.insert(detail.path.clone(), detail);
+            }
+            current_detail = Some(ArrayDetail { path: line.trim_end_matches(':').to_string(), ..Default::default() });
+            in_member_table = false;
+            continue;
+        }
+
+        if let Some(detail) = current_detail.as_mut() {
+            if trimmed.starts_with("Number") && trimmed.contains("RaidDevice") {
+                in_member_table = true;
+            }
+            else if in_member_table {
+                if let Some(member) = ArrayMember::from_detail_line(trimmed) {
+                    detail.members.push(member);
+                }
+            }
+            else if let Some((key, value)) = trimmed.split_once(" : ") {
+                match key.trim() {
+                    "State" => detail.state = value.trim().to_string(),
+                    "Raid Level" => detail.level = value.trim().to_string(),
+                    _ => {}
+                }
+            }
+            continue;
+        }
+
+        // /proc/mdstat, e.g. "md0 : active raid1 sdc1[2] sdb1[0](F)".
+        if line.starts_with("md") {
+            if let Some((name, rest)) = line.split_once(" : ") {
+                arrays.push(MdStatArray::from_line(name.trim(), rest));
+            }
+        }
+        else if let Some(array) = arrays.last_mut() {
+            array.parse_status_line(trimmed);
+        }
+    }
+
+    if let Some(detail) = current_detail.take() {
+        details.insert(detail.path.clone(), detail);
+    }
+
+    (arrays, details)
+}
+
+#[derive(Default)]
+struct MdStatArray {
+    name: String,
+    /// "active" or "inactive".
+    state: String,
+    level: String,
+    members: Vec<ArrayMember>,
+    /// E.g. [2/1] means 2 devices of which 1 is working.
+    devices_total: Option<u32>,
+    devices_working: Option<u32>,
+    /// Ongoing resync, recovery, reshape or check.
+    sync_action: Option<String>,
+    sync_progress: Option<f32>,
+    sync_finish: Option<String>,
+    sync_speed: Option<String>,
+}
+
+impl MdStatArray {
+    fn from_line(name: &str, rest: &str) -> Self {
+        let mut array = MdStatArray { name: name.to_string(), ..Default::default() };
+        let mut parts = rest.split_whitespace().peekable();
+
+        array.state = parts.next().unwrap_or_default().to_string();
+        if parts.peek() == Some(&"(read-only)") || parts.peek() == Some(&"(auto-read-only)") {
+            parts.next();
+        }
+        // Inactive arrays have no level.
+        if parts.peek().map(|part| !part.contains('[')).unwrap_or(false) {
+            array.level = parts.next().unwrap().to_string();
+        }
+
+        for part in parts {
+            // E.g. "sdb1[0](F)".
+            if let Some((device, flags)) = part.split_once('[') {
+                let state = if flags.ends_with("(F)") {
+                    "faulty"
+                }
+                else if flags.ends_with("(S)") {
+                    "spare"
+                }
+                else {
+                    "active"
+                };
+                array.members.push(ArrayMember::new(Some(format!("/dev/{}", device)), state));
+            }
+        }
+
+        array
+    }
+
+    fn parse_status_line(&mut self, line: &str) {
+        // E.g. "1046528 blocks super 1.2 [2/1] [U_]".
+        if let Some(counts) = line.split_whitespace().find(|part| part.starts_with('[') && part.contains('/')) {
+            if let Some((total, working)) = counts.trim_matches(|char| char == '[' || char == ']').split_once('/') {
+                self.devices_total = total.parse().ok();
+                self.devices_working = working.parse().ok();
+            }
+        }
+
+        // E.g. "[=>....]  recovery =  8.9% (93952/1046528) finish=0.5min speed=31317K/sec".
+        // Can also be "resync=DELAYED".
+        for action in ["resync", "recovery", "reshape", "check"] {
+            let status = match line.split_once(action) {
+                Some((_, status)) => status.trim_start(),
+                None => continue,
+            };
+
+            self.sync_action = Some(action.to_string());
+            if let Some(status) = status.strip_prefix('=') {
+                let mut parts = status.split_whitespace();
+                self.sync_progress = parts.next().and_then(|progress| progress.trim_end_matches('%').parse().ok());
+                for part in parts {
+                    if let Some(finish) = part.strip_prefix("finish=") {
+                        self.sync_finish = Some(finish.to_string());
+                    }
+                    else if let Some(speed) = part.strip_prefix("speed=") {
+                        self.sync_speed = Some(speed.to_string());
+                    }
+                }
+            }
+            break;
+        }
+    }
+
+    fn path(&self) -> String {
+        format!("/dev/{}", self.name)
+    }
+
+    fn is_degraded(&self) -> bool {
+        match (self.devices_total, self.devices_working) {
+            (Some(total), Some(working)) => working < total,
+            _ => false,
+        }
+    }
+
+    fn to_data_point(&self, detail: Option<&ArrayDetail>) -> DataPoint {
+        let state = match detail {
+            Some(detail) if !detail.state.is_empty() => detail.state.clone(),
+            _ if self.is_degraded() => format!("{}, degraded", self.state),
+            _ => self.state.clone(),
+        };
+        let level = detail.map(|detail| detail.level.clone()).filter(|level| !level.is_empty()).unwrap_or(self.level.clone());
+
+        let mut data_point = DataPoint::labeled_value(self.name.clone(), state.clone());
+        data_point.command_params = vec![self.path()];
+
+        let mut details = vec![level];
+        if let (Some(total), Some(working)) = (self.devices_total, self.devices_working) {
+            details.push(format!("{}/{} devices", working, total));
+        }
+        details.push(state.clone());
+
+        let state_words = state.split(',').map(|word| word.trim().to_lowercase()).collect::<Vec<_>>();
+        data_point.criticality = if state_words.iter().any(|word| word == "failed" || word == "inactive" || word == "degraded") || self.is_degraded() {
+            Criticality::Critical
+        }
+        else {
+            Criticality::Normal
+        };
+
+        if let Some(action) = self.sync_action.as_ref() {
+            match self.sync_progress {
+                Some(progress) => {
+                    // Displayed as a progress bar.
+                    data_point.value = format!("{:.0}", progress);
+                    details.push(format!("{} {:.1} %", action, progress));
+                },
+                None => details.push(format!("{} pending", action)),
+            }
+            if let Some(finish) = self.sync_finish.as_ref() {
+                details.push(format!("finish in {}", finish));
+            }
+            if let Some(speed) = self.sync_speed.as_ref() {
+                details.push(format!("speed {}", speed));
+            }
+
+            // Scheduled checks are normal, rebuilding means redundancy is (still) reduced.
+            if action != "check" {
+                data_point.criticality = data_point.criticality.max(Criticality::Warning);
+            }
+        }
+
+        data_point.description = details.into_iter().filter(|detail| !detail.is_empty()).collect::<Vec<_>>().join(" | ");
+
+        let members = match detail {
+            Some(detail) if !detail.members.is_empty() => &detail.members,
+            _ => &self.members,
+        };
+        for member in members.iter() {
+            data_point.multivalue.push(member.to_data_point(&self.path()));
+        }
+
+        data_point
+    }
+}
+
+#[derive(Default)]
+struct ArrayDetail {
+    path: String,
+    /// E.g. "clean, degraded, recovering".
+    state: String,
+    level: String,
+    members: Vec<ArrayMember>,
+}
+
+struct ArrayMember {
+    /// None for removed slots.
+    device: Option<String>,
+    /// E.g. "active sync", "spare rebuilding", "faulty" or "removed".
+    state: String,
+}
+
+impl ArrayMember {
+    fn new(device: Option<String>, state: &str) -> Self {
+        ArrayMember {
+            device: device,
+            state: state.to_string(),
+        }
+    }
+
+    /// Parses a row of the member table, e.g. "0  8  17  0  active sync  /dev/sdb1".
+    fn from_detail_line(line: &str) -> Option<Self> {
+        let parts = line.split_whitespace().collect::<Vec<_>>();
+        // Major and minor numbers are always present. Also skips e.g. error messages.
+        if parts.len() < 5 || parts[1].parse::<u32>().is_err() || parts[2].parse::<u32>().is_err() {
+            return None;
+        }
+
+        let (state, device) = match parts.last().filter(|part| part.starts_with("/dev/")) {
+            Some(device) => (parts[4..parts.len() - 1].join(" "), Some(device.to_string())),
+            None => (parts[4..].join(" "), None),
+        };
+        Some(ArrayMember::new(device, &state))
+    }
+
+    fn to_data_point(&self, array_path: &str) -> DataPoint {
+        let label = self.device.clone().unwrap_or(String::from("(missing)"));
+        let mut data_point = DataPoint::labeled_value(label, self.state.clone());
+        let state_words = self.state.split_whitespace().collect::<Vec<_>>();
+
+        data_point.criticality = if state_words.contains(&"faulty") || state_words.contains(&"removed") {
+            Criticality::Critical
+        }
+        else if state_words.contains(&"rebuilding") {
+            Criticality::Warning
+        }
+        else {
+            Criticality::Normal
+        };
+
+        // Used by the mdraid commands. Commands require all their tags, so removable members get a tag of their own.
+        if state_words.contains(&"faulty") {
+            data_point.tags.push(String::from("Faulty"));
+            data_point.tags.push(String::from("Removable"));
+        }
+        else if state_words.contains(&"spare") && !state_words.contains(&"rebuilding") {
+            data_point.tags.push(String::from("Spare"));
+            data_point.tags.push(String::from("Removable"));
+        }
+
+        match self.device.as_ref() {
+            Some(device) => data_point.command_params = vec![array_path.to_string(), device.clone()],
+            None => data_point.tags.push(String::from("Removed")),
+        }
+
+        data_point
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::HashMap;
+use serde_derive::Deserialize;
+use crate::enums::Criticality;
+use crate::module::connection::ResponseMessage;
+use crate::module::platform_info;
+use crate::{
+    Host,
+    frontend,
+    host::HostSetting,
+};
+use lightkeeper_module::monitoring_module;
+use crate::module::*;
+use crate::module::monitoring::*;
+use crate::utils::ShellCommand;
+
+const ATTRIBUTE_REALLOCATED_SECTORS: u32 = 5;
+const ATTRIBUTE_PENDING_SECTORS: u32 = 197;
+const ATTRIBUTE_OFFLINE_UNCORRECTABLE: u32 = 198;
+
+/// Bits of smartctl exit status.
+const EXIT_STATUS_OPEN_FAILED: i32 = 1 << 1;
+const EXIT_STATUS_DISK_FAILING: i32 = 1 << 3;
+const EXIT_STATUS_PREFAIL_THRESHOLD: i32 = 1 << 4;
+
+#[monitoring_module(
+    name="storage-smart",
+    version="0.0.1",
+    description="Shows disk health using smartctl from smartmontools. Requires root privileges.",
+    settings={
+        temperature_warning_threshold => "Warning threshold for temperature in Celsius. Default: 50",
+        temperature_critical_threshold => "Critical threshold for temperature in Celsius. Default: 60",
+        wear_warning_threshold => "Warning threshold for NVMe wear level (percentage used). Default: 80",
+        wear_critical_threshold => "Critical threshold for NVMe wear level (percentage used). Default: 95",
+    }
+)]
+pub struct Smart {
+    temperature_threshold_warning: i64,
+    temperature_threshold_critical: i64,
+    wear_threshold_warning: u64,
+    wear_threshold_critical: u64,
+}
+
+impl Module for Smart {
+    fn new(settings: &HashMap<String, String>) -> Self {
+        Smart {
+            temperature_threshold_warning: settings.get("temperature_warning_threshold").unwrap_or(&String::from("50")).parse().unwrap(),
+            temperature_threshold_critical: settings.get("temperature_critical_threshold").unwrap_or(&String::from("60")).parse().unwrap(),
+            wear_threshold_warning: settings.get("wear_warning_threshold").unwrap_or(&String::from("80")).parse().unwrap(),
+            wear_threshold_critical: settings.get("wear_critical_threshold").unwrap_or(&String::from("95")).parse().unwrap(),
+        }
+    }
+}
+
+impl MonitoringModule for Smart {
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            display_style: frontend::DisplayStyle::CriticalityLevel,
+            display_text: String::from("Disk health"),
+            category: String::from("storage"),
+            use_multivalue: true,
+            ..Default::default()
+        }
+    }
+
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new("ssh", "0.0.1"))
+    }
+
+    fn get_connector_message(&self, host: Host, _result: DataPoint) -> Result<String, String> {
+        // JSON output is supported since smartmontools 7.0.
+        if host.platform.version_is_same_or_greater_than(platform_info::Flavor::Debian, "11") ||
+           host.platform.version_is_same_or_greater_than(platform_info::Flavor::Ubuntu, "20") ||
+           host.platform.version_is_same_or_greater_than(platform_info::Flavor::CentOS, "8") {
+
+            let use_sudo = host.settings.contains(&HostSetting::UseSudo);
+            let mut scan = ShellCommand::new_from(vec!["smartctl", "--scan"]);
+            scan.use_sudo = use_sudo;
+            let mut smartctl = ShellCommand::new_from(vec!["smartctl", "--json", "-a"]);
+            smartctl.use_sudo = use_sudo;
+
+            // Empty output if smartmontools is not installed. It's usually in /usr/sbin, which is not always in PATH of normal users.
+            // Scan output is in format "/dev/sda -d sat # /dev/sda [SAT], ATA device".
+            // Stdin is closed so smartctl or sudo won't consume the device list.
+            Ok(format!("PATH=\"$PATH:/usr/sbin:/sbin\" command -v smartctl > /dev/null || exit 0; \
+                        {} | while read -r device _ type _; do {} -d \"$type\" \"$device\" < /dev/null; done",
+                       scan.to_string(), smartctl.to_string()))
+        }
+        else {
+            Err(String::from("Unsupported platform"))
+        }
+    }
+
+    fn process_response(&self, _host: Host, response: ResponseMessage, _result: DataPoint) -> Result<DataPoint, String> {
+        // Exit status of smartctl is non-zero also when disk has problems, so it is checked separately for each device.
+        let devices = serde_json::Deserializer::from_str(&response.message)
+                                              .into_iter::<SmartOutput>()
+                                              .collect::<Result<Vec<_>, _>>();
+
+        let devices = match devices {
+            Ok(devices) => devices,
+            // Probably sudo failed.
+            Err(_) if response.is_error() => return Err(response.message),
+            Err(error) => return Err(format!("Invalid smartctl output: {}", error)),
+        };
+
+        let mut result = DataPoint::empty();
+        for device in devices.iter().filter(|device| !device.device.name.is_empty()) {
+            result.multivalue.push(self.get_data_point(device));
+        }
+
+        if result.multivalue.is_empty() {
+            result.multivalue.push(DataPoint::labeled_value("No devices found", " "));
+        }
+
+        result.criticality = result.multivalue.iter().map(|data_point| data_point.criticality).max().unwrap_or(Criticality::Normal);
+        Ok(result)
+    }
+}
+
+impl Smart {
+    fn get_data_point(&self, device: &SmartOutput) -> DataPoint {
+        let mut data_point = DataPoint::labeled_value(device.device.name.clone(), String::new());
+        // Used by storage-smart-selftest.
+        data_point.command_params = vec![device.device.name.clone(), device.device.device_type.clone()];
+
+        let status = match device.smart_status.as_ref() {
+            Some(status) if device.smartctl.exit_status & EXIT_STATUS_OPEN_FAILED == 0 => status,
+            // E.g. virtual disks. Not shown as an error since there's nothing to fix.
+            _ => {
+                data_point.value = String::from("Unavailable");
+                data_point.description = device.smartctl.messages.iter().map(|message| message.string.clone()).collect::<Vec<_>>().join(" | ");
+                data_point.criticality = Criticality::Normal;
+                return data_point;
+            }
+        };
+
+        let mut details = Vec::new();
+        let mut criticalities = vec![Criticality::Normal];
+
+        if let Some(model) = device.model_name.as_ref() {
+            details.push(model.clone());
+        }
+
+        if !status.passed || device.smartctl.exit_status & (EXIT_STATUS_DISK_FAILING | EXIT_STATUS_PREFAIL_THRESHOLD) != 0 {
+            data_point.value = String::from("Failing");
+            criticalities.push(Criticality::Critical);
+        }
+        else {
+            data_point.value = String::from("Passed");
+        }
+
+        if let Some(temperature) = device.temperature.as_ref() {
+            details.push(format!("{} °C", temperature.current));
+            if temperature.current >= self.temperature_threshold_critical {
+                criticalities.push(Criticality::Critical);
+            }
+            else if temperature.current >= self.temperature_threshold_warning {
+                criticalities.push(Criticality::Warning);
+            }
+        }
+
+        if let Some(power_on_time) = device.power_on_time.as_ref() {
+            details.push(format!("{} h", power_on_time.hours));
+        }
+
+        if let Some(attributes) = device.ata_smart_attributes.as_ref() {
+            let raw_value = |id: u32| attributes.table.iter().find(|attribute| attribute.id == id).map(|attribute| attribute.raw.value);
+
+            if let Some(reallocated) = raw_value(ATTRIBUTE_REALLOCATED_SECTORS) {
+                details.push(format!("reallocated {}", reallocated));
+                if reallocated > 0 {
+                    criticalities.push(Criticality::Warning);
+                }
+            }
+
+            let pending = raw_value(ATTRIBUTE_PENDING_SECTORS).unwrap_or_default() + raw_value(ATTRIBUTE_OFFLINE_UNCORRECTABLE).unwrap_or_default();
+            details.push(format!("pending {}", pending));
+            if pending > 0 {
+                criticalities.push(Criticality::Warning);
+            }
+
+            for attribute in attributes.table.iter() {
+                match attribute.when_failed.as_str() {
+                    "now" => criticalities.push(Criticality::Critical),
+                    "past" => criticalities.push(Criticality::Warning),
+                    _ => {}
+                }
+            }
+        }
+
+        if let Some(health) = device.nvme_smart_health_information_log.as_ref() {
+            details.push(format!("wear {} %", health.percentage_used));
+            if health.critical_warning != 0 || health.percentage_used >= self.wear_threshold_critical {
+                criticalities.push(Criticality::Critical);
+            }
+            else if health.percentage_used >= self.wear_threshold_warning {
+                criticalities.push(Criticality::Warning);
+            }
+
+            if health.media_errors > 0 {
+                details.push(format!("media errors {}", health.media_errors));
+                criticalities.push(Criticality::Warning);
+            }
+        }
+
+        if device.is_self_test_running() {
+            details.push(String::from("self-test in progress"));
+            data_point.tags.push(String::from("Self-test running"));
+        }
+
+        data_point.description = details.join(" | ");
+        data_point.criticality = criticalities.into_iter().max().unwrap();
+        data_point
+    }
+}
+
+#[derive(Deserialize)]
+struct SmartOutput {
+    #[serde(default)]
+    smartctl: SmartctlInfo,
+    #[serde(default)]
+    device: DeviceInfo,
+    model_name: Option<String>,
+    smart_status: Option<SmartStatus>,
+    temperature: Option<Temperature>,
+    power_on_time: Option<PowerOnTime>,
+    ata_smart_data: Option<AtaSmartData>,
+    ata_smart_attributes: Option<AtaSmartAttributes>,
+    nvme_smart_health_information_log: Option<NvmeHealthLog>,
+    nvme_self_test_log: Option<NvmeSelfTestLog>,
+}
+
+impl SmartOutput {
+    fn is_self_test_running(&self) -> bool {
+        let ata_running = self.ata_smart_data.as_ref().and_then(|data| data.self_test.as_ref())
+                                             .map(|self_test| self_test.status.remaining_percent.is_some())
+                                             .unwrap_or(false);
+        let nvme_running = self.nvme_self_test_log.as_ref().and_then(|log| log.current_self_test_operation.as_ref())
+                                                 .map(|operation| operation.value != 0)
+                                                 .unwrap_or(false);
+        ata_running || nvme_running
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct SmartctlInfo {
+    #[serde(default)]
+    exit_status: i32,
+    #[serde(default)]
+    messages: Vec<SmartctlMessage>,
+}
+
+#[derive(Deserialize)]
+struct SmartctlMessage {
+    string: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct DeviceInfo {
+    name: String,
+    #[serde(rename = "type")]
+    device_type: String,
+}
+
+#[derive(Deserialize)]
+struct SmartStatus {
+    passed: bool,
+}
+
+#[derive(Deserialize)]
+struct Temperature {
+    current: i64,
+}
+
+#[derive(Deserialize)]
+struct PowerOnTime {
+    hours: u64,
+}
+
+#[derive(Deserialize)]
+struct AtaSmartData {
+    self_test: Option<AtaSelfTest>,
+}
+
+#[derive(Deserialize)]
+struct AtaSelfTest {
+    status: AtaSelfTestStatus,
+}
+
+#[derive(Deserialize)]
+struct AtaSelfTestStatus {
+    /// Only present while a test is running.
+    remaining_percent: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct AtaSmartAttributes {
+    table: Vec<AtaAttribute>,
+}
+
+#[derive(Deserialize)]
+struct AtaAttribute {
+    id: u32,
+    /// "now", "past" or empty.
+    #[serde(default)]
+    when_failed: String,
+    raw: AtaRawValue,
+}
+
+#[derive(Deserialize)]
+struct AtaRawValue {
+    value: u64,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct NvmeHealthLog {
+    critical_warning: u64,
+    percentage_used: u64,
+    media_errors: u64,
+}
+
+#[derive(Deserialize)]
+struct NvmeSelfTestLog {
+    current_self_test_operation: Option<NvmeSelfTestOperation>,
+}
+
+#[derive(Deserialize)]
+struct NvmeSelfTestOperation {
+    value: u32,
+}
@@ -0,0 +1,5 @@
+pub mod pool;
+pub use pool::Pool;
+
+pub mod dataset;
+pub use dataset::Dataset;
\ No newline at end of file
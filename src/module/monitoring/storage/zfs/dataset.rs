@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use crate::enums::Criticality;
+use crate::module::connection::ResponseMessage;
+use crate::{
+    Host,
+    frontend,
+    utils::string_manipulation,
+};
+
+use lightkeeper_module::monitoring_module;
+use crate::module::*;
+use crate::module::monitoring::*;
+use crate::utils::ShellCommand;
+use crate::host::HostSetting;
+
+#[monitoring_module(
+    name="storage-zfs-dataset",
+    version="0.0.1",
+    description="Provides information about ZFS filesystems and volumes. Usage is relative to quota if one is set.",
+    settings={
+        ignored_datasets => "Comma-separated list of dataset name prefixes to ignore. Default: none",
+        warning_threshold => "Warning threshold in percent. Default: 80",
+        error_threshold => "Error threshold in percent. Default: 90",
+        critical_threshold => "Critical threshold in percent. Default: 95",
+    }
+)]
+pub struct Dataset {
+    ignored_datasets: Vec<String>,
+    threshold_critical: f64,
+    threshold_error: f64,
+    threshold_warning: f64,
+}
+
+impl Module for Dataset {
+    fn new(settings: &HashMap<String, String>) -> Self {
+        Dataset {
+            ignored_datasets: settings.get("ignored_datasets").unwrap_or(&String::new())
+                                      .split(',').map(|dataset| dataset.trim().to_string())
+                                      .filter(|dataset| !dataset.is_empty())
+                                      .collect(),
+            threshold_critical: settings.get("critical_threshold").unwrap_or(&String::from("95")).parse().unwrap(),
+            threshold_error: settings.get("error_threshold").unwrap_or(&String::from("90")).parse().unwrap(),
+            threshold_warning: settings.get("warning_threshold").unwrap_or(&String::from("80")).parse().unwrap(),
+        }
+    }
+}
+
+impl MonitoringModule for Dataset {
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            display_style: frontend::DisplayStyle::ProgressBar,
+            display_text: String::from("ZFS datasets"),
+            category: String::from("storage"),
+            unit: String::from("%"),
+            use_multivalue: true,
+            ..Default::default()
+        }
+    }
+
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new("ssh", "0.0.1"))
+    }
+
+    fn get_connector_message(&self, host: Host, _result: DataPoint) -> Result<String, String> {
+        if host.platform.os != platform_info::OperatingSystem::Linux {
+            return Err(String::from("Unsupported platform"));
+        }
+
+        let mut command = ShellCommand::new();
+        command.use_sudo = host.settings.contains(&HostSetting::UseSudo);
+        command.arguments(vec![
+            "zfs", "list", "-Hp", "-t", "filesystem,volume", "-o", "name,used,avail,refer,quota,refquota,compressratio,mountpoint"
+        ]);
+        Ok(command.to_string())
+    }
+
+    fn process_response(&self, _host: Host, response: ResponseMessage, _result: DataPoint) -> Result<DataPoint, String> {
+        // Command does not exist.
+        if response.return_code == 127 {
+            return Ok(DataPoint::empty());
+        }
+        else if response.is_error() {
+            return Err(response.message);
+        }
+
+        let mut result = DataPoint::empty();
+
+        for line in response.message.lines().filter(|line| !line.trim().is_empty()) {
+            let parts = line.split('\t').collect::<Vec<_>>();
+            if parts.len() < 8 {
+                return Err(format!("Invalid zfs list output: {}", line));
+            }
+
+            let name = parts[0].to_string();
+            if self.ignored_datasets.iter().any(|prefix| name.starts_with(prefix)) {
+                continue;
+            }
+
+            // Quotas are "0" or "-" if not set.
+            let value = |index: usize| parts[index].parse::<u64>().unwrap_or_default();
+            let (used, available, referenced, quota, refquota) = (value(1), value(2), value(3), value(4), value(5));
+            let compression_ratio = parts[6].trim_end_matches('x');
+            let mountpoint = parts[7];
+
+            let used_percent = if quota > 0 || refquota > 0 {
+                let quota_percent = if quota > 0 { used as f64 / quota as f64 * 100.0 } else { 0.0 };
+                let refquota_percent = if refquota > 0 { referenced as f64 / refquota as f64 * 100.0 } else { 0.0 };
+                quota_percent.max(refquota_percent)
+            }
+            else {
+                used as f64 / (used + available).max(1) as f64 * 100.0
+            };
+
+            // Children are shown under the top-level dataset of the pool.
+            let label = match name.split_once('/') {
+                Some((_pool, child_name)) => child_name.to_string(),
+                None => name.clone(),
+            };
+
+            let mut data_point = DataPoint::labeled_value(label, format!("{:.0}", used_percent));
+            let mut details = vec![
+                format!("{} used", string_manipulation::format_bytes_human(used)),
+                format!("{} available", string_manipulation::format_bytes_human(available)),
+            ];
+            if quota > 0 {
+                details.push(format!("quota {}", string_manipulation::format_bytes_human(quota)));
+            }
+            if refquota > 0 {
+                details.push(format!("refquota {}", string_manipulation::format_bytes_human(refquota)));
+            }
+            details.push(format!("compression {}x", compression_ratio));
+            if mountpoint != "-" && mountpoint != "none" {
+                details.push(mountpoint.to_string());
+            }
+            data_point.description = details.join(" | ");
+
+            data_point.criticality = if used_percent >= self.threshold_critical {
+                Criticality::Critical
+            }
+            else if used_percent >= self.threshold_error {
+                Criticality::Error
+            }
+            else if used_percent >= self.threshold_warning {
+                Criticality::Warning
+            }
+            else {
+                Criticality::Normal
+            };
+
+            data_point.command_params = vec![name.clone()];
+
+            let pool_name = name.split('/').next().unwrap().to_string();
+            match result.multivalue.iter_mut().find(|pool_point| pool_point.command_params.first() == Some(&pool_name)) {
+                Some(pool_point) if pool_name != name => pool_point.multivalue.push(data_point),
+                _ => result.multivalue.push(data_point),
+            }
+        }
+
+        if result.multivalue.is_empty() {
+            result.multivalue.push(DataPoint::labeled_value("No datasets found", " "));
+        }
+
+        result.criticality = result.multivalue.iter().flat_map(|data_point| std::iter::once(data_point).chain(data_point.multivalue.iter()))
+                                                     .map(|data_point| data_point.criticality)
+                                                     .max().unwrap_or(Criticality::Normal);
+        Ok(result)
+    }
+}
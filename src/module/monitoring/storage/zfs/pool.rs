@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use crate::enums::Criticality;
+use crate::module::connection::ResponseMessage;
+use crate::{
+    Host,
+    frontend,
+    utils::string_manipulation,
+};
+
+use lightkeeper_module::monitoring_module;
+use crate::module::*;
+use crate::module::monitoring::*;
+use crate::utils::ShellCommand;
+use crate::host::HostSetting;
+
+#[monitoring_module(
+    name="storage-zfs-pool",
+    version="0.0.1",
+    description="Provides information about ZFS pools: health, capacity, fragmentation, scrub status, errors and devices.",
+    settings={
+        warning_threshold => "Warning threshold for pool capacity in percent. Default: 80",
+        critical_threshold => "Critical threshold for pool capacity in percent. Default: 90",
+    }
+)]
+pub struct Pool {
+    threshold_warning: f64,
+    threshold_critical: f64,
+}
+
+impl Module for Pool {
+    fn new(settings: &HashMap<String, String>) -> Self {
+        Pool {
+            threshold_warning: settings.get("warning_threshold").unwrap_or(&String::from("80")).parse().unwrap(),
+            threshold_critical: settings.get("critical_threshold").unwrap_or(&String::from("90")).parse().unwrap(),
+        }
+    }
+}
+
+impl MonitoringModule for Pool {
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            display_style: frontend::DisplayStyle::ProgressBar,
+            display_text: String::from("ZFS pools"),
+            category: String::from("storage"),
+            unit: String::from("%"),
+            use_multivalue: true,
+            ..Default::default()
+        }
+    }
+
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        Some(ModuleSpecification::new("ssh", "0.0.1"))
+    }
+
+    fn get_connector_messages(&self, host: Host, _result: DataPoint) -> Result<Vec<String>, String> {
+        if host.platform.os != platform_info::OperatingSystem::Linux {
+            return Err(String::from("Unsupported platform"));
+        }
+
+        let mut list = ShellCommand::new();
+        list.use_sudo = host.settings.contains(&HostSetting::UseSudo);
+        list.arguments(vec!["zpool", "list", "-Hp", "-o", "name,size,allocated,free,fragmentation,capacity,health"]);
+
+        // Full status is used instead of `zpool status -x` since scrub status is wanted for healthy pools too.
+        let mut status = ShellCommand::new();
+        status.use_sudo = host.settings.contains(&HostSetting::UseSudo);
+        status.arguments(vec!["zpool", "status"]);
+
+        Ok(vec![list.to_string(), status.to_string()])
+    }
+
+    fn process_responses(&self, _host: Host, responses: Vec<ResponseMessage>, _result: DataPoint) -> Result<DataPoint, String> {
+        let (list_response, status_response) = match responses.as_slice() {
+            [list_response, status_response] => (list_response, status_response),
+            _ => return Err(String::from("Invalid amount of responses")),
+        };
+
+        // Command does not exist.
+        if list_response.return_code == 127 {
+            return Ok(DataPoint::empty());
+        }
+        else if list_response.is_error() {
+            return Err(list_response.message.clone());
+        }
+
+        let statuses = parse_status(&status_response.message);
+        let mut result = DataPoint::empty();
+
+        for line in list_response.message.lines().filter(|line| !line.trim().is_empty()) {
+            let parts = line.split('\t').collect::<Vec<_>>();
+            if parts.len() < 7 {
+                return Err(format!("Invalid zpool list output: {}", line));
+            }
+
+            let name = parts[0].to_string();
+            let size = parts[1].parse::<u64>().unwrap_or_default();
+            let allocated = parts[2].parse::<u64>().unwrap_or_default();
+            let fragmentation = parts[4];
+            let capacity = parts[5].parse::<f64>().unwrap_or_default();
+            let health = parts[6].to_string();
+
+            let mut data_point = DataPoint::labeled_value(name.clone(), format!("{:.0}", capacity));
+            let mut details = vec![
+                health.clone(),
+                format!("{} / {} used", string_manipulation::format_bytes_human(allocated), string_manipulation::format_bytes_human(size)),
+            ];
+            if fragmentation != "-" {
+                details.push(format!("fragmentation {} %", fragmentation));
+            }
+
+            let mut criticalities = vec![get_state_criticality(&health)];
+            if capacity >= self.threshold_critical {
+                criticalities.push(Criticality::Critical);
+            }
+            else if capacity >= self.threshold_warning {
+                criticalities.push(Criticality::Warning);
+            }
+
+            if let Some(status) = statuses.get(&name) {
+                if !status.scan.is_empty() {
+                    details.push(status.scan.clone());
+                }
+
+                if status.scan.starts_with("scrub in progress") {
+                    data_point.tags.push(String::from("Scrubbing"));
+                }
+                else if status.scan.starts_with("resilver in progress") {
+                    data_point.tags.push(String::from("Resilvering"));
+                    criticalities.push(Criticality::Warning);
+                }
+
+                if !status.errors.is_empty() && status.errors != "No known data errors" {
+                    details.push(status.errors.clone());
+                    criticalities.push(Criticality::Critical);
+                }
+
+                for device in status.devices.iter() {
+                    let mut device_point = DataPoint::labeled_value(format!("{}{}", "  ".repeat(device.depth), device.name), device.state.clone());
+                    device_point.criticality = get_state_criticality(&device.state);
+                    if device.has_errors() {
+                        device_point.description = format!("read {} | write {} | checksum {}", device.read_errors, device.write_errors, device.checksum_errors);
+                        device_point.criticality = device_point.criticality.max(Criticality::Warning);
+                    }
+                    device_point.command_params = vec![name.clone(), device.name.clone()];
+
+                    criticalities.push(device_point.criticality);
+                    data_point.multivalue.push(device_point);
+                }
+            }
+
+            data_point.description = details.join(" | ");
+            data_point.criticality = criticalities.into_iter().max().unwrap();
+            data_point.command_params = vec![name];
+            result.multivalue.push(data_point);
+        }
+
+        if result.multivalue.is_empty() {
+            result.multivalue.push(DataPoint::labeled_value("No pools found", " "));
+        }
+
+        result.criticality = result.multivalue.iter().map(|data_point| data_point.criticality).max().unwrap_or(Criticality::Normal);
+        result.set_cache_state(&responses);
+        Ok(result)
+    }
+}
+
+/// For pools and devices.
+fn get_state_criticality(state: &str) -> Criticality {
+    match state {
+        "ONLINE" | "AVAIL" | "INUSE" => Criticality::Normal,
+        "DEGRADED" | "OFFLINE" => Criticality::Error,
+        _ => Criticality::Critical,
+    }
+}
+
+#[derive(Default)]
+struct PoolStatus {
+    /// E.g. "scrub repaired 0B in 00:00:01 with 0 errors on Sun Oct 19 10:00:00 2025".
+    scan: String,
+    errors: String,
+    devices: Vec<PoolDevice>,
+}
+
+struct PoolDevice {
+    name: String,
+    state: String,
+    /// Depth in the device tree. Vdevs have depth 0.
+    depth: usize,
+    read_errors: String,
+    write_errors: String,
+    checksum_errors: String,
+}
+
+impl PoolDevice {
+    fn has_errors(&self) -> bool {
+        [&self.read_errors, &self.write_errors, &self.checksum_errors].iter().any(|count| !count.is_empty() && count.as_str() != "0")
+    }
+}
+
+/// Parses `zpool status` output. Returns statuses by pool name.
+fn parse_status(output: &str) -> HashMap<String, PoolStatus> {
+    let mut statuses = HashMap::new();
+    let mut pool_name = String::new();
+    let mut status = PoolStatus::default();
+    let mut in_config = false;
+    let mut in_scan = false;
+
+    for line in output.lines() {
+        let trimmed = line.trim();
+
+        if let Some((key, value)) = trimmed.split_once(':').filter(|(key, _)| !key.contains(char::is_whitespace) && !line.starts_with('\t')) {
+            let value = value.trim().to_string();
+            in_scan = false;
+
+            match key {
+                "pool" => {
+                    if !pool_name.is_empty() {
+                        statuses.insert(pool_name.clone(), std::mem::take(&mut status));
+                    }
+                    pool_name = value;
+                    in_config = false;
+                },
+                "scan" => {
+                    status.scan = value;
+                    in_scan = true;
+                },
+                "config" => in_config = true,
+                "errors" => {
+                    status.errors = value;
+                    in_config = false;
+                },
+                _ => {}
+            }
+            continue;
+        }
+
+        if in_scan && trimmed.contains("% done") {
+            // E.g. "0B repaired, 4.88% done, 00:03:00 to go".
+            let progress = trimmed.split(", ").filter(|part| !part.ends_with("repaired")).collect::<Vec<_>>().join(", ");
+            status.scan = format!("{}, {}", status.scan.split(" since ").next().unwrap_or_default(), progress);
+        }
+        else if in_config {
+            let parts = trimmed.split_whitespace().collect::<Vec<_>>();
+            // Skips header, pool itself and section names like "logs" or "spares".
+            if parts.len() < 2 || parts[0] == "NAME" || parts[0] == pool_name {
+                continue;
+            }
+
+            // Config lines start with a tab and are indented by 2 spaces per level.
+            let indent = line.trim_start_matches('\t').len() - line.trim_start_matches('\t').trim_start().len();
+            status.devices.push(PoolDevice {
+                name: parts[0].to_string(),
+                state: parts[1].to_string(),
+                depth: (indent / 2).saturating_sub(1),
+                read_errors: parts.get(2).unwrap_or(&"").to_string(),
+                write_errors: parts.get(3).unwrap_or(&"").to_string(),
+                checksum_errors: parts.get(4).unwrap_or(&"").to_string(),
+            });
+        }
+    }
+
+    if !pool_name.is_empty() {
+        statuses.insert(pool_name, status);
+    }
+
+    statuses
+}
@@ -17,6 +17,10 @@ pub struct PlatformInfo {
     pub os_flavor: Flavor,
 
     pub architecture: Architecture,
+
+    /// Report version of lightkeeper-agent if it's installed on the host, otherwise 0.
+    #[serde(default)]
+    pub agent_report_version: u32,
 }
 
 impl PlatformInfo {
@@ -28,6 +32,11 @@ impl PlatformInfo {
         self.os != OperatingSystem::Unknown
     }
 
+    /// Agent is only used if its report format is the one this version understands.
+    pub fn has_agent(&self) -> bool {
+        self.agent_report_version == lightkeeper_agent::REPORT_VERSION
+    }
+
     // Version is given as str for convenience.
     pub fn version_is_same_or_greater_than(&self, flavor: Flavor, version: &str) -> bool {
         let parsed_version = VersionNumber::from_str(version).unwrap();
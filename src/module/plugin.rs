@@ -0,0 +1,88 @@
+pub mod plugin_monitor;
+pub use plugin_monitor::PluginMonitor;
+
+pub mod plugin_command;
+pub use plugin_command::PluginCommand;
+
+pub mod plugin_connector;
+pub use plugin_connector::PluginConnector;
+
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use lightkeeper_plugin::{ModuleManifest, PluginLibrary};
+use serde_json::Value;
+
+use crate::frontend;
+use crate::module::{Metadata, ModuleSpecification};
+
+/// File extension of plugin libraries.
+const PLUGIN_EXTENSION: &str = "so";
+
+
+/// Loads all plugin libraries in the directory. Incompatible or broken plugins are skipped with an error.
+pub fn load_plugins(plugins_dir: &Path) -> Vec<Arc<PluginLibrary>> {
+    let entries = match fs::read_dir(plugins_dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut paths = entries.filter_map(|entry| entry.ok())
+                           .map(|entry| entry.path())
+                           .filter(|path| path.extension().map(|extension| extension == PLUGIN_EXTENSION).unwrap_or(false))
+                           .collect::<Vec<_>>();
+    paths.sort();
+
+    paths.iter().filter_map(|path| {
+        match PluginLibrary::load(path) {
+            Ok(library) => {
+                log::info!("Loaded plugin {} {} from {}", library.manifest.name, library.manifest.version, path.display());
+                Some(library)
+            },
+            Err(error) => {
+                log::error!("Skipping plugin {}: {}", path.display(), error);
+                None
+            }
+        }
+    }).collect()
+}
+
+pub fn get_metadata(manifest: &ModuleManifest) -> Metadata {
+    Metadata {
+        module_spec: ModuleSpecification::new_with_type(&manifest.id, &manifest.version, &manifest.module_type),
+        description: manifest.description.clone(),
+        settings: manifest.settings.clone(),
+        parent_module: None,
+        is_stateless: !manifest.stateful,
+        cache_scope: crate::cache::CacheScope::Host,
+        cache_sensitivity: crate::cache::CacheSensitivity::Normal,
+    }
+}
+
+pub fn get_connector_spec(manifest: &ModuleManifest) -> Option<ModuleSpecification> {
+    manifest.connector.as_ref().map(|connector_id| ModuleSpecification::new(connector_id, "0.0.1"))
+}
+
+/// Plugin returns only the options it wants to change from the defaults, with the same field names as DisplayOptions.
+pub fn get_display_options(instance: &lightkeeper_plugin::ModuleInstance, defaults: frontend::DisplayOptions) -> Result<frontend::DisplayOptions, String> {
+    let overrides = match instance.call("get_display_options", &Value::Null) {
+        Ok(Value::Object(overrides)) => overrides,
+        Ok(_) => return Err(String::from("get_display_options has to return an object")),
+        Err(error) if error.is_empty() => return Ok(defaults),
+        Err(error) => return Err(error),
+    };
+
+    let mut options = serde_json::to_value(defaults).map_err(|error| error.to_string())?;
+    for (key, value) in overrides.into_iter() {
+        match options.get_mut(&key) {
+            Some(option) => *option = value,
+            None => return Err(format!("Unknown display option: {}", key)),
+        }
+    }
+
+    let display_options = serde_json::from_value::<frontend::DisplayOptions>(options)
+                                     .map_err(|error| format!("Invalid display options: {}", error))?;
+    display_options.validate()?;
+    Ok(display_options)
+}
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use lightkeeper_plugin::{ModuleInstance, ModuleManifest, PluginLibrary};
+use serde_json::json;
+
+use crate::enums::Criticality;
+use crate::module::connection::ResponseMessage;
+use crate::{
+    Host,
+    frontend,
+};
+use crate::module::*;
+use crate::module::command::*;
+
+
+/// Command module provided by a native plugin. Hooks are called with JSON-serialized arguments.
+#[derive(Clone)]
+pub struct PluginCommand {
+    instance: Option<Arc<ModuleInstance>>,
+    metadata: Metadata,
+    connector_spec: Option<ModuleSpecification>,
+    display_options: frontend::DisplayOptions,
+}
+
+impl PluginCommand {
+    pub fn new_plugin(library: &Arc<PluginLibrary>, manifest: &ModuleManifest, settings: &HashMap<String, String>) -> Result<Self, String> {
+        let instance = library.create_module(&manifest.module_type, &manifest.id, settings)?;
+        let display_options = super::get_display_options(&instance, Self::default_display_options(manifest))?;
+
+        Ok(PluginCommand {
+            instance: Some(Arc::new(instance)),
+            metadata: super::get_metadata(manifest),
+            connector_spec: super::get_connector_spec(manifest),
+            display_options: display_options,
+        })
+    }
+
+    /// Module that returns the error when executed. Used if the plugin fails to create the module.
+    pub fn new_failed(manifest: &ModuleManifest, error: String) -> Self {
+        log::error!("Plugin module {}: {}", manifest.id, error);
+        PluginCommand {
+            instance: None,
+            metadata: super::get_metadata(manifest),
+            connector_spec: None,
+            display_options: Self::default_display_options(manifest),
+        }
+    }
+
+    fn default_display_options(manifest: &ModuleManifest) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            display_style: frontend::DisplayStyle::Text,
+            display_text: manifest.id.clone(),
+            category: String::from("custom"),
+            ..Default::default()
+        }
+    }
+
+    fn get_instance(&self) -> Result<&ModuleInstance, String> {
+        self.instance.as_deref().ok_or(format!("Plugin module {} is not available", self.metadata.module_spec.id))
+    }
+}
+
+impl Module for PluginCommand {
+    /// Plugin modules are created through ModuleFactory. This creates an instance without a plugin.
+    fn new(_settings: &HashMap<String, String>) -> Self {
+        PluginCommand {
+            instance: None,
+            metadata: Self::get_metadata(),
+            connector_spec: None,
+            display_options: Default::default(),
+        }
+    }
+}
+
+impl MetadataSupport for PluginCommand {
+    fn get_metadata() -> Metadata {
+        Metadata {
+            module_spec: ModuleSpecification::new_with_type("plugin", "0.0.1", "command"),
+            description: String::new(),
+            settings: HashMap::new(),
+            parent_module: None,
+            is_stateless: true,
+            cache_scope: crate::cache::CacheScope::Host,
+            cache_sensitivity: crate::cache::CacheSensitivity::Normal,
+        }
+    }
+
+    fn get_metadata_self(&self) -> Metadata {
+        self.metadata.clone()
+    }
+
+    fn get_module_spec(&self) -> ModuleSpecification {
+        self.metadata.module_spec.clone()
+    }
+}
+
+impl BoxCloneableCommand for PluginCommand {
+    fn box_clone(&self) -> Command {
+        Box::new(self.clone())
+    }
+}
+
+impl CommandModule for PluginCommand {
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        self.connector_spec.clone()
+    }
+
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        self.display_options.clone()
+    }
+
+    fn get_connector_messages(&self, host: Host, parameters: Vec<String>) -> Result<Vec<String>, String> {
+        let result = self.get_instance()?.call("get_connector_messages", &json!({ "host": host, "parameters": parameters }))?;
+        serde_json::from_value(result).map_err(|error| format!("Invalid connector messages from plugin: {}", error))
+    }
+
+    /// Plugin returns an object with `message` and optionally `criticality`.
+    fn process_responses(&self, host: Host, responses: Vec<ResponseMessage>) -> Result<CommandResult, String> {
+        let result = self.get_instance()?.call("process_responses", &json!({ "host": host, "responses": responses }))?;
+        let message = result.get("message").and_then(|message| message.as_str()).unwrap_or_default();
+        let criticality = result.get("criticality").and_then(|criticality| criticality.as_str()).unwrap_or("Info");
+
+        match criticality.parse::<Criticality>() {
+            Ok(Criticality::Warning) => Ok(CommandResult::new_warning(message)),
+            Ok(Criticality::Error) => Ok(CommandResult::new_error(message)),
+            Ok(Criticality::Critical) => Ok(CommandResult::new_critical_error(message)),
+            Ok(_) => Ok(CommandResult::new_info(message)),
+            Err(_) => Err(format!("Invalid criticality from plugin: {}", criticality)),
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use lightkeeper_plugin::{ModuleInstance, ModuleManifest, PluginLibrary};
+use serde_json::{json, Value};
+
+use crate::module::*;
+use crate::module::connection::*;
+
+
+/// Connector module provided by a native plugin.
+pub struct PluginConnector {
+    instance: Option<ModuleInstance>,
+    metadata: Metadata,
+}
+
+impl PluginConnector {
+    pub fn new_plugin(library: &Arc<PluginLibrary>, manifest: &ModuleManifest, settings: &HashMap<String, String>) -> Result<Self, String> {
+        Ok(PluginConnector {
+            instance: Some(library.create_module(&manifest.module_type, &manifest.id, settings)?),
+            metadata: super::get_metadata(manifest),
+        })
+    }
+
+    /// Connector that returns the error for every message. Used if the plugin fails to create the module.
+    pub fn new_failed(manifest: &ModuleManifest, error: String) -> Self {
+        log::error!("Plugin module {}: {}", manifest.id, error);
+        PluginConnector {
+            instance: None,
+            metadata: super::get_metadata(manifest),
+        }
+    }
+
+    /// Hooks that aren't implemented by the plugin return Ok(Value::Null).
+    fn call(&self, hook: &str, arguments: Value) -> Result<Value, String> {
+        let instance = self.instance.as_ref().ok_or(format!("Plugin module {} is not available", self.metadata.module_spec.id))?;
+        match instance.call(hook, &arguments) {
+            Err(error) if error.is_empty() => Ok(Value::Null),
+            result => result,
+        }
+    }
+}
+
+impl Module for PluginConnector {
+    /// Plugin modules are created through ModuleFactory. This creates an instance without a plugin.
+    fn new(_settings: &HashMap<String, String>) -> Self {
+        PluginConnector {
+            instance: None,
+            metadata: Self::get_metadata(),
+        }
+    }
+}
+
+impl MetadataSupport for PluginConnector {
+    fn get_metadata() -> Metadata {
+        Metadata {
+            module_spec: ModuleSpecification::new_with_type("plugin", "0.0.1", "connector"),
+            description: String::new(),
+            settings: HashMap::new(),
+            parent_module: None,
+            is_stateless: true,
+            cache_scope: crate::cache::CacheScope::Host,
+            cache_sensitivity: crate::cache::CacheSensitivity::Normal,
+        }
+    }
+
+    fn get_metadata_self(&self) -> Metadata {
+        self.metadata.clone()
+    }
+
+    fn get_module_spec(&self) -> ModuleSpecification {
+        self.metadata.module_spec.clone()
+    }
+}
+
+impl ConnectionModule for PluginConnector {
+    /// Plugin returns an object with `message` and `return_code`.
+    fn send_message(&mut self, message: &str) -> Result<ResponseMessage, String> {
+        let result = self.call("send_message", json!({ "message": message }))?;
+        serde_json::from_value::<ResponseMessage>(result).map_err(|error| format!("Invalid response from plugin: {}", error))
+    }
+
+    fn is_connected(&self) -> bool {
+        self.call("is_connected", Value::Null).ok().and_then(|result| result.as_bool()).unwrap_or(false)
+    }
+
+    fn connect(&mut self, address: &IpAddr) -> Result<(), String> {
+        self.call("connect", json!({ "address": address.to_string() })).map(|_| ())
+    }
+
+    fn disconnect(&mut self) {
+        if let Err(error) = self.call("disconnect", Value::Null) {
+            log::error!("Error while disconnecting: {}", error);
+        }
+    }
+
+    fn reconnect(&mut self) -> Result<(), String> {
+        self.call("reconnect", Value::Null).map(|_| ())
+    }
+}
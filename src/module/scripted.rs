@@ -0,0 +1,8 @@
+pub mod runtime;
+pub use runtime::ScriptRuntime;
+
+pub mod scripted_monitor;
+pub use scripted_monitor::ScriptedMonitor;
+
+pub mod scripted_command;
+pub use scripted_command::ScriptedCommand;
@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use regex::Regex;
+use rhai::{Array, CallFnOptions, Dynamic, Engine, FuncArgs, Map, Scope, AST};
+
+use crate::cache::CacheSensitivity;
+use crate::enums::Criticality;
+use crate::frontend;
+use crate::host::{Host, HostSetting};
+use crate::module::connection::ResponseMessage;
+use crate::module::monitoring::DataPoint;
+use crate::module::platform_info::{Flavor, PlatformInfo};
+use crate::module::{Metadata, ModuleSpecification};
+use crate::utils::{ShellCommand, VersionNumber};
+
+pub const SCRIPT_EXTENSION: &str = "rhai";
+
+/// Limits keep a faulty script from hanging or exhausting memory of the whole application.
+const MAX_OPERATIONS: u64 = 10_000_000;
+const MAX_CALL_LEVELS: usize = 64;
+const MAX_EXPRESSION_DEPTH: usize = 64;
+const MAX_STRING_SIZE: usize = 10 * 1024 * 1024;
+const MAX_ARRAY_SIZE: usize = 100_000;
+const MAX_MAP_SIZE: usize = 10_000;
+
+
+/// Compiled module script and the engine used to run it.
+/// Scripts implement the same hooks as compiled modules, with module settings as the first argument.
+#[derive(Clone)]
+pub struct ScriptRuntime {
+    engine: Arc<Engine>,
+    ast: Arc<AST>,
+    pub module_id: String,
+}
+
+/// Metadata returned by the script's `metadata()`-function.
+#[derive(Clone)]
+pub struct ScriptMetadata {
+    /// "monitor" or "command".
+    pub module_type: String,
+    pub metadata: Metadata,
+    pub connector_spec: Option<ModuleSpecification>,
+}
+
+impl ScriptRuntime {
+    /// Runtime without a script. Every hook call fails.
+    pub fn empty() -> Self {
+        ScriptRuntime {
+            engine: Arc::new(Engine::new_raw()),
+            ast: Arc::new(AST::empty()),
+            module_id: String::new(),
+        }
+    }
+
+    pub fn has_function(&self, name: &str, parameter_count: usize) -> bool {
+        self.ast.iter_functions().any(|function| function.name == name && function.params.len() == parameter_count)
+    }
+
+    pub fn call(&self, name: &str, arguments: impl FuncArgs) -> Result<Dynamic, String> {
+        // Top-level statements are not evaluated on calls so scripts can't keep state between calls.
+        let options = CallFnOptions::new().eval_ast(false).rewind_scope(true);
+        self.engine.call_fn_with_options::<Dynamic>(options, &mut Scope::new(), &self.ast, name, arguments)
+                   .map_err(|error| format!("Error in module script {}, function {}: {}", self.module_id, name, error))
+    }
+
+    /// Uses `defaults` if the script doesn't implement `get_display_options`.
+    pub fn get_display_options(&self, defaults: frontend::DisplayOptions, settings: &HashMap<String, String>) -> Result<frontend::DisplayOptions, String> {
+        if !self.has_function("get_display_options", 1) {
+            return Ok(defaults);
+        }
+
+        let result = self.call("get_display_options", (settings_to_map(settings),))?;
+        let display_options = to_display_options(defaults, result)
+            .map_err(|error| format!("Error in module script {}, function get_display_options: {}", self.module_id, error))?;
+        display_options.validate()?;
+        Ok(display_options)
+    }
+
+    pub fn get_metadata(&self) -> Result<ScriptMetadata, String> {
+        let result = self.call("metadata", ())?;
+        let map = result.try_cast::<Map>().ok_or(String::from("metadata() has to return a map"))?;
+        let get_string = |key: &str, default: &str| map.get(key).map(|value| value.to_string()).unwrap_or_else(|| default.to_string());
+
+        let module_type = get_string("type", "");
+        if module_type != "monitor" && module_type != "command" {
+            return Err(String::from("Module type has to be \"monitor\" or \"command\""));
+        }
+
+        let settings = match map.get("settings") {
+            Some(settings) => settings.clone().try_cast::<Map>().ok_or(String::from("settings has to be a map"))?
+                                      .into_iter()
+                                      .map(|(key, description)| (key.to_string(), description.to_string()))
+                                      .collect(),
+            None => HashMap::new(),
+        };
+
+        let cache_sensitivity = match map.get(CacheSensitivity::SETTING).map(|value| value.as_bool()) {
+            Some(Ok(true)) => CacheSensitivity::Sensitive,
+            Some(Ok(false)) | None => CacheSensitivity::Normal,
+            Some(Err(_)) => return Err(String::from("sensitive has to be a boolean")),
+        };
+
+        let connector_spec = match get_string("connector", "ssh").as_str() {
+            "" => None,
+            connector_id => Some(ModuleSpecification::new(connector_id, "0.0.1")),
+        };
+
+        Ok(ScriptMetadata {
+            metadata: Metadata {
+                module_spec: ModuleSpecification::new_with_type(&self.module_id, &get_string("version", "0.0.1"), &module_type),
+                description: get_string("description", ""),
+                settings: settings,
+                parent_module: None,
+                is_stateless: true,
+                cache_scope: crate::cache::CacheScope::Host,
+                cache_sensitivity: cache_sensitivity,
+            },
+            module_type: module_type,
+            connector_spec: connector_spec,
+        })
+    }
+}
+
+/// Compiles all scripts in the directory. Module ID is the file name without extension.
+/// Scripts with errors are skipped so that a single broken script doesn't prevent startup.
+pub fn load_scripts(modules_dir: &Path) -> Vec<ScriptRuntime> {
+    let entries = match fs::read_dir(modules_dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let engine = Arc::new(new_engine());
+    let mut runtimes = Vec::new();
+
+    for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+        if path.extension().map(|extension| extension != SCRIPT_EXTENSION).unwrap_or(true) {
+            continue;
+        }
+
+        let module_id = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        if module_id.is_empty() || module_id.chars().any(char::is_whitespace) {
+            log::error!("Skipping module script {}: file name can't contain whitespace", path.display());
+            continue;
+        }
+
+        match engine.compile_file(path.clone()) {
+            Ok(ast) => runtimes.push(ScriptRuntime {
+                engine: engine.clone(),
+                ast: Arc::new(ast),
+                module_id: module_id,
+            }),
+            Err(error) => log::error!("Skipping module script {}: {}", path.display(), error),
+        }
+    }
+
+    runtimes.sort_by(|a, b| a.module_id.cmp(&b.module_id));
+    runtimes
+}
+
+pub fn settings_to_map(settings: &HashMap<String, String>) -> Map {
+    settings.iter().map(|(key, value)| (key.into(), Dynamic::from(value.clone()))).collect()
+}
+
+/// Script returns only the options it wants to change from the defaults. Names match DisplayOptions-fields and
+/// enum values are given as variant names, e.g. `#{ display_style: "CriticalityLevel", category: "redis" }`.
+fn to_display_options(defaults: frontend::DisplayOptions, value: Dynamic) -> Result<frontend::DisplayOptions, String> {
+    let overrides = value.try_cast::<Map>().ok_or(String::from("get_display_options() has to return a map"))?;
+    let mut options = rhai::serde::to_dynamic(defaults).map_err(|error| error.to_string())?
+                                                          .try_cast::<Map>().unwrap_or_default();
+
+    for (key, value) in overrides.into_iter() {
+        if !options.contains_key(&key) {
+            return Err(format!("Unknown display option: {}", key));
+        }
+        options.insert(key, value);
+    }
+
+    rhai::serde::from_dynamic::<frontend::DisplayOptions>(&Dynamic::from(options))
+        .map_err(|error| format!("Invalid display options: {}", error))
+}
+
+/// Accepts a string, a ShellCommand or an array of them.
+pub fn to_messages(value: Dynamic) -> Result<Vec<String>, String> {
+    let values = match value.is_array() {
+        true => value.cast::<Array>(),
+        false => vec![value],
+    };
+
+    values.into_iter().map(|value| {
+        if value.is::<ShellCommand>() {
+            Ok(value.cast::<ShellCommand>().to_string())
+        }
+        else {
+            value.into_string().map_err(|type_name| format!("Expected a string or ShellCommand, got {}", type_name))
+        }
+    }).collect()
+}
+
+pub fn responses_to_array(responses: Vec<ResponseMessage>) -> Array {
+    responses.into_iter().map(Dynamic::from).collect()
+}
+
+fn to_string_array(values: &[String]) -> Array {
+    values.iter().cloned().map(Dynamic::from).collect()
+}
+
+fn from_string_array(values: Array) -> Vec<String> {
+    values.into_iter().map(|value| value.to_string()).collect()
+}
+
+/// Scripts can't access files, network or other processes. Only the registered APIs are available.
+fn new_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS)
+          .set_max_call_levels(MAX_CALL_LEVELS)
+          .set_max_expr_depths(MAX_EXPRESSION_DEPTH, MAX_EXPRESSION_DEPTH)
+          .set_max_string_size(MAX_STRING_SIZE)
+          .set_max_array_size(MAX_ARRAY_SIZE)
+          .set_max_map_size(MAX_MAP_SIZE)
+          .set_module_resolver(rhai::module_resolvers::DummyModuleResolver::new())
+          .disable_symbol("eval");
+
+    engine.on_print(|text| log::info!("Module script: {}", text));
+    engine.on_debug(|text, source, position| log::debug!("Module script {}{}: {}", source.unwrap_or_default(), position, text));
+
+    register_data_point(&mut engine);
+    register_shell_command(&mut engine);
+    register_host(&mut engine);
+
+    engine.register_fn("regex_captures", |pattern: &str, text: &str| -> Result<Array, Box<rhai::EvalAltResult>> {
+        let regex = Regex::new(pattern).map_err(|error| error.to_string())?;
+        Ok(regex.captures(text)
+                .map(|captures| captures.iter().map(|capture| Dynamic::from(capture.map(|capture| capture.as_str().to_string()).unwrap_or_default())).collect())
+                .unwrap_or_default())
+    });
+
+    engine
+}
+
+fn register_data_point(engine: &mut Engine) {
+    engine.register_type_with_name::<DataPoint>("DataPoint")
+          .register_fn("data_point", |value: &str| DataPoint::new(value.to_string()))
+          .register_fn("data_point", |value: &str, criticality: &str| -> Result<DataPoint, Box<rhai::EvalAltResult>> {
+              Ok(DataPoint::value_with_level(value.to_string(), parse_criticality(criticality)?))
+          })
+          .register_fn("labeled_value", |label: &str, value: &str| DataPoint::labeled_value(label, value))
+          .register_fn("labeled_value", |label: &str, value: &str, criticality: &str| -> Result<DataPoint, Box<rhai::EvalAltResult>> {
+              Ok(DataPoint::labeled_value_with_level(label.to_string(), value.to_string(), parse_criticality(criticality)?))
+          })
+          .register_fn("no_data", DataPoint::no_data)
+          .register_fn("push", |data_point: &mut DataPoint, child: DataPoint| data_point.multivalue.push(child))
+          .register_fn("to_string", |data_point: &mut DataPoint| data_point.to_string())
+          .register_get_set("value", |data_point: &mut DataPoint| data_point.value.clone(),
+                                     |data_point: &mut DataPoint, value: String| data_point.value = value)
+          .register_get_set("label", |data_point: &mut DataPoint| data_point.label.clone(),
+                                     |data_point: &mut DataPoint, label: String| data_point.label = label)
+          .register_get_set("description", |data_point: &mut DataPoint| data_point.description.clone(),
+                                           |data_point: &mut DataPoint, description: String| data_point.description = description)
+          .register_get_set("tags", |data_point: &mut DataPoint| to_string_array(&data_point.tags),
+                                    |data_point: &mut DataPoint, tags: Array| data_point.tags = from_string_array(tags))
+          .register_get_set("command_params", |data_point: &mut DataPoint| to_string_array(&data_point.command_params),
+                                              |data_point: &mut DataPoint, params: Array| data_point.command_params = from_string_array(params))
+          .register_get_set("multivalue", |data_point: &mut DataPoint| data_point.multivalue.iter().cloned().map(Dynamic::from).collect::<Array>(),
+                                          |data_point: &mut DataPoint, values: Array| {
+                                              data_point.multivalue = values.into_iter().filter_map(|value| value.try_cast::<DataPoint>()).collect()
+                                          })
+          .register_get_set("criticality", |data_point: &mut DataPoint| data_point.criticality.to_string(),
+                                           |data_point: &mut DataPoint, criticality: String| -> Result<(), Box<rhai::EvalAltResult>> {
+                                               data_point.criticality = parse_criticality(&criticality)?;
+                                               Ok(())
+                                           });
+
+    engine.register_type_with_name::<ResponseMessage>("ResponseMessage")
+          .register_get("message", |response: &mut ResponseMessage| response.message.clone())
+          .register_get("return_code", |response: &mut ResponseMessage| response.return_code as i64)
+          .register_get("is_error", |response: &mut ResponseMessage| response.is_error())
+          .register_get("is_from_cache", |response: &mut ResponseMessage| response.is_from_cache);
+}
+
+fn register_shell_command(engine: &mut Engine) {
+    engine.register_type_with_name::<ShellCommand>("ShellCommand")
+          .register_fn("shell_command", ShellCommand::new)
+          .register_fn("shell_command", |arguments: Array| ShellCommand::new_from(from_string_array(arguments)))
+          .register_fn("argument", |command: &mut ShellCommand, argument: &str| command.argument(argument).clone())
+          .register_fn("arguments", |command: &mut ShellCommand, arguments: Array| command.arguments(from_string_array(arguments)).clone())
+          .register_fn("pipe_to", |command: &mut ShellCommand, arguments: Array| command.pipe_to(from_string_array(arguments)).clone())
+          .register_fn("to_string", |command: &mut ShellCommand| command.to_string())
+          .register_fn("quote", |argument: &str| ShellCommand::quote(argument))
+          .register_get_set("use_sudo", |command: &mut ShellCommand| command.use_sudo,
+                                        |command: &mut ShellCommand, use_sudo: bool| command.use_sudo = use_sudo);
+}
+
+fn register_host(engine: &mut Engine) {
+    engine.register_type_with_name::<Host>("Host")
+          .register_get("name", |host: &mut Host| host.name.clone())
+          .register_get("fqdn", |host: &mut Host| host.fqdn.clone())
+          .register_get("ip_address", |host: &mut Host| host.ip_address.to_string())
+          .register_get("use_sudo", |host: &mut Host| host.settings.contains(&HostSetting::UseSudo))
+          .register_get("platform", |host: &mut Host| host.platform.clone());
+
+    engine.register_type_with_name::<PlatformInfo>("PlatformInfo")
+          .register_get("os", |platform: &mut PlatformInfo| platform.os.to_string())
+          .register_get("os_version", |platform: &mut PlatformInfo| platform.os_version.to_string())
+          .register_get("os_flavor", |platform: &mut PlatformInfo| platform.os_flavor.to_string())
+          .register_get("architecture", |platform: &mut PlatformInfo| platform.architecture.to_string())
+          .register_fn("version_is_same_or_greater_than", |platform: &mut PlatformInfo, flavor: &str, version: &str| -> Result<bool, Box<rhai::EvalAltResult>> {
+              let (flavor, version) = parse_flavor_and_version(flavor, version)?;
+              Ok(platform.os_flavor == flavor && platform.os_version >= version)
+          })
+          .register_fn("version_is_same_as", |platform: &mut PlatformInfo, flavor: &str, version: &str| -> Result<bool, Box<rhai::EvalAltResult>> {
+              let (flavor, version) = parse_flavor_and_version(flavor, version)?;
+              Ok(platform.os_flavor == flavor && platform.os_version == version)
+          })
+          .register_fn("version_is_older_than", |platform: &mut PlatformInfo, flavor: &str, version: &str| -> Result<bool, Box<rhai::EvalAltResult>> {
+              let (flavor, version) = parse_flavor_and_version(flavor, version)?;
+              Ok(platform.os_flavor == flavor && platform.os_version < version)
+          });
+}
+
+fn parse_criticality(criticality: &str) -> Result<Criticality, Box<rhai::EvalAltResult>> {
+    Criticality::from_str(criticality).map_err(|_| format!("Invalid criticality: {}", criticality).into())
+}
+
+/// PlatformInfo's own methods panic on invalid versions, so values from scripts are validated first.
+fn parse_flavor_and_version(flavor: &str, version: &str) -> Result<(Flavor, VersionNumber), Box<rhai::EvalAltResult>> {
+    let flavor = Flavor::from_str(flavor).map_err(|_| format!("Invalid flavor: {}", flavor))?;
+    let version = VersionNumber::from_str(version).map_err(|_| format!("Invalid version: {}", version))?;
+    Ok((flavor, version))
+}
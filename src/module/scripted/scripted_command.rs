@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use rhai::Map;
+
+use crate::frontend;
+use crate::host::*;
+use crate::module::connection::ResponseMessage;
+use crate::module::*;
+use crate::module::command::*;
+use crate::cache::CacheSensitivity;
+use super::runtime::{self, ScriptRuntime, ScriptMetadata};
+
+
+/// Command module implemented with a script. Hooks: `get_display_options(settings)`,
+/// `get_connector_messages(settings, host, parameters)` and `process_responses(settings, host, responses)`.
+/// `process_responses` can return a string or a map with `message` and `criticality`. If it's missing, output is shown as is.
+#[derive(Clone)]
+pub struct ScriptedCommand {
+    runtime: ScriptRuntime,
+    metadata: Metadata,
+    connector_spec: Option<ModuleSpecification>,
+    settings: HashMap<String, String>,
+    display_options: frontend::DisplayOptions,
+}
+
+impl ScriptedCommand {
+    /// If the script fails to provide display options for these settings, the error is logged and defaults are used.
+    pub fn new_scripted(runtime: &ScriptRuntime, script_metadata: &ScriptMetadata, settings: &HashMap<String, String>) -> Self {
+        let display_options = runtime.get_display_options(Self::default_display_options(runtime), settings).unwrap_or_else(|error| {
+            log::error!("{}", error);
+            Self::default_display_options(runtime)
+        });
+
+        let mut metadata = script_metadata.metadata.clone();
+        metadata.cache_sensitivity = CacheSensitivity::from_settings(metadata.cache_sensitivity, settings);
+
+        ScriptedCommand {
+            runtime: runtime.clone(),
+            metadata: metadata,
+            connector_spec: script_metadata.connector_spec.clone(),
+            settings: settings.clone(),
+            display_options: display_options,
+        }
+    }
+
+    pub fn default_display_options(runtime: &ScriptRuntime) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            display_style: frontend::DisplayStyle::Text,
+            display_text: runtime.module_id.clone(),
+            category: String::from("custom"),
+            ..Default::default()
+        }
+    }
+}
+
+impl Module for ScriptedCommand {
+    /// Scripted modules are created through ModuleFactory. This creates an instance without a script.
+    fn new(settings: &HashMap<String, String>) -> Self {
+        ScriptedCommand {
+            runtime: ScriptRuntime::empty(),
+            metadata: Self::get_metadata(),
+            connector_spec: None,
+            settings: settings.clone(),
+            display_options: Default::default(),
+        }
+    }
+}
+
+impl MetadataSupport for ScriptedCommand {
+    fn get_metadata() -> Metadata {
+        Metadata {
+            module_spec: ModuleSpecification::new_with_type("scripted", "0.0.1", "command"),
+            description: String::new(),
+            settings: HashMap::new(),
+            parent_module: None,
+            is_stateless: true,
+            cache_scope: crate::cache::CacheScope::Host,
+            cache_sensitivity: crate::cache::CacheSensitivity::Normal,
+        }
+    }
+
+    fn get_metadata_self(&self) -> Metadata {
+        self.metadata.clone()
+    }
+
+    fn get_module_spec(&self) -> ModuleSpecification {
+        self.metadata.module_spec.clone()
+    }
+}
+
+impl BoxCloneableCommand for ScriptedCommand {
+    fn box_clone(&self) -> Command {
+        Box::new(self.clone())
+    }
+}
+
+impl CommandModule for ScriptedCommand {
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        self.connector_spec.clone()
+    }
+
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        self.display_options.clone()
+    }
+
+    fn get_connector_messages(&self, host: Host, parameters: Vec<String>) -> Result<Vec<String>, String> {
+        let parameters = parameters.into_iter().map(rhai::Dynamic::from).collect::<rhai::Array>();
+        let result = self.runtime.call("get_connector_messages", (runtime::settings_to_map(&self.settings), host, parameters))?;
+        runtime::to_messages(result)
+    }
+
+    fn process_responses(&self, host: Host, responses: Vec<ResponseMessage>) -> Result<CommandResult, String> {
+        if !self.runtime.has_function("process_responses", 3) {
+            return Err(String::new());
+        }
+
+        let result = self.runtime.call("process_responses", (runtime::settings_to_map(&self.settings), host, runtime::responses_to_array(responses)))?;
+
+        if result.is_string() {
+            return Ok(CommandResult::new_info(result.to_string()));
+        }
+
+        let map = result.try_cast::<Map>().ok_or(String::from("process_responses() has to return a string or a map"))?;
+        let message = map.get("message").map(|message| message.to_string()).unwrap_or_default();
+
+        match map.get("criticality").map(|criticality| criticality.to_string()).unwrap_or_default().as_str() {
+            "" | "Normal" | "Info" => Ok(CommandResult::new_info(message)),
+            "Warning" => Ok(CommandResult::new_warning(message)),
+            "Error" => Ok(CommandResult::new_error(message)),
+            "Critical" => Ok(CommandResult::new_critical_error(message)),
+            other => Err(format!("Invalid criticality: {}", other)),
+        }
+    }
+}
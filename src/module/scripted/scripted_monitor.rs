@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use crate::module::connection::ResponseMessage;
+use crate::{
+    Host,
+    frontend,
+};
+use crate::module::*;
+use crate::module::monitoring::*;
+use crate::cache::CacheSensitivity;
+use super::runtime::{self, ScriptRuntime, ScriptMetadata};
+
+
+/// Monitoring module implemented with a script. Hooks are optional except for `process_responses`:
+/// `get_display_options(settings)`, `get_connector_messages(settings, host, parent)` and
+/// `process_responses(settings, host, responses, parent)`.
+#[derive(Clone)]
+pub struct ScriptedMonitor {
+    runtime: ScriptRuntime,
+    metadata: Metadata,
+    connector_spec: Option<ModuleSpecification>,
+    settings: HashMap<String, String>,
+    display_options: frontend::DisplayOptions,
+}
+
+impl ScriptedMonitor {
+    /// If the script fails to provide display options for these settings, the error is logged and defaults are used.
+    pub fn new_scripted(runtime: &ScriptRuntime, script_metadata: &ScriptMetadata, settings: &HashMap<String, String>) -> Self {
+        let display_options = runtime.get_display_options(Self::default_display_options(runtime), settings).unwrap_or_else(|error| {
+            log::error!("{}", error);
+            Self::default_display_options(runtime)
+        });
+
+        let mut metadata = script_metadata.metadata.clone();
+        metadata.cache_sensitivity = CacheSensitivity::from_settings(metadata.cache_sensitivity, settings);
+
+        ScriptedMonitor {
+            runtime: runtime.clone(),
+            metadata: metadata,
+            connector_spec: script_metadata.connector_spec.clone(),
+            settings: settings.clone(),
+            display_options: display_options,
+        }
+    }
+
+    pub fn default_display_options(runtime: &ScriptRuntime) -> frontend::DisplayOptions {
+        frontend::DisplayOptions {
+            display_style: frontend::DisplayStyle::Text,
+            display_text: runtime.module_id.clone(),
+            category: String::from("custom"),
+            ..Default::default()
+        }
+    }
+}
+
+impl Module for ScriptedMonitor {
+    /// Scripted modules are created through ModuleFactory. This creates an instance without a script.
+    fn new(settings: &HashMap<String, String>) -> Self {
+        ScriptedMonitor {
+            runtime: ScriptRuntime::empty(),
+            metadata: Self::get_metadata(),
+            connector_spec: None,
+            settings: settings.clone(),
+            display_options: Default::default(),
+        }
+    }
+}
+
+impl MetadataSupport for ScriptedMonitor {
+    fn get_metadata() -> Metadata {
+        Metadata {
+            module_spec: ModuleSpecification::new_with_type("scripted", "0.0.1", "monitor"),
+            description: String::new(),
+            settings: HashMap::new(),
+            parent_module: None,
+            is_stateless: true,
+            cache_scope: crate::cache::CacheScope::Host,
+            cache_sensitivity: crate::cache::CacheSensitivity::Normal,
+        }
+    }
+
+    fn get_metadata_self(&self) -> Metadata {
+        self.metadata.clone()
+    }
+
+    fn get_module_spec(&self) -> ModuleSpecification {
+        self.metadata.module_spec.clone()
+    }
+}
+
+impl BoxCloneableMonitor for ScriptedMonitor {
+    fn box_clone(&self) -> Monitor {
+        Box::new(self.clone())
+    }
+}
+
+impl MonitoringModule for ScriptedMonitor {
+    fn get_connector_spec(&self) -> Option<ModuleSpecification> {
+        self.connector_spec.clone()
+    }
+
+    fn get_display_options(&self) -> frontend::DisplayOptions {
+        self.display_options.clone()
+    }
+
+    fn get_connector_messages(&self, host: Host, parent_result: DataPoint) -> Result<Vec<String>, String> {
+        if !self.runtime.has_function("get_connector_messages", 3) {
+            return Err(String::new());
+        }
+
+        let result = self.runtime.call("get_connector_messages", (runtime::settings_to_map(&self.settings), host, parent_result))?;
+        runtime::to_messages(result)
+    }
+
+    fn process_response(&self, host: Host, response: ResponseMessage, parent_result: DataPoint) -> Result<DataPoint, String> {
+        self.process_responses(host, vec![response], parent_result)
+    }
+
+    fn process_responses(&self, host: Host, responses: Vec<ResponseMessage>, parent_result: DataPoint) -> Result<DataPoint, String> {
+        let result = self.runtime.call(
+            "process_responses",
+            (runtime::settings_to_map(&self.settings), host, runtime::responses_to_array(responses.clone()), parent_result)
+        )?;
+
+        let mut data_point = match result.is::<DataPoint>() {
+            true => result.cast::<DataPoint>(),
+            false => DataPoint::new(result.into_string().map_err(|type_name| format!("process_responses() returned {}, expected DataPoint or string", type_name))?),
+        };
+        data_point.set_cache_state(&responses);
+        Ok(data_point)
+    }
+}
+
@@ -5,11 +5,12 @@ use std::sync::Arc;
 use std::sync::mpsc::{self, Sender};
 
 use crate::Host;
-use crate::configuration::{CacheSettings, Hosts};
+use crate::cache::CacheSensitivity;
+use crate::configuration::{CacheSettings, HostSettings, Hosts};
 use crate::enums::Criticality;
 use crate::module::connection::ResponseMessage;
-use crate::module::{monitoring::*, ModuleSpecification};
-use crate::module::ModuleFactory;
+use crate::module::monitoring::*;
+use crate::module::{ModuleFactory, ModuleSpecification};
 use crate::host_manager::{StateUpdateMessage, HostManager};
 use crate::connection_manager::{ ConnectorRequest, ResponseHandlerCallback, RequestType, CachePolicy };
 use crate::utils::ErrorMessage;
@@ -60,26 +61,41 @@ impl MonitorManager {
         self.state_update_sender = Some(state_update_sender);
 
         for (host_id, host_config) in hosts_config.hosts.iter() {
+            self.add_host_monitors(host_id, host_config);
+        }
+    }
 
-            let mut new_monitors = Vec::<Monitor>::new();
-            for (monitor_id, monitor_config) in host_config.monitors.iter() {
-                let monitor_spec = ModuleSpecification::new(monitor_id.as_str(), monitor_config.version.as_str());
-                let monitor = self.module_factory.new_monitor(&monitor_spec, &monitor_config.settings);
-                new_monitors.push(monitor);
-            }
+    /// Recreates monitors of the given hosts. Hosts missing from `hosts_config` are removed.
+    pub fn reconfigure_hosts(&mut self, hosts_config: &Hosts, host_ids: &[String]) {
+        for host_id in host_ids.iter() {
+            self.monitors.remove(host_id);
 
-            let base_modules = new_monitors.iter().filter(|monitor| monitor.get_metadata_self().parent_module.is_some())
-                                                  .map(|monitor| monitor.get_metadata_self().parent_module.unwrap())
-                                                  .collect::<Vec<_>>();
+            if let Some(host_config) = hosts_config.hosts.get(host_id) {
+                self.add_host_monitors(host_id, host_config);
+            }
+        }
+    }
 
-            for monitor in new_monitors {
-                // Base modules won't get the initial NoData data point sent.
-                let is_base = base_modules.contains(&monitor.get_module_spec());
-                self.add_monitor(host_id.clone(), monitor, !is_base);
+    fn add_host_monitors(&mut self, host_id: &String, host_config: &HostSettings) {
+        let mut new_monitors = Vec::<Monitor>::new();
+        for (monitor_id, monitor_config) in host_config.monitors.iter() {
+            let monitor_spec = monitor_config.get_module_spec(monitor_id);
+            match self.module_factory.new_monitor(&monitor_spec, &monitor_config.get_module_settings(monitor_id)) {
+                Ok(monitor) => new_monitors.push(monitor),
+                Err(error) => log::error!("[{}] Monitor {} is disabled: {}", host_id, monitor_id, error),
             }
         }
+
+        let base_modules = new_monitors.iter().filter(|monitor| monitor.get_metadata_self().parent_module.is_some())
+                                              .map(|monitor| monitor.get_metadata_self().parent_module.unwrap())
+                                              .collect::<Vec<_>>();
+
+        for monitor in new_monitors {
+            // Base modules won't get the initial NoData data point sent.
+            let is_base = base_modules.contains(&monitor.get_module_spec());
+            self.add_monitor(host_id.clone(), monitor, !is_base);
+        }
     }
-        
 
     // Adds a monitor but only if a monitor with the same ID doesn't exist.
     fn add_monitor(&mut self, host_id: String, monitor: Monitor, send_initial_value: bool) {
@@ -188,6 +204,7 @@ impl MonitorManager {
                     }
                 };
 
+                let cache_sensitivity = info_provider.get_metadata_self().cache_sensitivity;
                 self.request_sender.as_ref().unwrap().send(ConnectorRequest {
                     connector_spec: info_provider.get_connector_spec(),
                     source_id: info_provider.get_module_spec().id,
@@ -204,6 +221,8 @@ impl MonitorManager {
                         cache_policy
                     ),
                     cache_policy: cache_policy,
+                    cache_sensitivity: cache_sensitivity,
+                    ..Default::default()
                 }).unwrap_or_else(|error| {
                     log::error!("Couldn't send message to connector: {}", error);
                 });
@@ -267,6 +286,44 @@ impl MonitorManager {
         invocation_ids
     }
 
+    /// Removes cached responses of the given monitors, e.g. after a command has changed the state they report.
+    /// Messages of extension modules depend on the parent's result, so only base modules are handled.
+    /// Extension modules are refreshed along with their parent anyway.
+    pub fn evict_cached_responses(&self, host_id: &String, monitor_ids: &[String]) {
+        let host = self.host_manager.borrow().get_host(host_id);
+        let host_monitors = match self.monitors.get(host_id) {
+            Some(host_monitors) => host_monitors,
+            None => return,
+        };
+
+        let monitors = host_monitors.values().filter(|monitor| {
+            monitor_ids.contains(&monitor.get_module_spec().id) && monitor.get_metadata_self().parent_module.is_none()
+        });
+
+        for monitor in monitors {
+            let messages = match get_monitor_connector_messages(&host, monitor, &DataPoint::empty()) {
+                Ok(messages) => messages,
+                Err(error) => {
+                    log::warn!("[{}] Couldn't evict cached responses of {}: {}", host_id, monitor.get_module_spec().id, error);
+                    continue;
+                }
+            };
+
+            self.request_sender.as_ref().unwrap().send(ConnectorRequest {
+                connector_spec: monitor.get_connector_spec(),
+                source_id: monitor.get_module_spec().id,
+                host: host.clone(),
+                messages: messages,
+                request_type: RequestType::EvictCache,
+                response_handler: Box::new(|_| ()),
+                cache_policy: CachePolicy::BypassCache,
+                ..Default::default()
+            }).unwrap_or_else(|error| {
+                log::error!("Couldn't send message to connector: {}", error);
+            });
+        }
+    }
+
     fn refresh_monitors(&self, host: Host, monitors: HashMap<&String, &Monitor>, cache_policy: CachePolicy) -> Vec<u64> {
         if !host.platform.is_set() {
             log::warn!("[{}] Refreshing monitors despite missing platform info", host.name);
@@ -279,6 +336,7 @@ impl MonitorManager {
         let (extensions, bases): (Vec<&Monitor>, Vec<&Monitor>) = 
             monitors.values().partition(|monitor| monitor.get_metadata_self().parent_module.is_some());
 
+        let mut requests = Vec::new();
         for monitor in bases {
             current_invocation_id += 1;
             invocation_ids.push(current_invocation_id);
@@ -289,7 +347,7 @@ impl MonitorManager {
             extensions.iter().filter(|ext| ext.get_metadata_self().parent_module.unwrap() == monitor.get_module_spec())
                              .for_each(|extension| request_monitors.push(extension.box_clone()));
 
-            Self::send_connector_request(
+            let request = Self::create_connector_request(
                 host.clone(),
                 request_monitors,
                 current_invocation_id,
@@ -297,32 +355,116 @@ impl MonitorManager {
                 self.state_update_sender.as_ref().unwrap().clone(),
                 DataPoint::empty_and_critical(), cache_policy.clone()
             );
+            requests.extend(request);
+        }
+
+        // Base monitors are independent of each other, so their commands can be sent together.
+        for request in Self::combine_requests(requests) {
+            self.request_sender.as_ref().unwrap().send(request).unwrap_or_else(|error| {
+                log::error!("Couldn't send message to connector: {}", error);
+            });
         }
 
         invocation_ids
     }
 
+    /// Combines requests that use the same connector into one, so that the connector can send the commands in one batch.
+    /// Responses are split back and passed to the original response handlers.
+    fn combine_requests(requests: Vec<ConnectorRequest>) -> Vec<ConnectorRequest> {
+        let mut combined_requests = Vec::new();
+        let mut requests_by_connector = HashMap::<ModuleSpecification, Vec<ConnectorRequest>>::new();
+
+        for request in requests {
+            match request.connector_spec.clone() {
+                Some(connector_spec) => requests_by_connector.entry(connector_spec).or_default().push(request),
+                None => combined_requests.push(request),
+            }
+        }
+
+        for (connector_spec, mut requests) in requests_by_connector {
+            if requests.len() == 1 {
+                combined_requests.push(requests.remove(0));
+                continue;
+            }
+
+            let host = requests[0].host.clone();
+            let cache_policy = requests[0].cache_policy;
+            // Sensitive if any of the combined requests is.
+            let cache_sensitivity = requests.iter().map(|request| request.cache_sensitivity).max().unwrap_or(CacheSensitivity::Normal);
+            let source_id = requests.iter().map(|request| request.source_id.clone()).collect::<Vec<_>>().join(",");
+            let message_counts = requests.iter().map(|request| request.messages.len()).collect::<Vec<_>>();
+            let message_source_ids = requests.iter().flat_map(|request| vec![request.source_id.clone(); request.messages.len()]).collect::<Vec<_>>();
+            let messages = requests.iter().flat_map(|request| request.messages.clone()).collect::<Vec<_>>();
+            let response_handlers = requests.into_iter().map(|request| request.response_handler).collect::<Vec<_>>();
+            let message_count = messages.len();
+
+            combined_requests.push(ConnectorRequest {
+                connector_spec: Some(connector_spec),
+                source_id: source_id,
+                host: host,
+                messages: messages,
+                request_type: RequestType::Command,
+                response_handler: Box::new(move |results| {
+                    // E.g. a connection error is returned as a single result. It's then passed to every handler.
+                    if results.len() != message_count {
+                        for response_handler in response_handlers {
+                            response_handler(results.clone());
+                        }
+                        return;
+                    }
+
+                    let mut results = results.into_iter();
+                    for (response_handler, count) in response_handlers.into_iter().zip(message_counts) {
+                        response_handler(results.by_ref().take(count).collect());
+                    }
+                }),
+                cache_policy: cache_policy,
+                cache_sensitivity: cache_sensitivity,
+                message_source_ids: message_source_ids,
+                ..Default::default()
+            });
+        }
+
+        combined_requests
+    }
+
     // TODO: maybe refactor so there's less parameters to pass?
     /// Send a connector request to ConnectionManager.
     fn send_connector_request(host: Host, monitors: Vec<Monitor>, invocation_id: u64,
                               request_sender: Sender<ConnectorRequest>, state_update_sender: Sender<StateUpdateMessage>,
                               parent_result: DataPoint, cache_policy: CachePolicy) {
 
+        let request = Self::create_connector_request(
+            host, monitors, invocation_id, request_sender.clone(), state_update_sender, parent_result, cache_policy
+        );
+
+        if let Some(request) = request {
+            request_sender.send(request).unwrap_or_else(|error| {
+                log::error!("Couldn't send message to connector: {}", error);
+            });
+        }
+    }
+
+    /// Returns None if the monitor fails to provide connector messages.
+    fn create_connector_request(host: Host, monitors: Vec<Monitor>, invocation_id: u64,
+                                request_sender: Sender<ConnectorRequest>, state_update_sender: Sender<StateUpdateMessage>,
+                                parent_result: DataPoint, cache_policy: CachePolicy) -> Option<ConnectorRequest> {
+
         let monitor = monitors[0].box_clone();
 
         let messages = match get_monitor_connector_messages(&host, &monitor, &parent_result) {
             Ok(messages) => messages,
             Err(error) => {
                 log::error!("Monitor \"{}\" failed: {}", monitor.get_module_spec().id, error);
-                return;
+                return None;
             }
         };
 
         let response_handler = Self::get_response_handler(
-            host.clone(), monitors, invocation_id, request_sender.clone(), state_update_sender, parent_result, cache_policy 
+            host.clone(), monitors, invocation_id, request_sender, state_update_sender, parent_result, cache_policy 
         );
 
-        request_sender.send(ConnectorRequest {
+        Some(ConnectorRequest {
             connector_spec: monitor.get_connector_spec(),
             source_id: monitor.get_module_spec().id,
             host: host.clone(),
@@ -330,9 +472,9 @@ impl MonitorManager {
             request_type: RequestType::Command,
             response_handler: response_handler,
             cache_policy: cache_policy,
-        }).unwrap_or_else(|error| {
-            log::error!("Couldn't send message to connector: {}", error);
-        });
+            cache_sensitivity: monitor.get_metadata_self().cache_sensitivity,
+            ..Default::default()
+        })
     }
 
     fn get_response_handler(host: Host, mut monitors: Vec<Monitor>, invocation_id: u64,
@@ -366,7 +508,7 @@ impl MonitorManager {
                         // Was not implemented, so try the other method.
                         let response = responses[0].clone();
                         datapoint_result = monitor.process_response(host.clone(), response.clone(), parent_datapoint.clone())
-                                                  .map(|mut data_point| { data_point.is_from_cache = response.is_from_cache; data_point });
+                                                  .map(|mut data_point| { data_point.set_cache_state(&[response]); data_point });
                     }
                     else {
                         datapoint_result = Err(error);
@@ -10,4 +10,6 @@ pub mod shell_command;
 pub use shell_command::ShellCommand;
 
 pub mod error_message;
-pub use error_message::ErrorMessage;
\ No newline at end of file
+pub use error_message::ErrorMessage;
+pub mod command_batch;
+pub use command_batch::CommandBatch;
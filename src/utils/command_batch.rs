@@ -0,0 +1,57 @@
+use crate::utils::{strip_newline, ShellCommand};
+
+
+/// Combines multiple shell commands into one script so that they can be executed with a single round trip.
+/// Each command is followed by a delimiter line with its exit code, which is used to split the output back.
+pub struct CommandBatch {
+    commands: Vec<String>,
+    delimiter: String,
+}
+
+impl CommandBatch {
+    pub fn new(commands: Vec<String>) -> Self {
+        CommandBatch {
+            commands: commands,
+            // Random part makes it practically impossible for command output to contain the delimiter.
+            delimiter: format!("__lightkeeper_batch_{:016x}__", rand::random::<u64>()),
+        }
+    }
+
+    /// Commands run in their own subshells, so e.g. `exit` or `cd` in one command doesn't affect the others.
+    /// Script is run with `sh` since the login shell isn't necessarily POSIX-compatible.
+    pub fn to_script(&self) -> String {
+        let script = self.commands.iter()
+                                  .map(|command| format!("(\n{}\n)\nprintf '\\n%s %s\\n' {} \"$?\"", command, self.delimiter))
+                                  .collect::<Vec<_>>()
+                                  .join("\n");
+
+        format!("sh -c {}", ShellCommand::quote(&script))
+    }
+
+    /// Returns the output and exit code of each command in the same order as the commands.
+    /// Output is None for commands that didn't finish, e.g. if the batch was interrupted.
+    pub fn split_output(&self, output: &str) -> Vec<Option<(String, i32)>> {
+        let marker = format!("\n{} ", self.delimiter);
+        let mut results = Vec::new();
+        let mut remaining = output;
+
+        for _ in self.commands.iter() {
+            let result = remaining.find(&marker).and_then(|marker_index| {
+                let command_output = &remaining[..marker_index];
+                let after_marker = &remaining[marker_index + marker.len()..];
+                let (exit_code, rest) = after_marker.split_once('\n').unwrap_or((after_marker, ""));
+                let exit_code = exit_code.trim().parse::<i32>().ok()?;
+
+                remaining = rest;
+                Some((strip_newline(&command_output), exit_code))
+            });
+
+            if result.is_none() {
+                remaining = "";
+            }
+            results.push(result);
+        }
+
+        results
+    }
+}
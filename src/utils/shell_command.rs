@@ -3,6 +3,7 @@ use std::process;
 
 
 /// For building command line commands correctly.
+#[derive(Clone)]
 pub struct ShellCommand {
     arguments: VecDeque<String>,
     piped_to: VecDeque<Vec<String>>,
@@ -62,42 +63,38 @@ impl ShellCommand {
         self.arguments.iter().cloned().collect::<_>()
     }
 
+    /// Quotes a single argument for a POSIX shell. Arguments containing only safe characters are returned as is.
+    pub fn quote(argument: &str) -> String {
+        let is_safe = !argument.is_empty() && argument.chars().all(|char| char.is_ascii_alphanumeric() || "-_./:=@%+,".contains(char));
+        if is_safe {
+            argument.to_string()
+        }
+        else {
+            format!("'{}'", argument.replace('\'', "'\\''"))
+        }
+    }
 }
 
 impl ToString for ShellCommand {
+    /// Arguments are quoted so that the result is safe to pass to a POSIX shell.
     fn to_string(&self) -> String {
         if self.arguments.is_empty() {
             String::new()
         }
         else {
-            let mut command;
+            let mut arguments = self.arguments.iter().map(|argument| Self::quote(argument)).collect::<Vec<String>>();
             if self.use_sudo {
-                command = process::Command::new("sudo");
-                for argument in self.arguments.iter() {
-                    command.arg(argument);
-                }
+                arguments.insert(0, String::from("sudo"));
             }
-            else {
-                command = process::Command::new(&self.arguments[0]);
-                for argument in self.arguments.iter().skip(1) {
-                    command.arg(argument);
-                }
-            };
-
-            let mut command_string = format!("{:?}", command);
-
-            if !self.piped_to.is_empty() {
-                for piped_arguments in self.piped_to.iter() {
-                    let mut piped_command = process::Command::new(&piped_arguments[0]);
-                    for argument in piped_arguments.iter().skip(1) {
-                        piped_command.arg(argument);
-                    }
-
-                    command_string = format!("{} | {:?}", command_string, piped_command);
-                }
+
+            let mut command_string = arguments.join(" ");
+
+            for piped_arguments in self.piped_to.iter() {
+                let piped_command = piped_arguments.iter().map(|argument| Self::quote(argument)).collect::<Vec<String>>().join(" ");
+                command_string = format!("{} | {}", command_string, piped_command);
             }
 
             command_string
         }
     }
-}
\ No newline at end of file
+}
@@ -29,4 +29,62 @@ pub fn remove_quotes<Stringable: ToString>(input: &Stringable) -> String
          .or(input.strip_suffix('\''))
          .unwrap_or(&input)
          .to_string()
-}
\ No newline at end of file
+}
+
+/// Replaces `${name}` references with values returned by `lookup`. `$${` can be used to produce a literal `${`.
+/// References that `lookup` doesn't know and unterminated references are left as is.
+pub fn interpolate<Lookup: Fn(&str) -> Option<String>>(input: &str, lookup: Lookup) -> String
+{
+    let mut result = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(index) = rest.find('$') {
+        result.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        if rest.starts_with("$${") {
+            result.push_str("${");
+            rest = &rest[3..];
+        }
+        else if let (true, Some(end)) = (rest.starts_with("${"), rest.find('}')) {
+            match lookup(rest[2..end].trim()) {
+                Some(value) => result.push_str(&value),
+                None => result.push_str(&rest[..end + 1]),
+            }
+            rest = &rest[end + 1..];
+        }
+        else {
+            result.push('$');
+            rest = &rest[1..];
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Formats byte count similar to `df -h`, e.g. "976K", "3.2G" or "21G".
+pub fn format_bytes_human(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["K", "M", "G", "T", "P", "E"];
+
+    if bytes < 1024 {
+        return bytes.to_string();
+    }
+
+    let mut value = bytes as f64;
+    let mut unit_index = 0;
+    loop {
+        value /= 1024.0;
+        // Rounds up like df does. Values below 10 are shown with one decimal.
+        let rounded = if value < 10.0 { (value * 10.0).ceil() / 10.0 } else { value.ceil() };
+        if rounded < 1024.0 || unit_index == UNITS.len() - 1 {
+            return if rounded < 10.0 {
+                format!("{:.1}{}", rounded, UNITS[unit_index])
+            }
+            else {
+                format!("{:.0}{}", rounded, UNITS[unit_index])
+            };
+        }
+        unit_index += 1;
+    }
+}